@@ -47,7 +47,7 @@ app.post('/init-drift-bulk', async (req, res) => {
 
         console.log(`Signer: ${manager.publicKey}`)
 
-        await initializeDriftWithBulk(connection, manager, BULK_PROGRAM_ID, USDC_MINT_LOCAL, name, 5 * 60, 5 * 60, 1000 * 1_000_000, 10000, 1_000_000, 10_000, 0, 0, false); //1% fees 1% profit share
+        await initializeDriftWithBulk(connection, manager, BULK_PROGRAM_ID, USDC_MINT_LOCAL, name, 5 * 60, 5 * 60, 1000 * 1_000_000, 10000, 1_000_000, 10_000, 0, 0, false, 0); //1% fees 1% profit share
         res.status(200).send('Initialized Vault with bulk successfully');
     } catch (error) {
         console.error(error);
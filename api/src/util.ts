@@ -67,4 +67,32 @@ export function getTreasuryPDA(vaultName: string, programId: PublicKey): PublicK
         programId
     );
     return treasury;
+}
+
+/**
+ * Find the share price PDA for a given vault
+ * @param vault vault public key
+ * @param programId program ID
+ * @returns share price PDA public key
+ */
+export function getSharePricePDA(vault: PublicKey, programId: PublicKey): PublicKey {
+    const [sharePrice] = PublicKey.findProgramAddressSync(
+        [Buffer.from("share_price"), vault.toBuffer()],
+        programId
+    );
+    return sharePrice;
+}
+
+/**
+ * Find the share mint PDA for a given vault
+ * @param vault vault public key
+ * @param programId program ID
+ * @returns share mint PDA public key
+ */
+export function getShareMintPDA(vault: PublicKey, programId: PublicKey): PublicKey {
+    const [shareMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from("share_mint"), vault.toBuffer()],
+        programId
+    );
+    return shareMint;
 }
\ No newline at end of file
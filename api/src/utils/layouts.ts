@@ -1,4 +1,4 @@
-import { struct, u8, str, u64, u16, bool, u32 } from "@coral-xyz/borsh";
+import { struct, u8, str, u64, u16, bool, u32, option } from "@coral-xyz/borsh";
 
 export const baseVaultInstuctionLayout = struct([
     u8("variant"),
@@ -12,6 +12,7 @@ export const baseVaultInstuctionLayout = struct([
     u32("hurdle_rate"),
     u16("spot_market_index"),
     bool("permissioned"),
+    option(u64(), "max_deposit_per_depositor"),
 ]);
 
 export const depositInstuctionLayout = struct([
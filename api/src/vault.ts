@@ -1,5 +1,5 @@
 import { AccountMeta, ComputeBudgetProgram, Connection, Keypair, PublicKey, SystemProgram, TransactionInstruction } from "@solana/web3.js";
-import { BotStatus, getTreasuryPDA, getVaultDepositorPDA, getVaultPDA as getVaultPDA, FundStatus } from "./util";
+import { BotStatus, getShareMintPDA, getSharePricePDA, getTreasuryPDA, getVaultDepositorPDA, getVaultPDA as getVaultPDA, FundStatus } from "./util";
 import { DRIFT_PROGRAM, getDriftDepositKeys, getDriftManagerDepositKeys, getDriftManagerWithdrawKeys, getDriftUser, getDriftWithdrawKeys, getInitializeDriftKeys } from "./drift";
 import { createInitializeAccountInstruction, getOrCreateAssociatedTokenAccount, mintTo, TOKEN_PROGRAM_ID, TokenInstruction } from "@solana/spl-token"
 import { versionedTransactionSenderAndConfirmationWaiter } from "./utils/txns-sender";
@@ -64,6 +64,7 @@ export async function initializeDriftWithBulk(
     hurdle_rate: number,
     spot_market_index: number,
     permissioned: boolean,
+    max_deposit_per_depositor?: number,
 ) {
 
     // Log the input parameters
@@ -78,6 +79,8 @@ export async function initializeDriftWithBulk(
     const redeem_period_bn = new BN(redeem_period);
     const max_tokens_bn = new BN(max_tokens);
     const hurdle_rate_bn = new BN(hurdle_rate);
+    const max_deposit_per_depositor_bn =
+        max_deposit_per_depositor === undefined ? null : new BN(max_deposit_per_depositor);
 
     baseVaultInstuctionLayout.encode(
         {
@@ -92,6 +95,7 @@ export async function initializeDriftWithBulk(
             hurdle_rate: hurdle_rate_bn,
             spot_market_index,
             permissioned,
+            max_deposit_per_depositor: max_deposit_per_depositor_bn,
         },
         buffer
     );
@@ -298,6 +302,8 @@ export async function deposit(
     spotMarket: PublicKey,
     oracle: PublicKey,
     mint: PublicKey,
+    shareTokenAccount?: PublicKey,
+    remainingAccounts: AccountMeta[] = [],
 ) {
     // Log the input parameters
     console.log('Received deposit parameters:', { vault_name, amount, spotMarket: spotMarket.toString(), spotMarketVault: spotMarketVault.toString(), oracle: oracle.toString(), mint: mint.toString() });
@@ -374,6 +380,39 @@ export async function deposit(
 
     keys.push(...driftKeys);
 
+    // The share price is updated with every deposit and withdraw
+    keys.push({
+        pubkey: getSharePricePDA(vault, programId),
+        isSigner: false,
+        isWritable: true,
+    });
+
+    // Vaults with a share mint mint the deposit's shares to, or burn the requested share tokens
+    // from, the authority's share token account
+    if (shareTokenAccount) {
+        keys.push(
+            {
+                pubkey: getShareMintPDA(vault, programId),
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: shareTokenAccount,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: TOKEN_PROGRAM_ID,
+                isSigner: false,
+                isWritable: false,
+            },
+        );
+    }
+
+    // The drift users of the other sub accounts, then the oracles, spot markets and perp markets
+    // for every position of the vault
+    keys.push(...remainingAccounts);
+
     console.log(`Keys Length: ${keys.length}`);
 
     const instruction = new TransactionInstruction({
@@ -421,6 +460,8 @@ export async function requestWithdraw(
     amount: number,
     spotMarket: PublicKey,
     oracle: PublicKey,
+    shareTokenAccount?: PublicKey,
+    remainingAccounts: AccountMeta[] = [],
 ) {
     // Log the input parameters
     console.log('Received deposit parameters:', { vault_name, amount });
@@ -494,6 +535,20 @@ export async function requestWithdraw(
         },
     ];
 
+    // Vaults with a share mint take the authority's share token account, the share tokens can be
+    // requested on top of the unlocked shares
+    if (shareTokenAccount) {
+        keys.push({
+            pubkey: shareTokenAccount,
+            isSigner: false,
+            isWritable: false,
+        });
+    }
+
+    // The drift users of the other sub accounts, then the oracles, spot markets and perp markets
+    // for every position of the vault
+    keys.push(...remainingAccounts);
+
     console.log(`Keys Length: ${keys.length}`);
 
     const instruction = new TransactionInstruction({
@@ -540,6 +595,7 @@ export async function cancelWithdrawRequest(
     vault_name: string,
     spotMarket: PublicKey,
     oracle: PublicKey,
+    remainingAccounts: AccountMeta[] = [],
 ) {
     // Log the input parameters
     console.log('Received deposit parameters:', { vault_name });
@@ -609,6 +665,10 @@ export async function cancelWithdrawRequest(
         },
     ];
 
+    // The drift users of the other sub accounts, then the oracles, spot markets and perp markets
+    // for every position of the vault
+    keys.push(...remainingAccounts);
+
     console.log(`Keys Length: ${keys.length}`);
 
     const instruction = new TransactionInstruction({
@@ -657,6 +717,8 @@ export async function withdraw(
     spotMarket: PublicKey,
     oracle: PublicKey,
     mint: PublicKey,
+    shareTokenAccount?: PublicKey,
+    remainingAccounts: AccountMeta[] = [],
 ) {
 
     // Log the input parameters
@@ -727,6 +789,39 @@ export async function withdraw(
 
     keys.push(...driftKeys);
 
+    // The share price is updated with every deposit and withdraw
+    keys.push({
+        pubkey: getSharePricePDA(vault, programId),
+        isSigner: false,
+        isWritable: true,
+    });
+
+    // Vaults with a share mint mint the deposit's shares to, or burn the requested share tokens
+    // from, the authority's share token account
+    if (shareTokenAccount) {
+        keys.push(
+            {
+                pubkey: getShareMintPDA(vault, programId),
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: shareTokenAccount,
+                isSigner: false,
+                isWritable: true,
+            },
+            {
+                pubkey: TOKEN_PROGRAM_ID,
+                isSigner: false,
+                isWritable: false,
+            },
+        );
+    }
+
+    // The drift users of the other sub accounts, then the oracles, spot markets and perp markets
+    // for every position of the vault
+    keys.push(...remainingAccounts);
+
     console.log(`Keys Length: ${keys.length}`);
 
     const instruction = new TransactionInstruction({
@@ -1187,6 +1282,7 @@ export async function updateVault(
     profit_share: number,
     hurdle_rate: number,
     permissioned: boolean,
+    max_deposit_per_depositor?: number,
 ) {
 
     // Log the input parameters
@@ -1201,6 +1297,8 @@ export async function updateVault(
     const redeem_period_bn = new BN(redeem_period);
     const max_tokens_bn = new BN(max_tokens);
     const hurdle_rate_bn = new BN(hurdle_rate);
+    const max_deposit_per_depositor_bn =
+        max_deposit_per_depositor === undefined ? null : new BN(max_deposit_per_depositor);
 
     baseVaultInstuctionLayout.encode(
         {
@@ -1214,6 +1312,7 @@ export async function updateVault(
             profit_share: profit_share_bn,
             hurdle_rate: hurdle_rate_bn,
             permissioned,
+            max_deposit_per_depositor: max_deposit_per_depositor_bn,
         },
         buffer
     );
@@ -1,21 +1,45 @@
 use anchor_lang::{Owner, ZeroCopy};
 use arrayref::array_ref;
-use borsh::BorshSerialize;
 use bytemuck::from_bytes;
-use drift::math::insurance::vault_amount_to_if_shares;
+use drift::instructions::optional_accounts::{load_maps, AccountMaps};
+use drift::math::casting::Cast;
+use drift::math::insurance::{if_shares_to_vault_amount, vault_amount_to_if_shares};
+use drift::state::insurance_fund_stake::InsuranceFundStake;
+use drift::state::perp_market_map::PerpMarketMap;
+use drift::state::spot_market_map::{get_writable_spot_market_set, SpotMarketMap};
+use drift::state::user::{User, UserStats};
+use mpl_token_metadata::types::DataV2;
 use serde::Serialize;
 use serde_json::to_string;
 use solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
     log::sol_log_data,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{clock::Clock, Sysvar},
 };
 use spl_token::instruction;
+use spl_token_2022::{
+    extension::{
+        memo_transfer::MemoTransfer, transfer_fee::TransferFeeConfig, BaseStateWithExtensions,
+        StateWithExtensions,
+    },
+    instruction::transfer_checked as transfer_checked_instruction,
+    state::{Account, Mint},
+};
+use std::collections::BTreeSet;
 
-use crate::{error::wrap_drift_error, state::Vault};
+use crate::{
+    constants::{SUPPORTED_MINT_EXTENSIONS, VAULT_TRANSFER_MEMO},
+    custom_validate,
+    error::{wrap_drift_error, VaultErrorCode},
+    state::{Event, SharePrice, TradingPolicy, Vault, VaultMetadata},
+};
 
 /// Deserializes a zero-copy account from the given account data.
 ///
@@ -100,10 +124,11 @@ pub fn log_accounts(accounts: &[(&AccountInfo, &str)]) {
     }
 }
 
-/// Logs serializable data to the program log using Solana's data logging syscall
+/// Logs a record to the program log using Solana's data logging syscall, prefixed with its event
+/// discriminator so anchor event parsers can decode it
 ///
 /// # Arguments
-/// * `record` - Any type that implements the Serialize trait
+/// * `record` - Any record that implements the Event trait
 ///
 /// # Returns
 /// * `Result<(), ProgramError>` - Success or serialization error
@@ -116,8 +141,8 @@ pub fn log_accounts(accounts: &[(&AccountInfo, &str)]) {
 ///
 /// # Errors
 /// Returns error if serialization fails
-pub fn log_data<T: Serialize + BorshSerialize>(record: &T) -> Result<(), ProgramError> {
-    sol_log_data(&[&record.try_to_vec()?]);
+pub fn log_data<T: Serialize + Event>(record: &T) -> Result<(), ProgramError> {
+    sol_log_data(&[&record.data()?]);
     Ok(())
 }
 
@@ -132,32 +157,151 @@ pub fn calculate_amount_to_shares(
     Ok(shares)
 }
 
-pub fn transfer_fees<'a>(
-    fees: u64,
+/// Loads the decimals of a mint and checks it is owned by the token program passed. Token-2022
+/// mints can only use the extensions in SUPPORTED_MINT_EXTENSIONS.
+///
+/// # Arguments
+///
+/// * `token_program` - The token program, spl token or token-2022.
+/// * `mint` - The mint of the tokens transferred.
+///
+/// # Returns
+///
+/// The decimals of the mint.
+pub fn load_mint_decimals(
+    token_program: &AccountInfo,
+    mint: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    if *token_program.key != spl_token::id() && *token_program.key != spl_token_2022::id() {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if mint.owner != token_program.key {
+        msg!(
+            "Mint {} is not owned by the token program {}",
+            mint.key,
+            token_program.key
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    for extension in mint_state.get_extension_types()? {
+        custom_validate!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            VaultErrorCode::UnsupportedMint,
+            "mint extension {:?} is not supported",
+            extension
+        )?;
+    }
+
+    Ok(mint_state.base.decimals)
+}
+
+/// Unpacks a spl token or token-2022 account, without its extensions.
+pub fn unpack_token_account(token_account: &AccountInfo) -> Result<Account, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base)
+}
+
+/// Returns the balance of a spl token or token-2022 account.
+pub fn get_token_balance(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(unpack_token_account(token_account)?.amount)
+}
+
+/// Returns the fee token-2022 withholds when transferring the amount of a mint with a transfer
+/// fee, 0 for other mints.
+pub fn get_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    if *mint.owner != spl_token_2022::id() {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => Ok(transfer_fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(VaultErrorCode::MathError)?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Logs a memo ahead of a transfer into a token-2022 account that requires incoming transfer
+/// memos. The memo program has to be one of the instruction's accounts.
+fn log_memo_if_required(destination_token_account: &AccountInfo) -> ProgramResult {
+    let memo_required = {
+        let data = destination_token_account.try_borrow_data()?;
+        let account = StateWithExtensions::<Account>::unpack(&data)?;
+
+        account
+            .get_extension::<MemoTransfer>()
+            .map(|memo_transfer| bool::from(memo_transfer.require_incoming_transfer_memos))
+            .unwrap_or(false)
+    };
+
+    if memo_required {
+        invoke(
+            &spl_memo::build_memo(VAULT_TRANSFER_MEMO.as_bytes(), &[]),
+            &[],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Transfers tokens with transfer_checked through the token program that owns the mint.
+///
+/// # Returns
+///
+/// The amount the destination received, less than the amount sent for mints with a transfer fee.
+fn transfer_checked<'a>(
+    amount: u64,
     token_program: &AccountInfo<'a>,
-    user_token_account: &AccountInfo<'a>,
-    treasury_token_account: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
-) -> ProgramResult {
-    msg!("Depositing Fees to Treasury Pda...");
-    invoke(
-        &instruction::transfer(
-            &token_program.key,
-            &user_token_account.key,
-            &treasury_token_account.key,
-            &authority.key,
-            &[authority.key],
-            fees,
+    signers_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let decimals = load_mint_decimals(token_program, mint)?;
+
+    log_memo_if_required(destination_token_account)?;
+
+    let balance_before = get_token_balance(destination_token_account)?;
+
+    invoke_signed(
+        &transfer_checked_instruction(
+            token_program.key,
+            source_token_account.key,
+            mint.key,
+            destination_token_account.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
         )?,
         &[
+            source_token_account.clone(),
             mint.clone(),
-            user_token_account.clone(),
-            treasury_token_account.clone(),
+            destination_token_account.clone(),
             authority.clone(),
             token_program.clone(),
         ],
-    )
+        signers_seeds,
+    )?;
+
+    let received = get_token_balance(destination_token_account)?.saturating_sub(balance_before);
+
+    if received != amount {
+        msg!("Received {} of {} after transfer fee", received, amount);
+    }
+
+    Ok(received)
 }
 
 pub fn transfer_fees_from_vault<'a>(
@@ -168,24 +312,15 @@ pub fn transfer_fees_from_vault<'a>(
     treasury_token_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
-) -> ProgramResult {
+) -> Result<u64, ProgramError> {
     msg!("Depositing Fees to Treasury Pda...");
-    invoke_signed(
-        &instruction::transfer(
-            &token_program.key,
-            &user_token_account.key,
-            &treasury_token_account.key,
-            &authority.key,
-            &[authority.key],
-            fees,
-        )?,
-        &[
-            mint.clone(),
-            user_token_account.clone(),
-            treasury_token_account.clone(),
-            authority.clone(),
-            token_program.clone(),
-        ],
+    transfer_checked(
+        fees,
+        token_program,
+        user_token_account,
+        treasury_token_account,
+        authority,
+        mint,
         &[&[
             b"vault",
             bytes32_to_string(vault.name).as_ref(),
@@ -201,24 +336,16 @@ pub fn transfer_to_vault<'a>(
     vault_token_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
-) -> ProgramResult {
+) -> Result<u64, ProgramError> {
     msg!("Transfering to Vault Pda...");
-    invoke(
-        &instruction::transfer(
-            &token_program.key,
-            &user_token_account.key,
-            &vault_token_account.key,
-            &authority.key,
-            &[authority.key],
-            amount,
-        )?,
-        &[
-            mint.clone(),
-            user_token_account.clone(),
-            vault_token_account.clone(),
-            authority.clone(),
-            token_program.clone(),
-        ],
+    transfer_checked(
+        amount,
+        token_program,
+        user_token_account,
+        vault_token_account,
+        authority,
+        mint,
+        &[],
     )
 }
 
@@ -230,20 +357,72 @@ pub fn transfer_to_user_from_vault<'a>(
     source_token_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     mint: &AccountInfo<'a>,
-) -> ProgramResult {
+) -> Result<u64, ProgramError> {
+    msg!("Transfering to User....");
+    transfer_checked(
+        amount,
+        token_program,
+        source_token_account,
+        destination_token_account,
+        authority,
+        mint,
+        &[&[
+            b"vault",
+            bytes32_to_string(vault.name).as_ref(),
+            &[vault.bump],
+        ]],
+    )
+}
+
+pub fn transfer_to_user_from_treasury<'a>(
+    amount: u64,
+    token_program: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    signature_seeds: [&[u8]; 3],
+) -> Result<u64, ProgramError> {
     msg!("Transfering to User....");
+
+    transfer_checked(
+        amount,
+        token_program,
+        source_token_account,
+        destination_token_account,
+        authority,
+        mint,
+        &[&signature_seeds],
+    )
+}
+
+pub fn mint_shares_to_user<'a>(
+    vault: &Vault,
+    amount: u64,
+    token_program: &AccountInfo<'a>,
+    share_mint: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+) -> ProgramResult {
+    msg!("Minting share tokens to User...");
+
+    // The vault signs the mint, so it must go to the token program of the share mint
+    if *token_program.key != spl_token::id() {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
     invoke_signed(
-        &instruction::transfer(
+        &instruction::mint_to(
             &token_program.key,
-            &source_token_account.key,
+            &share_mint.key,
             &destination_token_account.key,
             &authority.key,
             &[authority.key],
             amount,
         )?,
         &[
-            mint.clone(),
-            source_token_account.clone(),
+            share_mint.clone(),
             destination_token_account.clone(),
             authority.clone(),
             token_program.clone(),
@@ -256,33 +435,797 @@ pub fn transfer_to_user_from_vault<'a>(
     )
 }
 
-pub fn transfer_to_user_from_treasury<'a>(
+pub fn burn_shares_from_user<'a>(
     amount: u64,
     token_program: &AccountInfo<'a>,
-    destination_token_account: &AccountInfo<'a>,
+    share_mint: &AccountInfo<'a>,
     source_token_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
-    mint: &AccountInfo<'a>,
-    signature_seeds: [&[u8]; 3],
 ) -> ProgramResult {
-    msg!("Transfering to User....");
+    msg!("Burning share tokens of User...");
 
-    invoke_signed(
-        &instruction::transfer(
+    // Shares are credited for the burn, so it must go to the token program of the share mint
+    if *token_program.key != spl_token::id() {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke(
+        &instruction::burn(
             &token_program.key,
             &source_token_account.key,
-            &destination_token_account.key,
+            &share_mint.key,
             &authority.key,
             &[authority.key],
             amount,
         )?,
         &[
-            mint.clone(),
             source_token_account.clone(),
-            destination_token_account.clone(),
+            share_mint.clone(),
             authority.clone(),
             token_program.clone(),
         ],
-        &[&signature_seeds],
     )
 }
+
+/// Returns the share tokens an authority holds in a token account of the vault's share mint
+pub fn get_share_token_balance(
+    vault: &Vault,
+    share_token_account: &AccountInfo,
+    authority: &Pubkey,
+) -> Result<u64, ProgramError> {
+    if *share_token_account.owner != spl_token::id() {
+        msg!("Share Token Account is not owned by the token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let token_account = unpack_token_account(share_token_account)?;
+
+    custom_validate!(
+        token_account.mint == vault.share_mint && token_account.owner == *authority,
+        VaultErrorCode::InvalidShareMint,
+        "share token account must be the authority's account of share mint {}",
+        vault.share_mint
+    )?;
+
+    Ok(token_account.amount)
+}
+
+/// Closes a program owned account, sending its rent to the destination. The account is handed
+/// back to the system program with no data so it can't be revived in the same transaction
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    msg!("Closing account {}...", account.key);
+
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(VaultErrorCode::MathError)?;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.assign(&system_program::ID);
+    account.realloc(0, false)
+}
+
+/// Returns the Metaplex symbol and uri of the vault's tokens from its vault metadata, empty if
+/// the vault has no metadata account
+pub fn get_metaplex_symbol_and_uri(
+    vault_account: &AccountInfo,
+    vault_metadata_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> (String, String) {
+    if *vault_metadata_account.owner == *program_id
+        && VaultMetadata::get_pda(vault_account.key, program_id).0 == *vault_metadata_account.key
+    {
+        let vault_metadata = VaultMetadata::get(vault_metadata_account);
+        (vault_metadata.symbol, vault_metadata.uri)
+    } else {
+        (String::new(), String::new())
+    }
+}
+
+/// The Metaplex metadata of the vault's share mint, named after the vault
+pub fn get_share_mint_metadata_data(vault: &Vault, symbol: String, uri: String) -> DataV2 {
+    DataV2 {
+        name: bytes32_to_string(vault.name),
+        symbol,
+        uri,
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    }
+}
+
+/// Invokes a drift instruction built with drift_interface, signed by the vault.
+///
+/// # Arguments
+///
+/// * `ix` - The drift instruction.
+/// * `accounts` - The instruction's accounts, in drift_interface order.
+/// * `remaining_accounts` - Extra accounts such as oracles and markets, appended after the instruction's accounts.
+/// * `drift_program` - The drift program.
+/// * `vault` - The vault signing as the authority of its drift users.
+pub fn invoke_drift_signed<'a, A: Into<[AccountInfo<'a>; N]>, const N: usize>(
+    mut ix: Instruction,
+    accounts: A,
+    remaining_accounts: &[AccountInfo<'a>],
+    drift_program: &AccountInfo<'a>,
+    vault: &Vault,
+) -> ProgramResult {
+    let mut account_infos = Vec::with_capacity(1 + N + remaining_accounts.len());
+    account_infos.push(drift_program.clone());
+    account_infos.extend(accounts.into());
+
+    for account in remaining_accounts.iter() {
+        ix.accounts.push(AccountMeta {
+            pubkey: *account.key,
+            is_signer: false,
+            is_writable: account.is_writable,
+        });
+        account_infos.push(account.clone());
+    }
+
+    invoke_signed(
+        &ix,
+        &account_infos,
+        &[&[
+            b"vault",
+            bytes32_to_string(vault.name).as_ref(),
+            &[vault.bump],
+        ]],
+    )
+}
+
+/// Maps a failed drift_interface account key check to a program error.
+///
+/// # Arguments
+///
+/// * `keys` - The account passed in and the account drift expects.
+pub fn drift_account_key_error((actual, expected): (Pubkey, Pubkey)) -> ProgramError {
+    msg!("Invalid Drift account {}, expected {}", actual, expected);
+    ProgramError::InvalidArgument
+}
+
+/// Maps a failed drift_interface privilege check to a program error.
+///
+/// # Arguments
+///
+/// * `failure` - The account without the privilege and the error for it.
+pub fn drift_account_privilege_error(
+    (account, error): (&AccountInfo, ProgramError),
+) -> ProgramError {
+    msg!("Missing privileges for Drift account {}", account.key);
+    error
+}
+
+/// Loads the drift users of all the vault's sub accounts, primary first.
+///
+/// # Arguments
+///
+/// * `vault` - The vault the users belong to.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `remaining_accounts` - Starts with the drift users of the other sub accounts, in registration order.
+///
+/// # Returns
+///
+/// The deserialized users, used to sum vault equity across sub accounts.
+pub fn load_vault_users(
+    vault: &Vault,
+    drift_user: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+) -> Result<Vec<Box<User>>, ProgramError> {
+    if vault.user != *drift_user.key {
+        msg!("Drift User is not the vault's primary sub account");
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    let secondary_sub_accounts = vault.get_secondary_sub_accounts();
+
+    if remaining_accounts.len() < secondary_sub_accounts.len() {
+        msg!(
+            "Expected drift users for {} more sub accounts",
+            secondary_sub_accounts.len()
+        );
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    let mut users = vec![deserialize_zero_copy::<User>(
+        &*drift_user.try_borrow_data()?,
+    )];
+
+    for (sub_account, account) in secondary_sub_accounts.iter().zip(remaining_accounts.iter()) {
+        if sub_account.user != *account.key {
+            msg!(
+                "Invalid Drift User for sub account {}",
+                sub_account.sub_account_id
+            );
+            return Err(VaultErrorCode::InvalidSubAccount.into());
+        }
+
+        users.push(deserialize_zero_copy::<User>(&*account.try_borrow_data()?));
+    }
+
+    Ok(users)
+}
+
+/// Checks that no sub account of the vault is being liquidated or is bankrupt on drift.
+///
+/// # Arguments
+///
+/// * `vault` - The vault to check.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `remaining_accounts` - The drift users of the other sub accounts first.
+pub fn validate_vault_not_in_liquidation(
+    vault: &Vault,
+    drift_user: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+) -> ProgramResult {
+    let users = load_vault_users(vault, drift_user, remaining_accounts)?;
+
+    for user in users.iter() {
+        if user.is_being_liquidated() || user.is_bankrupt() {
+            msg!(
+                "Drift sub account {} is being liquidated or is bankrupt",
+                user.sub_account_id
+            );
+            return Err(VaultErrorCode::VaultInLiquidation.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the total drift fuel earned by the vault's user stats.
+///
+/// # Arguments
+///
+/// * `user_stats` - The drift user stats of the vault.
+pub fn get_total_fuel(user_stats: &UserStats) -> u64 {
+    [
+        user_stats.fuel_insurance,
+        user_stats.fuel_deposits,
+        user_stats.fuel_borrows,
+        user_stats.fuel_positions,
+        user_stats.fuel_taker,
+        user_stats.fuel_maker,
+    ]
+    .iter()
+    .map(|fuel| *fuel as u64)
+    .sum()
+}
+
+/// Spreads the drift fuel the vault earned since the last update over the vault shares. Must be
+/// called before the vault shares change so the fuel goes to the shares that earned it.
+///
+/// # Arguments
+///
+/// * `vault` - The vault to update.
+/// * `drift_user_stats` - The drift user stats of the vault.
+pub fn update_vault_fuel(vault: &mut Vault, drift_user_stats: &AccountInfo) -> ProgramResult {
+    if vault.user_stats != *drift_user_stats.key {
+        msg!("Invalid Drift User Stats");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let user_stats = deserialize_zero_copy::<UserStats>(&*drift_user_stats.try_borrow_data()?);
+
+    vault.update_fuel_per_share(get_total_fuel(&user_stats))
+}
+
+/// Checks that the accounts are the drift users of all the vault's sub accounts, in registration order.
+///
+/// # Arguments
+///
+/// * `vault` - The vault the users belong to.
+/// * `user_accounts` - The drift user accounts.
+pub fn validate_sub_account_users(vault: &Vault, user_accounts: &[AccountInfo]) -> ProgramResult {
+    let sub_accounts = vault.get_sub_accounts();
+
+    if user_accounts.len() != sub_accounts.len() {
+        msg!(
+            "Expected drift users for {} sub accounts",
+            sub_accounts.len()
+        );
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    for (sub_account, account) in sub_accounts.iter().zip(user_accounts.iter()) {
+        if sub_account.user != *account.key {
+            msg!(
+                "Invalid Drift User for sub account {}",
+                sub_account.sub_account_id
+            );
+            return Err(VaultErrorCode::InvalidSubAccount.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the oracles and markets passed after the sub account users.
+///
+/// # Arguments
+///
+/// * `vault` - The vault the accounts are passed for.
+/// * `default_market_accounts` - The vault's oracle and spot market, returned when no market accounts are passed.
+/// * `remaining_accounts` - The drift users of the other sub accounts followed by the market accounts.
+///
+/// # Returns
+///
+/// The market accounts, in the oracles, spot markets, perp markets order drift expects.
+pub fn get_market_accounts<'a>(
+    vault: &Vault,
+    default_market_accounts: &'a [AccountInfo<'a>],
+    remaining_accounts: &'a [AccountInfo<'a>],
+) -> &'a [AccountInfo<'a>] {
+    let num_vault_accounts = vault
+        .get_num_remaining_vault_accounts()
+        .min(remaining_accounts.len());
+
+    match &remaining_accounts[num_vault_accounts..] {
+        [] => default_market_accounts,
+        market_accounts => market_accounts,
+    }
+}
+
+/// Calculates the vault equity across all sub accounts.
+///
+/// # Arguments
+///
+/// * `vault` - The vault to calculate the equity for.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `default_market_accounts` - The vault's oracle and spot market, used when no market accounts are passed.
+/// * `remaining_accounts` - The drift users of the other sub accounts, the insurance fund stake and
+///   vault if staked, followed by the oracles, spot markets and perp markets for every position of
+///   every sub account.
+/// * `slot` - The current slot.
+///
+/// # Returns
+///
+/// The vault equity in the vault's spot market token.
+pub fn calculate_vault_equity<'a>(
+    vault: &Vault,
+    drift_user: &'a AccountInfo<'a>,
+    default_market_accounts: &'a [AccountInfo<'a>],
+    remaining_accounts: &'a [AccountInfo<'a>],
+    slot: u64,
+) -> Result<u64, ProgramError> {
+    calculate_vault_equity_and_oracle_confidence(
+        vault,
+        drift_user,
+        default_market_accounts,
+        remaining_accounts,
+        slot,
+    )
+    .map(|(vault_equity, _)| vault_equity)
+}
+
+/// Calculates the vault equity across all sub accounts and the confidence of the oracle it is
+/// priced in the vault's spot market token with.
+///
+/// # Arguments
+///
+/// * `vault` - The vault to calculate the equity for.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `default_market_accounts` - The vault's oracle and spot market, used when no market accounts are passed.
+/// * `remaining_accounts` - The drift users of the other sub accounts, the insurance fund stake and
+///   vault if staked, followed by the oracles, spot markets and perp markets for every position of
+///   every sub account.
+/// * `slot` - The current slot.
+///
+/// # Returns
+///
+/// The vault equity in the vault's spot market token and the oracle confidence relative to the
+/// oracle price: SHARE_PRICE_PRECISION.
+pub fn calculate_vault_equity_and_oracle_confidence<'a>(
+    vault: &Vault,
+    drift_user: &'a AccountInfo<'a>,
+    default_market_accounts: &'a [AccountInfo<'a>],
+    remaining_accounts: &'a [AccountInfo<'a>],
+    slot: u64,
+) -> Result<(u64, u128), ProgramError> {
+    let users = load_vault_users(vault, drift_user, remaining_accounts)?;
+
+    let market_accounts = get_market_accounts(vault, default_market_accounts, remaining_accounts);
+
+    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_market_maps(market_accounts, &writable_spot_market_set, slot)?;
+
+    validate_market_accounts(vault, &users, &perp_market_map, &spot_market_map)?;
+
+    let vault_equity = vault
+        .calculate_total_equity(&users, &perp_market_map, &spot_market_map, &mut oracle_map)
+        .map_err(wrap_drift_error)?;
+
+    let insurance_fund_stake_value =
+        calculate_insurance_fund_stake_value(vault, remaining_accounts, &spot_market_map)?;
+
+    let oracle_confidence = vault
+        .calculate_oracle_confidence(&spot_market_map, &mut oracle_map)
+        .map_err(wrap_drift_error)?;
+
+    Ok((
+        vault_equity.saturating_add(insurance_fund_stake_value),
+        oracle_confidence,
+    ))
+}
+
+/// Updates the vault's published share price if it was initialized by RefreshSharePrice.
+///
+/// # Arguments
+///
+/// * `program_id` - The vault program.
+/// * `vault` - The vault, with its total shares after the instruction.
+/// * `share_price_account` - The share price pda of the vault.
+/// * `vault_equity` - The vault equity after the instruction.
+/// * `oracle_confidence` - The oracle confidence relative to the oracle price: SHARE_PRICE_PRECISION.
+/// * `slot` - The current slot.
+/// * `now` - The current unix timestamp.
+pub fn update_share_price(
+    program_id: &Pubkey,
+    vault: &Vault,
+    share_price_account: &AccountInfo,
+    vault_equity: u64,
+    oracle_confidence: u128,
+    slot: u64,
+    now: i64,
+) -> ProgramResult {
+    let (share_price_pda, _) = SharePrice::get_pda(&vault.pubkey, program_id);
+
+    if share_price_pda != *share_price_account.key {
+        msg!("Invalid seeds for Share Price PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if share_price_account.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut share_price = SharePrice::get(share_price_account);
+
+    share_price.update(vault, vault_equity, oracle_confidence, slot, now)?;
+
+    share_price.save(share_price_account)
+}
+
+/// Calculates the vault equity and the value of a collateral deposit in the vault's spot market token.
+///
+/// # Arguments
+///
+/// * `vault` - The vault the collateral is deposited into.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `default_market_accounts` - The vault's and the collateral's oracles and spot markets, used when no market accounts are passed.
+/// * `remaining_accounts` - The drift users of the other sub accounts, the insurance fund stake and
+///   vault if staked, followed by the oracles, spot markets and perp markets for every position of
+///   every sub account.
+/// * `market_index` - The collateral spot market index.
+/// * `amount` - The collateral amount deposited.
+/// * `slot` - The current slot.
+///
+/// # Returns
+///
+/// The vault equity and the deposit value.
+pub fn calculate_vault_equity_and_collateral_value<'a>(
+    vault: &Vault,
+    drift_user: &'a AccountInfo<'a>,
+    default_market_accounts: &'a [AccountInfo<'a>],
+    remaining_accounts: &'a [AccountInfo<'a>],
+    market_index: u16,
+    amount: u64,
+    slot: u64,
+) -> Result<(u64, u64), ProgramError> {
+    let users = load_vault_users(vault, drift_user, remaining_accounts)?;
+
+    let market_accounts = get_market_accounts(vault, default_market_accounts, remaining_accounts);
+
+    let mut writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    writable_spot_market_set.insert(market_index);
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_market_maps(market_accounts, &writable_spot_market_set, slot)?;
+
+    validate_market_accounts(vault, &users, &perp_market_map, &spot_market_map)?;
+
+    if !spot_market_map.0.contains_key(&market_index) {
+        msg!("Missing spot market {}", market_index);
+        return Err(VaultErrorCode::MissingMarketAccount.into());
+    }
+
+    let vault_equity = vault
+        .calculate_total_equity(&users, &perp_market_map, &spot_market_map, &mut oracle_map)
+        .map_err(wrap_drift_error)?;
+
+    let insurance_fund_stake_value =
+        calculate_insurance_fund_stake_value(vault, remaining_accounts, &spot_market_map)?;
+
+    let value = vault
+        .calculate_collateral_value(market_index, amount, &spot_market_map, &mut oracle_map)
+        .map_err(wrap_drift_error)?;
+
+    Ok((
+        vault_equity.saturating_add(insurance_fund_stake_value),
+        value,
+    ))
+}
+
+/// Calculates the vault equity and the bounds of a swap out of a deposit of the primary sub
+/// account.
+///
+/// # Arguments
+///
+/// * `vault` - The vault swapping.
+/// * `trading_policy` - The vault's trading policy, its max_swap_slippage bounds the amount out.
+/// * `drift_user` - The drift user of the primary sub account.
+/// * `remaining_accounts` - The drift users of the other sub accounts, the insurance fund stake and
+///   vault if staked, followed by the oracles, spot markets and perp markets for every position of
+///   every sub account and for both swap markets.
+/// * `in_token_account` - The vault token account the swapped deposit is withdrawn to.
+/// * `out_token_account` - The vault token account the swap pays out to.
+/// * `in_market_index` - The spot market swapped from.
+/// * `out_market_index` - The spot market swapped to.
+/// * `amount_in` - The requested amount to swap.
+/// * `slot` - The current slot.
+///
+/// # Returns
+///
+/// The vault equity, the amount in capped at the primary sub account's deposit, its value in the
+/// vault's spot market token and the least amount out the swap must return.
+pub fn calculate_vault_equity_and_swap_bounds<'a>(
+    vault: &Vault,
+    trading_policy: &TradingPolicy,
+    drift_user: &'a AccountInfo<'a>,
+    remaining_accounts: &'a [AccountInfo<'a>],
+    in_token_account: &AccountInfo,
+    out_token_account: &AccountInfo,
+    in_market_index: u16,
+    out_market_index: u16,
+    amount_in: u64,
+    slot: u64,
+) -> Result<(u64, u64, u64, u64), ProgramError> {
+    let users = load_vault_users(vault, drift_user, remaining_accounts)?;
+
+    let market_accounts = get_market_accounts(vault, &[], remaining_accounts);
+
+    let mut writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    writable_spot_market_set.insert(in_market_index);
+    writable_spot_market_set.insert(out_market_index);
+
+    let AccountMaps {
+        perp_market_map,
+        spot_market_map,
+        mut oracle_map,
+    } = load_market_maps(market_accounts, &writable_spot_market_set, slot)?;
+
+    validate_market_accounts(vault, &users, &perp_market_map, &spot_market_map)?;
+
+    for market_index in [in_market_index, out_market_index] {
+        if !spot_market_map.0.contains_key(&market_index) {
+            msg!("Missing spot market {}", market_index);
+            return Err(VaultErrorCode::MissingMarketAccount.into());
+        }
+    }
+
+    for (token_account, market_index) in [
+        (in_token_account, in_market_index),
+        (out_token_account, out_market_index),
+    ] {
+        let mint = spot_market_map
+            .get_ref(&market_index)
+            .map_err(wrap_drift_error)?
+            .mint;
+
+        if unpack_token_account(token_account)?.mint != mint {
+            msg!(
+                "Token account {} is not of spot market {}",
+                token_account.key,
+                market_index
+            );
+            return Err(VaultErrorCode::InvalidSwap.into());
+        }
+    }
+
+    // Only a deposit can be swapped, drift would otherwise borrow the difference
+    let deposit = match users[0].get_spot_position(in_market_index) {
+        Ok(spot_position) => {
+            let in_market = spot_market_map
+                .get_ref(&in_market_index)
+                .map_err(wrap_drift_error)?;
+
+            spot_position
+                .get_signed_token_amount(&in_market)
+                .and_then(|token_amount| token_amount.max(0).cast::<u64>())
+                .map_err(wrap_drift_error)?
+        }
+        Err(_) => 0,
+    };
+
+    let amount_in = amount_in.min(deposit);
+
+    custom_validate!(
+        amount_in > 0,
+        VaultErrorCode::InvalidSwap,
+        "no deposit in spot market {} to swap",
+        in_market_index
+    )?;
+
+    let vault_equity = vault
+        .calculate_total_equity(&users, &perp_market_map, &spot_market_map, &mut oracle_map)
+        .map_err(wrap_drift_error)?;
+
+    let insurance_fund_stake_value =
+        calculate_insurance_fund_stake_value(vault, remaining_accounts, &spot_market_map)?;
+
+    let value_in = if in_market_index == vault.spot_market_index {
+        amount_in
+    } else {
+        vault
+            .calculate_collateral_value(
+                in_market_index,
+                amount_in,
+                &spot_market_map,
+                &mut oracle_map,
+            )
+            .map_err(wrap_drift_error)?
+    };
+
+    let min_amount_out = trading_policy.calculate_min_swap_amount_out(
+        in_market_index,
+        amount_in,
+        out_market_index,
+        &spot_market_map,
+        &mut oracle_map,
+    )?;
+
+    Ok((
+        vault_equity.saturating_add(insurance_fund_stake_value),
+        amount_in,
+        value_in,
+        min_amount_out,
+    ))
+}
+
+/// Values the vault's drift insurance fund stake in the vault's spot market token.
+///
+/// # Arguments
+///
+/// * `vault` - The vault that owns the stake.
+/// * `remaining_accounts` - The drift users of the other sub accounts followed by the insurance
+///   fund stake and the insurance fund vault.
+/// * `spot_market_map` - The spot markets, must contain the vault's spot market.
+///
+/// # Returns
+///
+/// The stake value, 0 if the vault never staked.
+pub fn calculate_insurance_fund_stake_value(
+    vault: &Vault,
+    remaining_accounts: &[AccountInfo],
+    spot_market_map: &SpotMarketMap,
+) -> Result<u64, ProgramError> {
+    if !vault.has_insurance_fund_stake() {
+        return Ok(0);
+    }
+
+    let num_sub_account_users = vault.get_secondary_sub_accounts().len();
+
+    let (insurance_fund_stake_account, insurance_fund_vault) =
+        match remaining_accounts.get(num_sub_account_users..num_sub_account_users + 2) {
+            Some([stake, if_vault]) => (stake, if_vault),
+            _ => {
+                msg!("Expected the insurance fund stake and insurance fund vault");
+                return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+            }
+        };
+
+    if vault.insurance_fund_stake != *insurance_fund_stake_account.key {
+        msg!("Invalid Insurance Fund Stake");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    let spot_market = spot_market_map
+        .get_ref(&vault.spot_market_index)
+        .map_err(wrap_drift_error)?;
+
+    if spot_market.insurance_fund.vault != *insurance_fund_vault.key {
+        msg!("Invalid Insurance Fund Vault");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    let insurance_fund_stake = deserialize_zero_copy::<InsuranceFundStake>(
+        &*insurance_fund_stake_account.try_borrow_data()?,
+    );
+    let insurance_fund_vault_amount = get_token_balance(insurance_fund_vault)?;
+
+    let if_shares = insurance_fund_stake
+        .checked_if_shares(&spot_market)
+        .map_err(wrap_drift_error)?;
+
+    let value = if_shares_to_vault_amount(
+        if_shares,
+        spot_market.insurance_fund.total_shares,
+        insurance_fund_vault_amount,
+    )
+    .map_err(wrap_drift_error)?;
+
+    Ok(value)
+}
+
+/// Loads the oracle, spot market and perp market maps from the market accounts.
+///
+/// # Arguments
+///
+/// * `market_accounts` - The oracles, spot markets and perp markets, in that order.
+/// * `writable_spot_market_set` - The spot markets that must be writable.
+/// * `slot` - The current slot.
+///
+/// # Returns
+///
+/// The loaded maps.
+pub fn load_market_maps<'a>(
+    market_accounts: &'a [AccountInfo<'a>],
+    writable_spot_market_set: &BTreeSet<u16>,
+    slot: u64,
+) -> Result<AccountMaps<'a>, ProgramError> {
+    // Load maps with proper account references and types
+    match load_maps(
+        &mut market_accounts.iter().peekable(),
+        &BTreeSet::new(),
+        writable_spot_market_set,
+        slot,
+        None,
+    ) {
+        Ok(maps) => Ok(maps),
+        Err(e) => Err(ProgramError::Custom(e as u32)),
+    }
+}
+
+/// Checks that a market was passed for every position of the users, so equity can not be
+/// moved by leaving one out.
+///
+/// # Arguments
+///
+/// * `vault` - The vault the users belong to.
+/// * `users` - The drift users of all sub accounts.
+/// * `perp_market_map` - The perp markets that were passed.
+/// * `spot_market_map` - The spot markets that were passed.
+pub fn validate_market_accounts(
+    vault: &Vault,
+    users: &[Box<User>],
+    perp_market_map: &PerpMarketMap,
+    spot_market_map: &SpotMarketMap,
+) -> ProgramResult {
+    if !spot_market_map.0.contains_key(&vault.spot_market_index) {
+        msg!("Missing spot market {}", vault.spot_market_index);
+        return Err(VaultErrorCode::MissingMarketAccount.into());
+    }
+
+    for user in users.iter() {
+        for spot_position in user.spot_positions.iter() {
+            if !spot_position.is_available()
+                && !spot_market_map.0.contains_key(&spot_position.market_index)
+            {
+                msg!("Missing spot market {}", spot_position.market_index);
+                return Err(VaultErrorCode::MissingMarketAccount.into());
+            }
+        }
+
+        for perp_position in user.perp_positions.iter() {
+            if !perp_position.is_available()
+                && !perp_market_map.0.contains_key(&perp_position.market_index)
+            {
+                msg!("Missing perp market {}", perp_position.market_index);
+                return Err(VaultErrorCode::MissingMarketAccount.into());
+            }
+        }
+    }
+
+    Ok(())
+}
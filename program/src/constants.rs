@@ -1,3 +1,6 @@
+use solana_program::{pubkey, pubkey::Pubkey};
+use spl_token_2022::extension::ExtensionType;
+
 // TIME
 pub const ONE_HOUR: i64 = 60 * 60;
 pub const ONE_DAY: i64 = ONE_HOUR * 24;
@@ -7,3 +10,43 @@ pub const PERCENTAGE_PRECISION_U64: u64 = PERCENTAGE_PRECISION as u64;
 
 pub const DECIMALS_USDC: u128 = 1_000_000; // USDC uses 6 decimals
 pub const DECIMALS_SHARES: u128 = 1_000_000_000_000_000_000; // Shares use 18 decimals
+
+// Drift fuel per vault share is scaled by this so small fuel amounts over many shares aren't lost
+pub const FUEL_PER_SHARE_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+// Published share prices and their confidence are scaled by this, one share starts out worth one
+// token unit so the price starts at SHARE_PRICE_PRECISION
+pub const SHARE_PRICE_PRECISION: u128 = 1_000_000_000_000;
+
+// Snapshots kept by a vault's nav history, sized so the account can be created in one instruction
+pub const NAV_HISTORY_LENGTH: usize = 180;
+
+pub const MAX_TRADING_POLICY_MARKETS: usize = 16;
+
+// The swap program collateral swaps are routed through
+pub const JUPITER_V6_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+pub const MAX_VAULT_SUB_ACCOUNTS: usize = 8;
+
+pub const MAX_VAULT_COLLATERAL_MARKETS: usize = 8;
+
+// Vault metadata, uri and symbol limits match Metaplex token metadata
+pub const MAX_VAULT_SYMBOL_LENGTH: usize = 10;
+pub const MAX_VAULT_URI_LENGTH: usize = 200;
+pub const MAX_VAULT_DESCRIPTION_LENGTH: usize = 256;
+pub const MAX_VAULT_WEBSITE_LENGTH: usize = 64;
+pub const MAX_VAULT_TAGS: usize = 8;
+pub const MAX_VAULT_TAG_LENGTH: usize = 16;
+pub const MAX_VAULT_RISK_RATING: u8 = 5;
+
+// Token-2022 mint extensions vault tokens can use. Transfer fees are handled by crediting the
+// amount received, interest only changes the ui amount and metadata doesn't affect transfers
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+// Memo logged ahead of transfers into token accounts that require incoming transfer memos
+pub const VAULT_TRANSFER_MEMO: &str = "vault transfer";
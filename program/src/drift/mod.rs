@@ -1,252 +0,0 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{declare_id, pubkey::Pubkey};
-use std::io::Read;
-
-declare_id!("dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH");
-
-pub const INITIALIZE_USER_STATS_IX_DISCM: [u8; 8] = [254, 243, 72, 98, 251, 130, 168, 213];
-#[derive(Clone, Debug, PartialEq)]
-pub struct InitializeUserStatsIxData;
-impl InitializeUserStatsIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != INITIALIZE_USER_STATS_IX_DISCM {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "discm does not match. Expected: {:?}. Received: {:?}",
-                    INITIALIZE_USER_STATS_IX_DISCM, maybe_discm
-                ),
-            ));
-        }
-        Ok(Self)
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&INITIALIZE_USER_STATS_IX_DISCM)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
-
-pub const INITIALIZE_USER_IX_DISCM: [u8; 8] = [111, 17, 185, 250, 60, 122, 38, 254];
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct InitializeUserIxArgs {
-    pub sub_account_id: u16,
-    pub name: [u8; 32],
-}
-#[derive(Clone, Debug, PartialEq)]
-pub struct InitializeUserIxData(pub InitializeUserIxArgs);
-impl From<InitializeUserIxArgs> for InitializeUserIxData {
-    fn from(args: InitializeUserIxArgs) -> Self {
-        Self(args)
-    }
-}
-impl InitializeUserIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != INITIALIZE_USER_IX_DISCM {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "discm does not match. Expected: {:?}. Received: {:?}",
-                    INITIALIZE_USER_IX_DISCM, maybe_discm
-                ),
-            ));
-        }
-        Ok(Self(InitializeUserIxArgs::deserialize(&mut reader)?))
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&INITIALIZE_USER_IX_DISCM)?;
-        self.0.serialize(&mut writer)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
-
-pub const DEPOSIT_IX_DISCM: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct DepositIxArgs {
-    pub market_index: u16,
-    pub amount: u64,
-    pub reduce_only: bool,
-}
-#[derive(Clone, Debug, PartialEq)]
-pub struct DepositIxData(pub DepositIxArgs);
-impl From<DepositIxArgs> for DepositIxData {
-    fn from(args: DepositIxArgs) -> Self {
-        Self(args)
-    }
-}
-impl DepositIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != DEPOSIT_IX_DISCM {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "discm does not match. Expected: {:?}. Received: {:?}",
-                    DEPOSIT_IX_DISCM, maybe_discm
-                ),
-            ));
-        }
-        Ok(Self(DepositIxArgs::deserialize(&mut reader)?))
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&DEPOSIT_IX_DISCM)?;
-        self.0.serialize(&mut writer)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
-
-pub const WITHDRAW_IX_DISCM: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct WithdrawIxArgs {
-    pub market_index: u16,
-    pub amount: u64,
-    pub reduce_only: bool,
-}
-#[derive(Clone, Debug, PartialEq)]
-pub struct WithdrawIxData(pub WithdrawIxArgs);
-impl From<WithdrawIxArgs> for WithdrawIxData {
-    fn from(args: WithdrawIxArgs) -> Self {
-        Self(args)
-    }
-}
-impl WithdrawIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != WITHDRAW_IX_DISCM {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "discm does not match. Expected: {:?}. Received: {:?}",
-                    WITHDRAW_IX_DISCM, maybe_discm
-                ),
-            ));
-        }
-        Ok(Self(WithdrawIxArgs::deserialize(&mut reader)?))
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&WITHDRAW_IX_DISCM)?;
-        self.0.serialize(&mut writer)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
-
-pub const UPDATE_USER_DELEGATE_IX_DISCM: [u8; 8] = [139, 205, 141, 141, 113, 36, 94, 187];
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct UpdateUserDelegateIxArgs {
-    pub sub_account_id: u16,
-    pub delegate: Pubkey,
-}
-#[derive(Clone, Debug, PartialEq)]
-pub struct UpdateUserDelegateIxData(pub UpdateUserDelegateIxArgs);
-impl From<UpdateUserDelegateIxArgs> for UpdateUserDelegateIxData {
-    fn from(args: UpdateUserDelegateIxArgs) -> Self {
-        Self(args)
-    }
-}
-impl UpdateUserDelegateIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != UPDATE_USER_DELEGATE_IX_DISCM {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "discm does not match. Expected: {:?}. Received: {:?}",
-                    UPDATE_USER_DELEGATE_IX_DISCM, maybe_discm
-                ),
-            ));
-        }
-        Ok(Self(UpdateUserDelegateIxArgs::deserialize(&mut reader)?))
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&UPDATE_USER_DELEGATE_IX_DISCM)?;
-        self.0.serialize(&mut writer)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
-
-pub const UPDATE_USER_REDUCE_ONLY_IX_DISCM: [u8; 8] = [
-    199,
-    71,
-    42,
-    67,
-    144,
-    19,
-    86,
-    109,
-];
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct UpdateUserReduceOnlyIxArgs {
-    pub sub_account_id: u16,
-    pub reduce_only: bool,
-}
-#[derive(Clone, Debug, PartialEq)]
-pub struct UpdateUserReduceOnlyIxData(pub UpdateUserReduceOnlyIxArgs);
-impl From<UpdateUserReduceOnlyIxArgs> for UpdateUserReduceOnlyIxData {
-    fn from(args: UpdateUserReduceOnlyIxArgs) -> Self {
-        Self(args)
-    }
-}
-impl UpdateUserReduceOnlyIxData {
-    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
-        let mut reader = buf;
-        let mut maybe_discm = [0u8; 8];
-        reader.read_exact(&mut maybe_discm)?;
-        if maybe_discm != UPDATE_USER_REDUCE_ONLY_IX_DISCM {
-            return Err(
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "discm does not match. Expected: {:?}. Received: {:?}",
-                        UPDATE_USER_REDUCE_ONLY_IX_DISCM, maybe_discm
-                    ),
-                ),
-            );
-        }
-        Ok(Self(UpdateUserReduceOnlyIxArgs::deserialize(&mut reader)?))
-    }
-    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&UPDATE_USER_REDUCE_ONLY_IX_DISCM)?;
-        self.0.serialize(&mut writer)
-    }
-    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
-        let mut data = Vec::new();
-        self.serialize(&mut data)?;
-        Ok(data)
-    }
-}
\ No newline at end of file
@@ -59,6 +59,81 @@ pub enum VaultErrorCode {
 
     #[error("InvalidOracle")]
     InvalidOracle,
+
+    #[error("InvalidTradingPolicy")]
+    InvalidTradingPolicy,
+
+    #[error("TradingPolicyDisabled")]
+    TradingPolicyDisabled,
+
+    #[error("MarketNotAllowed")]
+    MarketNotAllowed,
+
+    #[error("OrderTypeNotAllowed")]
+    OrderTypeNotAllowed,
+
+    #[error("PositionNotionalExceeded")]
+    PositionNotionalExceeded,
+
+    #[error("LeverageExceeded")]
+    LeverageExceeded,
+
+    #[error("InvalidPositionTransfer")]
+    InvalidPositionTransfer,
+
+    #[error("DepositorIsAtCapacity")]
+    DepositorIsAtCapacity,
+
+    #[error("InvalidVaultMetadata")]
+    InvalidVaultMetadata,
+
+    #[error("InvalidSubAccount")]
+    InvalidSubAccount,
+
+    #[error("MaxSubAccountsReached")]
+    MaxSubAccountsReached,
+
+    #[error("MissingMarketAccount")]
+    MissingMarketAccount,
+
+    #[error("InvalidCollateralMarket")]
+    InvalidCollateralMarket,
+
+    #[error("InvalidInsuranceFundStake")]
+    InvalidInsuranceFundStake,
+
+    #[error("InvalidSwap")]
+    InvalidSwap,
+
+    #[error("SwapSlippageExceeded")]
+    SwapSlippageExceeded,
+
+    #[error("InvalidReferrer")]
+    InvalidReferrer,
+
+    #[error("MarginRatioTimelocked")]
+    MarginRatioTimelocked,
+
+    #[error("VaultInLiquidation")]
+    VaultInLiquidation,
+
+    #[error("InvalidShareMint")]
+    InvalidShareMint,
+
+    #[error("UnsupportedMint")]
+    UnsupportedMint,
+
+    #[error("LotReceiptsDisabled")]
+    LotReceiptsDisabled,
+
+    #[error("InvalidLotReceipt")]
+    InvalidLotReceipt,
+
+    #[error("TradingPolicyTimelocked")]
+    TradingPolicyTimelocked,
+
+    #[error("AccountNeedsMigration")]
+    AccountNeedsMigration,
 }
 
 impl From<VaultErrorCode> for ProgramError {
@@ -1,4 +1,8 @@
 use borsh::BorshDeserialize;
+use drift::{
+    controller::position::PositionDirection,
+    state::{order_params::OrderParams, user::MarketType},
+};
 use solana_program::program_error::ProgramError;
 
 pub enum VaultInstruction {
@@ -13,6 +17,7 @@ pub enum VaultInstruction {
         hurdle_rate: u32,
         spot_market_index: u16,
         permissioned: bool,
+        max_deposit_per_depositor: Option<u64>,
     },
     InitializeVaultDepositor {},
     Deposit {
@@ -48,8 +53,121 @@ pub enum VaultInstruction {
         profit_share: u32,
         hurdle_rate: u32,
         permissioned: bool,
+        max_deposit_per_depositor: Option<u64>,
     },
     ResetDelegate {},
+    UpdateTradingPolicy {
+        allowed_perp_markets: Vec<u16>,
+        allowed_spot_markets: Vec<u16>,
+        max_leverage: u64,
+        max_position_notional: u64,
+        allowed_order_types: u8,
+        max_swap_slippage: u64,
+        enabled: bool,
+    },
+    PlaceOrders {
+        params: Vec<OrderParams>,
+    },
+    CancelOrders {
+        market_type: Option<MarketType>,
+        market_index: Option<u16>,
+        direction: Option<PositionDirection>,
+    },
+    TransferDepositorPosition {},
+    UpdateDepositorCap {
+        deposit_cap: Option<u64>,
+    },
+    UpdateVaultRoles {
+        admin: String,
+        operator: String,
+        fee_recipient: String,
+    },
+    UpdateVaultMetadata {
+        symbol: String,
+        description: String,
+        tags: Vec<String>,
+        website: String,
+        risk_rating: u8,
+        uri: String,
+    },
+    InitializeSubAccount {
+        sub_account_id: u16,
+    },
+    SetPrimarySubAccount {
+        sub_account_id: u16,
+    },
+    UpdateCollateralMarkets {
+        spot_market_indexes: Vec<u16>,
+    },
+    DepositCollateral {
+        name: String,
+        market_index: u16,
+        amount: u64,
+    },
+    SettleVaultPnl {
+        market_indexes: Vec<u16>,
+    },
+    InitializeInsuranceFundStake {},
+    AddInsuranceFundStake {
+        amount: u64,
+    },
+    RequestRemoveInsuranceFundStake {
+        amount: u64,
+    },
+    RemoveInsuranceFundStake {},
+    SwapCollateral {
+        in_market_index: u16,
+        out_market_index: u16,
+        amount_in: u64,
+        num_swap_accounts: u8,
+        swap_data: Vec<u8>,
+    },
+    InitializeReferrerName {},
+    SweepReferrerRewards {},
+    UpdateMarginRatio {
+        margin_ratio: u32,
+    },
+    UpdateMarginTradingEnabled {
+        enabled: bool,
+    },
+    UpdateHighLeverageMode {
+        enabled: bool,
+    },
+    RecordVaultLiquidation {},
+    UpdateFuelBonus {},
+    InitializeShareMint {},
+    WrapShares {
+        shares: u128,
+    },
+    UnwrapShares {
+        shares: u128,
+    },
+    PreviewDeposit {
+        amount: u64,
+    },
+    PreviewWithdraw {
+        amount: u64,
+    },
+    ConvertToShares {
+        amount: u64,
+    },
+    ConvertToAssets {
+        shares: u128,
+    },
+    UpdateLotReceipts {
+        enabled: bool,
+    },
+    MintLotReceipt {
+        deposit_ts: i64,
+    },
+    RedeemLotReceipt {},
+    RefreshSharePrice {},
+    UpdateNavHistory {
+        interval: u64,
+    },
+    RefreshDepositorStats {},
+    MigrateVaultDepositor {},
+    MigrateVault {},
 }
 
 #[derive(BorshDeserialize)]
@@ -64,6 +182,21 @@ struct BaseVaultPayload {
     hurdle_rate: u32,
     spot_market_index: u16,
     permissioned: bool,
+    /// Appended after the other fields, clients built before it was added don't send it
+    #[borsh_skip]
+    max_deposit_per_depositor: Option<u64>,
+}
+
+impl BaseVaultPayload {
+    fn unpack(mut data: &[u8]) -> Result<Self, ProgramError> {
+        let mut payload = Self::deserialize(&mut data)?;
+
+        if !data.is_empty() {
+            payload.max_deposit_per_depositor = Option::<u64>::try_from_slice(data)?;
+        }
+
+        Ok(payload)
+    }
 }
 
 #[derive(BorshDeserialize)]
@@ -84,6 +217,117 @@ struct WithdrawRequestPayload {
     amount: u64,
 }
 
+#[derive(BorshDeserialize)]
+struct UpdateDepositorCapPayload {
+    deposit_cap: Option<u64>,
+}
+
+#[derive(BorshDeserialize)]
+struct UpdateVaultRolesPayload {
+    admin: String,
+    operator: String,
+    fee_recipient: String,
+}
+
+#[derive(BorshDeserialize)]
+struct VaultMetadataPayload {
+    symbol: String,
+    description: String,
+    tags: Vec<String>,
+    website: String,
+    risk_rating: u8,
+    uri: String,
+}
+
+#[derive(BorshDeserialize)]
+struct SubAccountPayload {
+    sub_account_id: u16,
+}
+
+#[derive(BorshDeserialize)]
+struct CollateralMarketsPayload {
+    spot_market_indexes: Vec<u16>,
+}
+
+#[derive(BorshDeserialize)]
+struct DepositCollateralPayload {
+    name: String,
+    market_index: u16,
+    amount: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct SettleVaultPnlPayload {
+    market_indexes: Vec<u16>,
+}
+
+#[derive(BorshDeserialize)]
+struct InsuranceFundStakePayload {
+    amount: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct SwapCollateralPayload {
+    in_market_index: u16,
+    out_market_index: u16,
+    amount_in: u64,
+    num_swap_accounts: u8,
+    swap_data: Vec<u8>,
+}
+
+#[derive(BorshDeserialize)]
+struct MarginRatioPayload {
+    margin_ratio: u32,
+}
+
+#[derive(BorshDeserialize)]
+struct EnabledPayload {
+    enabled: bool,
+}
+
+#[derive(BorshDeserialize)]
+struct SharesPayload {
+    shares: u128,
+}
+
+#[derive(BorshDeserialize)]
+struct AmountPayload {
+    amount: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct IntervalPayload {
+    interval: u64,
+}
+
+#[derive(BorshDeserialize)]
+struct LotPayload {
+    deposit_ts: i64,
+}
+
+#[derive(BorshDeserialize)]
+struct TradingPolicyPayload {
+    allowed_perp_markets: Vec<u16>,
+    allowed_spot_markets: Vec<u16>,
+    max_leverage: u64,
+    max_position_notional: u64,
+    allowed_order_types: u8,
+    max_swap_slippage: u64,
+    enabled: bool,
+}
+
+#[derive(BorshDeserialize)]
+struct PlaceOrdersPayload {
+    params: Vec<OrderParams>,
+}
+
+#[derive(BorshDeserialize)]
+struct CancelOrdersPayload {
+    market_type: Option<MarketType>,
+    market_index: Option<u16>,
+    direction: Option<PositionDirection>,
+}
+
 impl VaultInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
         let (&variant, rest) = input
@@ -91,7 +335,7 @@ impl VaultInstruction {
             .ok_or(ProgramError::InvalidInstructionData)?;
         Ok(match variant {
             0 => {
-                let payload = BaseVaultPayload::try_from_slice(rest).unwrap();
+                let payload = BaseVaultPayload::unpack(rest)?;
                 Self::InitializeDriftWithBulk {
                     name: payload.name,
                     lock_in_period: payload.lock_in_period,
@@ -103,6 +347,7 @@ impl VaultInstruction {
                     hurdle_rate: payload.hurdle_rate,
                     spot_market_index: payload.spot_market_index,
                     permissioned: payload.permissioned,
+                    max_deposit_per_depositor: payload.max_deposit_per_depositor,
                 }
             }
             1 => Self::InitializeVaultDepositor {},
@@ -150,7 +395,7 @@ impl VaultInstruction {
                 }
             }
             10 => {
-                let payload = BaseVaultPayload::try_from_slice(rest).unwrap();
+                let payload = BaseVaultPayload::unpack(rest)?;
                 Self::UpdateVault {
                     lock_in_period: payload.lock_in_period,
                     redeem_period: payload.redeem_period,
@@ -160,9 +405,200 @@ impl VaultInstruction {
                     profit_share: payload.profit_share,
                     hurdle_rate: payload.hurdle_rate,
                     permissioned: payload.permissioned,
+                    max_deposit_per_depositor: payload.max_deposit_per_depositor,
                 }
             }
             11 => Self::ResetDelegate {},
+            12 => {
+                let payload = TradingPolicyPayload::try_from_slice(rest).unwrap();
+                Self::UpdateTradingPolicy {
+                    allowed_perp_markets: payload.allowed_perp_markets,
+                    allowed_spot_markets: payload.allowed_spot_markets,
+                    max_leverage: payload.max_leverage,
+                    max_position_notional: payload.max_position_notional,
+                    allowed_order_types: payload.allowed_order_types,
+                    max_swap_slippage: payload.max_swap_slippage,
+                    enabled: payload.enabled,
+                }
+            }
+            13 => {
+                let payload = PlaceOrdersPayload::try_from_slice(rest).unwrap();
+                Self::PlaceOrders {
+                    params: payload.params,
+                }
+            }
+            14 => {
+                let payload = CancelOrdersPayload::try_from_slice(rest).unwrap();
+                Self::CancelOrders {
+                    market_type: payload.market_type,
+                    market_index: payload.market_index,
+                    direction: payload.direction,
+                }
+            }
+            15 => Self::TransferDepositorPosition {},
+            16 => {
+                let payload = UpdateDepositorCapPayload::try_from_slice(rest).unwrap();
+                Self::UpdateDepositorCap {
+                    deposit_cap: payload.deposit_cap,
+                }
+            }
+            17 => {
+                let payload = UpdateVaultRolesPayload::try_from_slice(rest).unwrap();
+                Self::UpdateVaultRoles {
+                    admin: payload.admin,
+                    operator: payload.operator,
+                    fee_recipient: payload.fee_recipient,
+                }
+            }
+            18 => {
+                let payload = VaultMetadataPayload::try_from_slice(rest).unwrap();
+                Self::UpdateVaultMetadata {
+                    symbol: payload.symbol,
+                    description: payload.description,
+                    tags: payload.tags,
+                    website: payload.website,
+                    risk_rating: payload.risk_rating,
+                    uri: payload.uri,
+                }
+            }
+            19 => {
+                let payload = SubAccountPayload::try_from_slice(rest).unwrap();
+                Self::InitializeSubAccount {
+                    sub_account_id: payload.sub_account_id,
+                }
+            }
+            20 => {
+                let payload = SubAccountPayload::try_from_slice(rest).unwrap();
+                Self::SetPrimarySubAccount {
+                    sub_account_id: payload.sub_account_id,
+                }
+            }
+            21 => {
+                let payload = CollateralMarketsPayload::try_from_slice(rest).unwrap();
+                Self::UpdateCollateralMarkets {
+                    spot_market_indexes: payload.spot_market_indexes,
+                }
+            }
+            22 => {
+                let payload = DepositCollateralPayload::try_from_slice(rest).unwrap();
+                Self::DepositCollateral {
+                    name: payload.name,
+                    market_index: payload.market_index,
+                    amount: payload.amount,
+                }
+            }
+            23 => {
+                let payload = SettleVaultPnlPayload::try_from_slice(rest).unwrap();
+                Self::SettleVaultPnl {
+                    market_indexes: payload.market_indexes,
+                }
+            }
+            24 => Self::InitializeInsuranceFundStake {},
+            25 => {
+                let payload = InsuranceFundStakePayload::try_from_slice(rest).unwrap();
+                Self::AddInsuranceFundStake {
+                    amount: payload.amount,
+                }
+            }
+            26 => {
+                let payload = InsuranceFundStakePayload::try_from_slice(rest).unwrap();
+                Self::RequestRemoveInsuranceFundStake {
+                    amount: payload.amount,
+                }
+            }
+            27 => Self::RemoveInsuranceFundStake {},
+            28 => {
+                let payload = SwapCollateralPayload::try_from_slice(rest).unwrap();
+                Self::SwapCollateral {
+                    in_market_index: payload.in_market_index,
+                    out_market_index: payload.out_market_index,
+                    amount_in: payload.amount_in,
+                    num_swap_accounts: payload.num_swap_accounts,
+                    swap_data: payload.swap_data,
+                }
+            }
+            29 => Self::InitializeReferrerName {},
+            30 => Self::SweepReferrerRewards {},
+            31 => {
+                let payload = MarginRatioPayload::try_from_slice(rest).unwrap();
+                Self::UpdateMarginRatio {
+                    margin_ratio: payload.margin_ratio,
+                }
+            }
+            32 => {
+                let payload = EnabledPayload::try_from_slice(rest).unwrap();
+                Self::UpdateMarginTradingEnabled {
+                    enabled: payload.enabled,
+                }
+            }
+            33 => {
+                let payload = EnabledPayload::try_from_slice(rest).unwrap();
+                Self::UpdateHighLeverageMode {
+                    enabled: payload.enabled,
+                }
+            }
+            34 => Self::RecordVaultLiquidation {},
+            35 => Self::UpdateFuelBonus {},
+            36 => Self::InitializeShareMint {},
+            37 => {
+                let payload = SharesPayload::try_from_slice(rest).unwrap();
+                Self::WrapShares {
+                    shares: payload.shares,
+                }
+            }
+            38 => {
+                let payload = SharesPayload::try_from_slice(rest).unwrap();
+                Self::UnwrapShares {
+                    shares: payload.shares,
+                }
+            }
+            39 => {
+                let payload = AmountPayload::try_from_slice(rest).unwrap();
+                Self::PreviewDeposit {
+                    amount: payload.amount,
+                }
+            }
+            40 => {
+                let payload = AmountPayload::try_from_slice(rest).unwrap();
+                Self::PreviewWithdraw {
+                    amount: payload.amount,
+                }
+            }
+            41 => {
+                let payload = AmountPayload::try_from_slice(rest).unwrap();
+                Self::ConvertToShares {
+                    amount: payload.amount,
+                }
+            }
+            42 => {
+                let payload = SharesPayload::try_from_slice(rest).unwrap();
+                Self::ConvertToAssets {
+                    shares: payload.shares,
+                }
+            }
+            43 => {
+                let payload = EnabledPayload::try_from_slice(rest).unwrap();
+                Self::UpdateLotReceipts {
+                    enabled: payload.enabled,
+                }
+            }
+            44 => {
+                let payload = LotPayload::try_from_slice(rest).unwrap();
+                Self::MintLotReceipt {
+                    deposit_ts: payload.deposit_ts,
+                }
+            }
+            45 => Self::RedeemLotReceipt {},
+            46 => Self::RefreshSharePrice {},
+            47 => {
+                let payload = IntervalPayload::try_from_slice(rest).unwrap();
+                Self::UpdateNavHistory {
+                    interval: payload.interval,
+                }
+            }
+            48 => Self::RefreshDepositorStats {},
+            49 => Self::MigrateVaultDepositor {},
+            50 => Self::MigrateVault {},
             _ => return Err(ProgramError::InvalidInstructionData),
         })
     }
@@ -0,0 +1,164 @@
+use drift_interface::{
+    add_insurance_fund_stake_ix_with_program_id, add_insurance_fund_stake_verify_account_keys,
+    add_insurance_fund_stake_verify_writable_privileges, AddInsuranceFundStakeAccounts,
+    AddInsuranceFundStakeIxArgs, AddInsuranceFundStakeKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+    },
+    error::VaultErrorCode,
+    instructions::drift_withdraw,
+    state::Vault,
+};
+
+pub fn add_insurance_fund_stake<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount: u64,
+) -> ProgramResult {
+    msg!("Adding insurance fund stake...");
+    msg!("amount: {}", amount);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_spot_market_vault = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+    let drift_signer = next_account_info(account_info_iter)?;
+    let insurance_fund_stake = next_account_info(account_info_iter)?;
+    let insurance_fund_vault = next_account_info(account_info_iter)?;
+
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        // Drift accounts
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_spot_market_vault, "Drift Spot Market Vault"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+        (drift_signer, "Drift Signer"),
+        (insurance_fund_stake, "Insurance Fund Stake"),
+        (insurance_fund_vault, "Insurance Fund Vault"),
+        // Token accounts
+        (vault_token_account, "Vault Token Account"),
+        (mint, "Mint"),
+        (token_program, "Token Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.insurance_fund_stake != *insurance_fund_stake.key {
+        msg!("Invalid Insurance Fund Stake");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    if vault.user != *drift_user.key {
+        msg!("Drift User is not the vault's primary sub account");
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    // The markets for the primary sub account's positions follow the fixed accounts
+    let market_accounts = match &accounts[15..] {
+        [] => &accounts[7..9],
+        market_accounts => market_accounts,
+    };
+
+    // The stake is paid from the vault's token account, so take it out of the drift user first
+    drift_withdraw(
+        &vault,
+        vault.spot_market_index,
+        amount,
+        false,
+        drift_program,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        drift_spot_market_vault,
+        drift_signer,
+        vault_token_account,
+        token_program,
+        market_accounts,
+        Some(mint),
+    )?;
+
+    let cpi_accounts = AddInsuranceFundStakeAccounts {
+        state: drift_state,
+        spot_market: drift_spot_market,
+        insurance_fund_stake,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        spot_market_vault: drift_spot_market_vault,
+        insurance_fund_vault,
+        drift_signer,
+        user_token_account: vault_token_account,
+        token_program,
+    };
+
+    add_insurance_fund_stake_verify_account_keys(
+        cpi_accounts,
+        AddInsuranceFundStakeKeys {
+            insurance_fund_stake: vault.insurance_fund_stake,
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    add_insurance_fund_stake_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = add_insurance_fund_stake_ix_with_program_id(
+        *drift_program.key,
+        cpi_accounts.into(),
+        AddInsuranceFundStakeIxArgs {
+            market_index: vault.spot_market_index,
+            amount,
+        },
+    )?;
+
+    invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)
+}
@@ -0,0 +1,118 @@
+use drift::{controller::position::PositionDirection, state::user::MarketType};
+use drift_interface::{
+    cancel_orders_ix_with_program_id, cancel_orders_verify_account_keys,
+    cancel_orders_verify_writable_privileges, CancelOrdersAccounts, CancelOrdersIxArgs,
+    CancelOrdersKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+    },
+    state::Vault,
+};
+
+pub fn cancel_orders<'info>(
+    _program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    market_type: Option<MarketType>,
+    market_index: Option<u16>,
+    direction: Option<PositionDirection>,
+) -> ProgramResult {
+    msg!("Cancelling orders...");
+    msg!("market_type: {:?}", market_type);
+    msg!("market_index: {:?}", market_index);
+    msg!("direction: {:?}", direction);
+
+    let mut account_info_iter = accounts.iter();
+
+    let trader = next_account_info(&mut account_info_iter)?;
+    let vault_account = next_account_info(&mut account_info_iter)?;
+
+    let drift_program = next_account_info(&mut account_info_iter)?;
+    let drift_state = next_account_info(&mut account_info_iter)?;
+    let drift_user = next_account_info(&mut account_info_iter)?;
+
+    // Oracles, spot markets and perp markets for drift
+    let remaining_accounts = &accounts[5..];
+
+    log_accounts(&[
+        (trader, "Trader"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+    ]);
+
+    if !trader.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let sub_account = match vault.get_sub_account_by_user(drift_user.key) {
+        Some(sub_account) => sub_account,
+        None => {
+            msg!("Drift User is not a vault sub account");
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    if sub_account.delegate != *trader.key && vault.operator != *trader.key {
+        msg!("Trader must be the sub account delegate or vault operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!("Cancel orders CPI to Drift...");
+
+    let cpi_accounts = CancelOrdersAccounts {
+        state: drift_state,
+        user: drift_user,
+        authority: vault_account,
+    };
+
+    cancel_orders_verify_account_keys(
+        cpi_accounts,
+        CancelOrdersKeys {
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    cancel_orders_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let args = CancelOrdersIxArgs {
+        market_type: market_type.map(|market_type| match market_type {
+            MarketType::Spot => drift_interface::MarketType::Spot,
+            MarketType::Perp => drift_interface::MarketType::Perp,
+        }),
+        market_index,
+        direction: direction.map(|direction| match direction {
+            PositionDirection::Long => drift_interface::PositionDirection::Long,
+            PositionDirection::Short => drift_interface::PositionDirection::Short,
+        }),
+    };
+
+    let ix = cancel_orders_ix_with_program_id(*drift_program.key, cpi_accounts.into(), args)?;
+
+    invoke_drift_signed(ix, cpi_accounts, remaining_accounts, drift_program, &vault)
+}
@@ -1,9 +1,3 @@
-use std::collections::BTreeSet;
-
-use drift::{
-    instructions::optional_accounts::{load_maps, AccountMaps},
-    state::{spot_market_map::get_writable_spot_market_set, user::User},
-};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -14,8 +8,7 @@ use solana_program::{
 };
 
 use crate::{
-    common::{deserialize_zero_copy, log_accounts},
-    error::wrap_drift_error,
+    common::{calculate_vault_equity, log_accounts},
     state::{Vault, VaultDepositor},
 };
 
@@ -62,33 +55,17 @@ pub fn cancel_withdraw_request<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
-    let mut vault_depositor = VaultDepositor::get(vault_depositor_account);
-
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    let mut vault = Vault::get(vault_account)?;
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
 
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[6..8].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[6..8],
+        &accounts[8..],
         clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
-    };
-
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
-
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    )?;
 
     msg!("vault_equity: {:?}", vault_equity);
 
@@ -1,11 +1,16 @@
-use crate::common::{deserialize_zero_copy, log_accounts, transfer_fees, transfer_to_vault};
-use crate::drift::{DepositIxArgs, DepositIxData};
-use crate::error::wrap_drift_error;
-use crate::state::{DepositInfo, Vault, VaultDepositor};
-use drift::instructions::optional_accounts::{load_maps, AccountMaps};
-use drift::state::spot_market_map::get_writable_spot_market_set;
-use drift::state::user::User;
-use solana_program::instruction::{AccountMeta, Instruction};
+use crate::common::{
+    bytes32_to_string, calculate_vault_equity_and_oracle_confidence, drift_account_key_error,
+    drift_account_privilege_error, get_market_accounts, get_transfer_fee, invoke_drift_signed,
+    log_accounts, mint_shares_to_user, transfer_fees_from_vault, transfer_to_vault,
+    update_share_price, update_vault_fuel, validate_vault_not_in_liquidation,
+};
+use crate::custom_validate;
+use crate::error::VaultErrorCode;
+use crate::state::{DepositInfo, Treasury, Vault, VaultDepositor};
+use drift_interface::{
+    deposit_ix_with_program_id, deposit_verify_account_keys, deposit_verify_writable_privileges,
+    DepositAccounts, DepositIxArgs, DepositKeys,
+};
 use solana_program::program::invoke;
 use solana_program::rent::Rent;
 use solana_program::system_instruction;
@@ -13,13 +18,10 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::{clock::Clock, Sysvar},
 };
-use std::collections::BTreeSet;
-
 pub fn deposit<'info>(
     program_id: &Pubkey,
     accounts: &'info [AccountInfo<'info>],
@@ -55,6 +57,8 @@ pub fn deposit<'info>(
     let token_program = next_account_info(&mut account_info_iter)?;
     let system_program = next_account_info(&mut account_info_iter)?;
 
+    let share_price = next_account_info(&mut account_info_iter)?;
+
     log_accounts(&[
         (vault_account, "Vault"),
         (vault_depositor_account, "Vault Depositor"),
@@ -75,6 +79,8 @@ pub fn deposit<'info>(
         (mint, "Mint"),
         // System accounts
         (token_program, "Token Program"),
+        (system_program, "System Program"),
+        (share_price, "Share Price"),
     ]);
 
     if !authority.is_signer {
@@ -95,15 +101,27 @@ pub fn deposit<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let (vault_pda, vault_bump_seed) = Vault::get_pda(&name, program_id);
+    let (vault_pda, _) = Vault::get_pda(&name, program_id);
 
     if vault_pda != *vault_account.key {
         msg!("Invalid seeds for Vault PDA");
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
-    let mut vault_depositor = VaultDepositor::get(vault_depositor_account);
+    let mut vault = Vault::get(vault_account)?;
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    let (treasury_pda, _) = Treasury::get_pda(&bytes32_to_string(vault.name), program_id);
+
+    if treasury_pda != *treasury.key {
+        msg!("Invalid seeds for Treasury PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if Treasury::get(treasury).token_account != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
 
     let need_resize = vault_depositor.does_need_resize(vault_depositor_account.data.borrow().len());
 
@@ -116,62 +134,111 @@ pub fn deposit<'info>(
         )?;
     }
 
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
-
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[9..11].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
-        clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
+    // Vaults with a share mint take the share mint, the token account the deposit's shares are
+    // minted to and the spl token program
+    let share_accounts = if vault.has_share_mint() {
+        let share_mint = next_account_info(&mut account_info_iter)?;
+        let share_token_account = next_account_info(&mut account_info_iter)?;
+        let share_token_program = next_account_info(&mut account_info_iter)?;
+
+        custom_validate!(
+            vault.share_mint == *share_mint.key,
+            VaultErrorCode::InvalidShareMint,
+            "vault share mint is {}",
+            vault.share_mint
+        )?;
+
+        Some((share_mint, share_token_account, share_token_program))
+    } else {
+        None
     };
 
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+    let remaining_accounts_start = if share_accounts.is_some() { 21 } else { 18 };
+    let remaining_accounts = &accounts[remaining_accounts_start..];
 
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, remaining_accounts)?;
 
-    let timestamp = clock.unix_timestamp;
+    // Fuel earned so far goes to the shares that earned it
+    update_vault_fuel(&mut vault, drift_user_stats)?;
 
-    let (deposit_amount, fees) =
-        vault_depositor.deposit(amount, vault_equity, &mut vault, timestamp)?;
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let (vault_equity, oracle_confidence) = calculate_vault_equity_and_oracle_confidence(
+        &vault,
+        drift_user,
+        &accounts[9..11],
+        remaining_accounts,
+        clock.slot,
+    )?;
 
-    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
-    Vault::save(&vault, vault_account)?;
+    let timestamp = clock.unix_timestamp;
 
-    transfer_fees(
-        fees,
+    // Shares are issued for the amount the vault received, less than the amount sent for mints
+    // with a transfer fee
+    let received_amount = transfer_to_vault(
+        amount,
         token_program,
         user_token_account,
-        treasury_token_account,
+        vault_token_account,
         authority,
         mint,
     )?;
 
-    transfer_to_vault(
-        deposit_amount,
+    // Drift takes the deposit with another transfer. Its fee is withheld up front, at most the fee
+    // on the whole amount received, so shares are only issued for what drift receives
+    let drift_transfer_fee = get_transfer_fee(mint, received_amount)?;
+
+    let (deposit_amount, fees) = vault_depositor.deposit(
+        received_amount.saturating_sub(drift_transfer_fee),
+        vault_equity,
+        &mut vault,
+        timestamp,
+    )?;
+
+    // The deposit's shares are minted as share tokens instead of staying in the ledger
+    let share_token_amount = match share_accounts {
+        Some(_) => vault_depositor.wrap_deposit(deposit_amount, &mut vault, timestamp)?,
+        None => 0,
+    };
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    Vault::save(&vault, vault_account)?;
+
+    if let Some((share_mint, share_token_account, share_token_program)) = share_accounts {
+        mint_shares_to_user(
+            &vault,
+            share_token_amount,
+            share_token_program,
+            share_mint,
+            share_token_account,
+            vault_account,
+        )?;
+    }
+
+    update_share_price(
+        program_id,
+        &vault,
+        share_price,
+        vault_equity.saturating_add(deposit_amount),
+        oracle_confidence,
+        clock.slot,
+        timestamp,
+    )?;
+
+    transfer_fees_from_vault(
+        &vault,
+        fees,
         token_program,
-        user_token_account,
         vault_token_account,
-        authority,
+        treasury_token_account,
+        vault_account,
         mint,
     )?;
 
     drift_deposit(
         &vault,
-        deposit_amount,
-        name,
-        vault_bump_seed,
+        vault.spot_market_index,
+        deposit_amount.saturating_add(drift_transfer_fee),
         drift_state,
         drift_user,
         drift_user_stats,
@@ -179,8 +246,8 @@ pub fn deposit<'info>(
         drift_spot_market_vault,
         vault_token_account,
         token_program,
-        drift_oracle,
-        drift_spot_market,
+        get_market_accounts(&vault, &accounts[9..11], remaining_accounts),
+        Some(mint),
         drift_program,
     )?;
 
@@ -189,9 +256,8 @@ pub fn deposit<'info>(
 
 pub fn drift_deposit<'a>(
     vault: &Vault,
+    market_index: u16,
     amount: u64,
-    name: String,
-    vault_bump_seed: u8,
     // Individual accounts
     drift_state: &'a AccountInfo<'a>,
     drift_user: &'a AccountInfo<'a>,
@@ -200,90 +266,49 @@ pub fn drift_deposit<'a>(
     drift_spot_market_vault: &'a AccountInfo<'a>,
     vault_token_account: &'a AccountInfo<'a>,
     token_program: &'a AccountInfo<'a>,
-    drift_oracle: &'a AccountInfo<'a>,
-    drift_spot_market: &'a AccountInfo<'a>,
+    // Oracles, spot markets and perp markets for drift
+    market_accounts: &'a [AccountInfo<'a>],
+    // The mint of the deposited token, drift needs it to transfer token-2022 tokens
+    mint: Option<&'a AccountInfo<'a>>,
     drift_program: &'a AccountInfo<'a>,
 ) -> ProgramResult {
     msg!("Transfering from Vault Pda to Drift Vault...");
 
-    let accounts_meta = vec![
-        AccountMeta {
-            pubkey: *drift_state.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *drift_user.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_user_stats.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_account.key,
-            is_signer: true,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_spot_market_vault.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_token_account.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *token_program.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *drift_oracle.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_spot_market.key,
-            is_signer: false,
-            is_writable: true,
-        },
-    ];
-
-    let args = DepositIxArgs {
-        market_index: vault.spot_market_index,
-        amount,
-        reduce_only: false,
+    let accounts = DepositAccounts {
+        state: drift_state,
+        user: drift_user,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        spot_market_vault: drift_spot_market_vault,
+        user_token_account: vault_token_account,
+        token_program,
     };
 
-    let data: DepositIxData = args.into();
+    deposit_verify_account_keys(
+        accounts,
+        DepositKeys {
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    deposit_verify_writable_privileges(accounts).map_err(drift_account_privilege_error)?;
+
+    let ix = deposit_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        DepositIxArgs {
+            market_index,
+            amount,
+            reduce_only: false,
+        },
+    )?;
 
-    let ix = Instruction {
-        program_id: *drift_program.key,
-        accounts: accounts_meta,
-        data: data.try_to_vec()?,
-    };
+    let mut remaining_accounts = market_accounts.to_vec();
+    remaining_accounts.extend(mint.cloned());
 
-    invoke_signed(
-        &ix,
-        &[
-            drift_state.clone(),
-            drift_user.clone(),
-            drift_user_stats.clone(),
-            vault_account.clone(),
-            drift_spot_market_vault.clone(),
-            vault_token_account.clone(),
-            token_program.clone(),
-            drift_oracle.clone(),
-            drift_spot_market.clone(),
-            drift_program.clone(),
-        ],
-        &[&[b"vault", name.as_ref(), &[vault_bump_seed]]],
-    )
+    invoke_drift_signed(ix, accounts, &remaining_accounts, drift_program, vault)
 }
 
 /// Resizes the vault depositor account to accommodate new deposits
@@ -293,7 +318,7 @@ pub fn drift_deposit<'a>(
 /// * `authority` - Authority that pays for reallocation
 /// * `system_program` - System program for CPI
 /// * `additional_items` - Number of additional DepositInfo items to accommodate
-fn resize_vault_depositor_account<'a>(
+pub fn resize_vault_depositor_account<'a>(
     vault_depositor_account: &AccountInfo<'a>,
     authority: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
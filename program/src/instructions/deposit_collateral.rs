@@ -0,0 +1,194 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        calculate_vault_equity_and_collateral_value, get_market_accounts, log_accounts,
+        transfer_to_vault, update_vault_fuel, validate_vault_not_in_liquidation,
+    },
+    error::VaultErrorCode,
+    instructions::{drift_deposit, resize_vault_depositor_account},
+    state::{Vault, VaultDepositor},
+};
+
+pub fn deposit_collateral<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    name: String,
+    market_index: u16,
+    amount: u64,
+) -> ProgramResult {
+    msg!("Starting collateral deposit...");
+    msg!("name: {}", name);
+    msg!("market_index: {}", market_index);
+    msg!("amount: {}", amount);
+
+    let clock = &Clock::get()?;
+
+    let mut account_info_iter = accounts.iter();
+
+    let vault_account = next_account_info(&mut account_info_iter)?;
+    let vault_depositor_account = next_account_info(&mut account_info_iter)?;
+    let authority = next_account_info(&mut account_info_iter)?;
+
+    let drift_program = next_account_info(&mut account_info_iter)?;
+    let drift_user = next_account_info(&mut account_info_iter)?;
+    let drift_user_stats = next_account_info(&mut account_info_iter)?;
+    let drift_state = next_account_info(&mut account_info_iter)?;
+    let drift_collateral_spot_market_vault = next_account_info(&mut account_info_iter)?;
+    let drift_oracle = next_account_info(&mut account_info_iter)?;
+    let drift_collateral_oracle = next_account_info(&mut account_info_iter)?;
+    let drift_spot_market = next_account_info(&mut account_info_iter)?;
+    let drift_collateral_spot_market = next_account_info(&mut account_info_iter)?;
+
+    let user_collateral_token_account = next_account_info(&mut account_info_iter)?;
+    let vault_collateral_token_account = next_account_info(&mut account_info_iter)?;
+    let collateral_mint = next_account_info(&mut account_info_iter)?;
+
+    let token_program = next_account_info(&mut account_info_iter)?;
+    let system_program = next_account_info(&mut account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (authority, "Authority"),
+        // Drift accounts
+        (drift_program, "Drift Program"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_state, "Drift State"),
+        (
+            drift_collateral_spot_market_vault,
+            "Drift Collateral Spot Market Vault",
+        ),
+        (drift_oracle, "Drift Oracle"),
+        (drift_collateral_oracle, "Drift Collateral Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+        (drift_collateral_spot_market, "Drift Collateral Spot Market"),
+        // Token accounts
+        (
+            user_collateral_token_account,
+            "User Collateral Token Account",
+        ),
+        (
+            vault_collateral_token_account,
+            "Vault Collateral Token Account",
+        ),
+        (collateral_mint, "Collateral Mint"),
+        // System accounts
+        (token_program, "Token Program"),
+        (system_program, "System Program"),
+    ]);
+
+    if !authority.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(&vault_account.key, &authority.key, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_pda, _) = Vault::get_pda(&name, program_id);
+
+    if vault_pda != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    if !vault.is_collateral_market(market_index) {
+        msg!(
+            "Spot market {} is not a vault collateral market",
+            market_index
+        );
+        return Err(VaultErrorCode::InvalidCollateralMarket.into());
+    }
+
+    let need_resize = vault_depositor.does_need_resize(vault_depositor_account.data.borrow().len());
+
+    if need_resize {
+        resize_vault_depositor_account(
+            vault_depositor_account,
+            authority,
+            system_program,
+            2, // Space for 2 additional DepositInfo
+        )?;
+    }
+
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, &accounts[17..])?;
+
+    // Fuel earned so far goes to the shares that earned it, before the deposit and the manager
+    // fee shares dilute them
+    update_vault_fuel(&mut vault, drift_user_stats)?;
+
+    // The deposit is valued at the amount the vault received, less than the amount sent for
+    // mints with a transfer fee
+    let received_amount = transfer_to_vault(
+        amount,
+        token_program,
+        user_collateral_token_account,
+        vault_collateral_token_account,
+        authority,
+        collateral_mint,
+    )?;
+
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let (vault_equity, value) = calculate_vault_equity_and_collateral_value(
+        &vault,
+        drift_user,
+        &accounts[8..12],
+        &accounts[17..],
+        market_index,
+        received_amount,
+        clock.slot,
+    )?;
+
+    msg!("vault_equity: {}", vault_equity);
+    msg!("collateral value: {}", value);
+
+    let (deposit_value, fee) =
+        vault_depositor.deposit(value, vault_equity, &mut vault, clock.unix_timestamp)?;
+
+    // The whole collateral amount goes to drift, the fee stays in the vault as manager shares
+    vault.issue_manager_fee_shares(fee, vault_equity.saturating_add(deposit_value))?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    Vault::save(&vault, vault_account)?;
+
+    drift_deposit(
+        &vault,
+        market_index,
+        received_amount,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        drift_collateral_spot_market_vault,
+        vault_collateral_token_account,
+        token_program,
+        get_market_accounts(&vault, &accounts[8..12], &accounts[17..]),
+        Some(collateral_mint),
+        drift_program,
+    )?;
+
+    Ok(())
+}
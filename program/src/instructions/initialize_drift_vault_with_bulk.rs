@@ -1,16 +1,24 @@
 use crate::{
-    common::{log_accounts, log_params, string_to_bytes32},
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+        log_params, string_to_bytes32,
+    },
     constants::{PERCENTAGE_PRECISION, PERCENTAGE_PRECISION_U64},
-    drift::{self, InitializeUserIxArgs, InitializeUserIxData, InitializeUserStatsIxData},
     error::VaultErrorCode,
     state::Treasury,
 };
+use drift_interface::{
+    initialize_user_ix_with_program_id, initialize_user_stats_ix_with_program_id,
+    initialize_user_stats_verify_account_keys, initialize_user_stats_verify_writable_privileges,
+    initialize_user_verify_account_keys, initialize_user_verify_writable_privileges,
+    InitializeUserAccounts, InitializeUserIxArgs, InitializeUserKeys, InitializeUserStatsAccounts,
+    InitializeUserStatsKeys,
+};
 use serde::Serialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
-    instruction::{AccountMeta, Instruction},
     msg,
     program::invoke_signed,
     program_error::ProgramError,
@@ -45,6 +53,9 @@ pub fn initialize_drift_vault_with_bulk<'a>(
     let rent = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
 
+    // Optional drift referrer user and referrer user stats
+    let referrer_accounts = &accounts[11..];
+
     log_accounts(&[
         (manager, "Manager"),
         (vault_account, "Vault Account"),
@@ -69,6 +80,20 @@ pub fn initialize_drift_vault_with_bulk<'a>(
         return Err(ProgramError::InvalidArgument);
     }
 
+    match referrer_accounts {
+        [] => {}
+        [referrer, referrer_stats] => {
+            log_accounts(&[
+                (referrer, "Drift Referrer"),
+                (referrer_stats, "Drift Referrer Stats"),
+            ]);
+        }
+        _ => {
+            msg!("Expected the drift referrer and referrer stats or no referrer accounts");
+            return Err(VaultErrorCode::InvalidReferrer.into());
+        }
+    }
+
     initialize_vault(
         program_id,
         manager,
@@ -90,8 +115,10 @@ pub fn initialize_drift_vault_with_bulk<'a>(
         &params,
     )?;
 
+    let vault = Vault::get(vault_account)?;
+
     initialize_user_stats(
-        &program_id,
+        &vault,
         drift_program,
         drift_user_stats,
         drift_state,
@@ -99,11 +126,10 @@ pub fn initialize_drift_vault_with_bulk<'a>(
         manager,
         rent,
         system_program,
-        &params,
     )?;
 
     initialize_user(
-        program_id,
+        &vault,
         drift_program,
         drift_user,
         drift_user_stats,
@@ -112,7 +138,8 @@ pub fn initialize_drift_vault_with_bulk<'a>(
         manager,
         rent,
         system_program,
-        &params,
+        0,
+        referrer_accounts,
     )?;
 
     Ok(())
@@ -130,6 +157,7 @@ pub struct VaultParams {
     pub hurdle_rate: u32,
     pub spot_market_index: u16,
     pub permissioned: bool,
+    pub max_deposit_per_depositor: Option<u64>,
 }
 
 fn initialize_vault<'a>(
@@ -175,15 +203,18 @@ fn initialize_vault<'a>(
 
     msg!("Vault created: {}", vault_pda);
 
-    let mut vault = Vault::get(vault_account);
+    let mut vault = Vault::get(vault_account)?;
 
     let name_32 = string_to_bytes32(&params.name);
     vault.name = name_32;
     vault.pubkey = *vault_account.key;
     vault.manager = *manager.key;
+    vault.admin = *manager.key;
+    vault.operator = *manager.key;
+    vault.fee_recipient = *manager.key;
     vault.token_account = *vault_token_account.key;
     vault.user_stats = *drift_user_stats.key;
-    vault.user = *drift_user.key;
+    vault.add_sub_account(*drift_user.key, 0)?;
     vault.total_shares = 0;
     vault.lock_in_period = params.lock_in_period;
     vault.redeem_period = params.redeem_period;
@@ -198,6 +229,7 @@ fn initialize_vault<'a>(
 
     vault.init_ts = Clock::get()?.unix_timestamp as u64;
     vault.min_deposit_amount = params.min_deposit_amount;
+    vault.max_deposit_per_depositor = params.max_deposit_per_depositor.unwrap_or(0);
 
     if params.profit_share >= PERCENTAGE_PRECISION as u32 {
         msg!("profit share must be < 100%");
@@ -270,7 +302,7 @@ fn initialize_treasury<'a>(
 }
 
 fn initialize_user_stats<'a>(
-    program_id: &Pubkey,
+    vault: &Vault,
     drift_program: &'a AccountInfo<'a>,
     drift_user_stats: &'a AccountInfo<'a>,
     drift_state: &'a AccountInfo<'a>,
@@ -278,153 +310,76 @@ fn initialize_user_stats<'a>(
     manager: &'a AccountInfo<'a>,
     rent: &'a AccountInfo<'a>,
     system_program: &'a AccountInfo<'a>,
-    params: &VaultParams,
 ) -> ProgramResult {
-    let (_, vault_bump_seed) = Vault::get_pda(&params.name, program_id);
+    let accounts = InitializeUserStatsAccounts {
+        user_stats: drift_user_stats,
+        state: drift_state,
+        authority: vault_account,
+        payer: manager,
+        rent,
+        system_program,
+    };
 
-    let user_stats_accounts = vec![
-        AccountMeta {
-            pubkey: *drift_user_stats.key,
-            is_signer: false,
-            is_writable: true,
+    initialize_user_stats_verify_account_keys(
+        accounts,
+        InitializeUserStatsKeys {
+            authority: vault.pubkey,
+            ..accounts.into()
         },
-        AccountMeta {
-            pubkey: *drift_state.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_account.key,
-            is_signer: true,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *manager.key,
-            is_signer: true,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *rent.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *system_program.key,
-            is_signer: false,
-            is_writable: false,
-        },
-    ];
+    )
+    .map_err(drift_account_key_error)?;
+    initialize_user_stats_verify_writable_privileges(accounts)
+        .map_err(drift_account_privilege_error)?;
 
-    let user_stats_ix = Instruction {
-        program_id: *drift_program.key,
-        accounts: user_stats_accounts,
-        data: InitializeUserStatsIxData.try_to_vec()?,
-    };
+    let ix = initialize_user_stats_ix_with_program_id(*drift_program.key, accounts.into())?;
 
-    invoke_signed(
-        &user_stats_ix,
-        &[
-            drift_program.clone(),
-            drift_user_stats.clone(),
-            drift_state.clone(),
-            vault_account.clone(),
-            manager.clone(),
-            rent.clone(),
-            system_program.clone(),
-        ],
-        &[&[
-            b"vault",
-            params.name.as_bytes().as_ref(),
-            &[vault_bump_seed],
-        ]],
-    )?;
-
-    Ok(())
+    invoke_drift_signed(ix, accounts, &[], drift_program, vault)
 }
 
-fn initialize_user<'a>(
-    program_id: &Pubkey,
+/// Initializes a drift user for the vault under the given sub account id, referred by the
+/// referrer user and referrer stats in `referrer_accounts` if passed
+pub fn initialize_user<'a>(
+    vault: &Vault,
     drift_program: &'a AccountInfo<'a>,
     drift_user: &'a AccountInfo<'a>,
     drift_user_stats: &'a AccountInfo<'a>,
     drift_state: &'a AccountInfo<'a>,
     vault_account: &'a AccountInfo<'a>,
-    manager: &'a AccountInfo<'a>,
+    payer: &'a AccountInfo<'a>,
     rent: &'a AccountInfo<'a>,
     system_program: &'a AccountInfo<'a>,
-    params: &VaultParams,
+    sub_account_id: u16,
+    referrer_accounts: &'a [AccountInfo<'a>],
 ) -> ProgramResult {
-    let (_, vault_bump_seed) = Vault::get_pda(&params.name, program_id);
+    let accounts = InitializeUserAccounts {
+        user: drift_user,
+        user_stats: drift_user_stats,
+        state: drift_state,
+        authority: vault_account,
+        payer,
+        rent,
+        system_program,
+    };
 
-    let user_accounts = vec![
-        AccountMeta {
-            pubkey: *drift_user.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_user_stats.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_state.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_account.key,
-            is_signer: true,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *manager.key,
-            is_signer: true,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *rent.key,
-            is_signer: false,
-            is_writable: false,
+    initialize_user_verify_account_keys(
+        accounts,
+        InitializeUserKeys {
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..accounts.into()
         },
-        AccountMeta {
-            pubkey: *system_program.key,
-            is_signer: false,
-            is_writable: false,
+    )
+    .map_err(drift_account_key_error)?;
+    initialize_user_verify_writable_privileges(accounts).map_err(drift_account_privilege_error)?;
+
+    let ix = initialize_user_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        InitializeUserIxArgs {
+            sub_account_id,
+            name: vault.name,
         },
-    ];
-
-    let user_args = InitializeUserIxArgs {
-        sub_account_id: 0,
-        name: string_to_bytes32(&params.name),
-    };
-
-    let data: InitializeUserIxData = user_args.into();
-
-    let user_ix = Instruction {
-        program_id: *drift_program.key,
-        accounts: user_accounts,
-        data: data.try_to_vec()?,
-    };
-
-    invoke_signed(
-        &user_ix,
-        &[
-            drift_program.clone(),
-            drift_user.clone(),
-            drift_user_stats.clone(),
-            drift_state.clone(),
-            vault_account.clone(),
-            manager.clone(),
-            rent.clone(),
-            system_program.clone(),
-        ],
-        &[&[
-            b"vault",
-            params.name.as_bytes().as_ref(),
-            &[vault_bump_seed],
-        ]],
     )?;
 
-    Ok(())
+    invoke_drift_signed(ix, accounts, referrer_accounts, drift_program, vault)
 }
@@ -0,0 +1,133 @@
+use drift::state::spot_market::SpotMarket;
+use drift_interface::{
+    initialize_insurance_fund_stake_ix_with_program_id,
+    initialize_insurance_fund_stake_verify_account_keys,
+    initialize_insurance_fund_stake_verify_writable_privileges,
+    InitializeInsuranceFundStakeAccounts, InitializeInsuranceFundStakeIxArgs,
+    InitializeInsuranceFundStakeKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        deserialize_zero_copy, drift_account_key_error, drift_account_privilege_error,
+        invoke_drift_signed, log_accounts,
+    },
+    error::VaultErrorCode,
+    state::Vault,
+};
+
+pub fn initialize_insurance_fund_stake<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Initializing insurance fund stake...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+    let insurance_fund_stake = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+
+    let rent = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_spot_market, "Drift Spot Market"),
+        (insurance_fund_stake, "Insurance Fund Stake"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_state, "Drift State"),
+        (rent, "Rent"),
+        (system_program, "System Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.has_insurance_fund_stake() {
+        msg!("Vault already has an insurance fund stake");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    let cpi_accounts = InitializeInsuranceFundStakeAccounts {
+        spot_market: drift_spot_market,
+        insurance_fund_stake,
+        user_stats: drift_user_stats,
+        state: drift_state,
+        authority: vault_account,
+        payer: operator,
+        rent,
+        system_program,
+    };
+
+    initialize_insurance_fund_stake_verify_account_keys(
+        cpi_accounts,
+        InitializeInsuranceFundStakeKeys {
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    initialize_insurance_fund_stake_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = initialize_insurance_fund_stake_ix_with_program_id(
+        *drift_program.key,
+        cpi_accounts.into(),
+        InitializeInsuranceFundStakeIxArgs {
+            market_index: vault.spot_market_index,
+        },
+    )?;
+
+    invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)?;
+
+    // Drift checked the spot market against the vault's market index
+    let spot_market = deserialize_zero_copy::<SpotMarket>(&*drift_spot_market.try_borrow_data()?);
+
+    vault.insurance_fund_stake = *insurance_fund_stake.key;
+    vault.insurance_fund_unstaking_period =
+        spot_market.insurance_fund.unstaking_period.max(0) as u64;
+
+    msg!(
+        "insurance_fund_unstaking_period: {}",
+        vault.insurance_fund_unstaking_period
+    );
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
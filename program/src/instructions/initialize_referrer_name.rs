@@ -0,0 +1,114 @@
+use drift_interface::{
+    initialize_referrer_name_ix_with_program_id, initialize_referrer_name_verify_account_keys,
+    initialize_referrer_name_verify_writable_privileges, InitializeReferrerNameAccounts,
+    InitializeReferrerNameIxArgs, InitializeReferrerNameKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+    },
+    error::VaultErrorCode,
+    state::Vault,
+};
+
+/// Registers the vault as a drift referrer under the vault's name. Drift pays referrer rewards
+/// into sub account 0, from where SweepReferrerRewards moves them to the treasury
+pub fn initialize_referrer_name<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Initializing referrer name...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_referrer_name = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+
+    let rent = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (admin, "Admin"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_referrer_name, "Drift Referrer Name"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (rent, "Rent"),
+        (system_program, "System Program"),
+    ]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Drift only lets sub account 0 be a referrer
+    match vault.get_sub_account(0) {
+        Some(sub_account) if sub_account.user == *drift_user.key => {}
+        _ => {
+            msg!("Drift User is not the vault's sub account 0");
+            return Err(VaultErrorCode::InvalidReferrer.into());
+        }
+    }
+
+    let cpi_accounts = InitializeReferrerNameAccounts {
+        referrer_name: drift_referrer_name,
+        user: drift_user,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        payer: admin,
+        rent,
+        system_program,
+    };
+
+    initialize_referrer_name_verify_account_keys(
+        cpi_accounts,
+        InitializeReferrerNameKeys {
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    initialize_referrer_name_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = initialize_referrer_name_ix_with_program_id(
+        *drift_program.key,
+        cpi_accounts.into(),
+        InitializeReferrerNameIxArgs { name: vault.name },
+    )?;
+
+    invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)
+}
@@ -0,0 +1,180 @@
+use mpl_token_metadata::instructions::CreateMetadataAccountV3CpiBuilder;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+
+use crate::{
+    common::{
+        bytes32_to_string, get_metaplex_symbol_and_uri, get_share_mint_metadata_data, log_accounts,
+        unpack_token_account,
+    },
+    custom_validate,
+    error::VaultErrorCode,
+    state::Vault,
+};
+
+/// Creates the vault's share token mint. The mint is a pda of the vault with the vault as mint
+/// authority and the decimals of the vault's token, since a share starts out worth one token unit.
+/// Its Metaplex metadata is named after the vault and takes the symbol and uri of the vault
+/// metadata, the vault is its update authority and keeps it in sync on UpdateVaultMetadata
+pub fn initialize_share_mint<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Initializing share mint...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let share_mint = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let vault_metadata_account = next_account_info(account_info_iter)?;
+    let metadata = next_account_info(account_info_iter)?;
+
+    let token_program = next_account_info(account_info_iter)?;
+    let token_metadata_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (admin, "Admin"),
+        (vault_account, "Vault"),
+        (share_mint, "Share Mint"),
+        (vault_token_account, "Vault Token Account"),
+        (mint, "Mint"),
+        (vault_metadata_account, "Vault Metadata"),
+        (metadata, "Metadata"),
+        (token_program, "Token Program"),
+        (token_metadata_program, "Token Metadata Program"),
+        (system_program, "System Program"),
+    ]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if spl_token::id() != *token_program.key {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if mpl_token_metadata::ID != *token_metadata_program.key {
+        msg!("Invalid Token Metadata Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    custom_validate!(
+        !vault.has_share_mint(),
+        VaultErrorCode::InvalidShareMint,
+        "share mint {} already initialized",
+        vault.share_mint
+    )?;
+
+    let (share_mint_pda, share_mint_bump_seed) =
+        Vault::get_share_mint_pda(vault_account.key, program_id);
+
+    if share_mint_pda != *share_mint.key {
+        msg!("Invalid seeds for Share Mint PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.token_account != *vault_token_account.key {
+        msg!("Invalid Vault Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if unpack_token_account(vault_token_account)?.mint != *mint.key {
+        msg!("Invalid Mint");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let decimals = {
+        let data = mint.try_borrow_data()?;
+        StateWithExtensions::<Mint>::unpack(&data)?.base.decimals
+    };
+
+    let account_len = spl_token::state::Mint::LEN;
+
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(account_len);
+
+    let binding = [share_mint_bump_seed];
+    let signature_seeds = Vault::get_share_mint_signer_seeds(vault_account.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            share_mint.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            token_program.key,
+        ),
+        &[admin.clone(), share_mint.clone(), system_program.clone()],
+        &[&signature_seeds],
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_mint2(
+            token_program.key,
+            share_mint.key,
+            vault_account.key,
+            None,
+            decimals,
+        )?,
+        &[share_mint.clone(), token_program.clone()],
+    )?;
+
+    msg!(
+        "Share mint created: {}, decimals: {}",
+        share_mint.key,
+        decimals
+    );
+
+    let (symbol, uri) =
+        get_metaplex_symbol_and_uri(vault_account, vault_metadata_account, program_id);
+
+    let name = bytes32_to_string(vault.name);
+    let bump = [vault.bump];
+    let vault_signature_seeds = Vault::get_vault_signer_seeds(&name, &bump);
+
+    CreateMetadataAccountV3CpiBuilder::new(token_metadata_program)
+        .metadata(metadata)
+        .mint(share_mint)
+        .mint_authority(vault_account)
+        .payer(admin)
+        .update_authority(vault_account, true)
+        .system_program(system_program)
+        .data(get_share_mint_metadata_data(&vault, symbol, uri))
+        .is_mutable(true)
+        .invoke_signed(&[&vault_signature_seeds])?;
+
+    vault.share_mint = *share_mint.key;
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
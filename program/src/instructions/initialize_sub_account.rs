@@ -0,0 +1,108 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::log_accounts,
+    instructions::{drift_update_user_custom_margin_ratio, initialize_user},
+    state::Vault,
+};
+
+pub fn initialize_sub_account<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    sub_account_id: u16,
+) -> ProgramResult {
+    msg!("Initializing sub account...");
+    msg!("sub_account_id: {}", sub_account_id);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+
+    let rent = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_state, "Drift State"),
+        (rent, "Rent"),
+        (system_program, "System Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.user_stats != *drift_user_stats.key {
+        msg!("Invalid Drift User Stats");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vault.add_sub_account(*drift_user.key, sub_account_id)?;
+
+    Vault::save(&vault, vault_account)?;
+
+    // Drift checks the user pda and that sub account ids are created in order
+    initialize_user(
+        &vault,
+        drift_program,
+        drift_user,
+        drift_user_stats,
+        drift_state,
+        vault_account,
+        operator,
+        rent,
+        system_program,
+        sub_account_id,
+        &[],
+    )?;
+
+    // New sub accounts run under the same leverage ceiling as the others
+    if vault.custom_margin_ratio > 0 {
+        drift_update_user_custom_margin_ratio(
+            &vault,
+            sub_account_id,
+            vault.custom_margin_ratio,
+            drift_program,
+            drift_user,
+            vault_account,
+        )?;
+    }
+
+    msg!("Sub account {} created: {}", sub_account_id, drift_user.key);
+
+    Ok(())
+}
@@ -46,7 +46,7 @@ pub fn initialize_vault_depositor<'a>(
     }
 
     // Verify vault permissions
-    let vault = Vault::get(vault_account);
+    let vault = Vault::get(vault_account)?;
     if vault.permissioned {
         if vault.manager != *authority.key {
             msg!("Vault depositor can only be created by vault manager");
@@ -111,13 +111,14 @@ fn initialize_depositor<'a>(
     msg!("Vault Depositor created: {}", vault_depositor_account.key);
 
     // Create and initialize vault depositor
-    let mut vault_depositor = VaultDepositor::get(vault_depositor_account);
-
-    // Set initial state
-    vault_depositor.vault = *vault_account_pubkey;
-    vault_depositor.pubkey = *vault_depositor_account.key;
-    vault_depositor.authority = *authority.key;
-    vault_depositor.init_ts = Clock::get()?.unix_timestamp as u64;
+    let vault_depositor = VaultDepositor {
+        vault: *vault_account_pubkey,
+        pubkey: *vault_depositor_account.key,
+        authority: *authority.key,
+        init_ts: Clock::get()?.unix_timestamp as u64,
+        version: VaultDepositor::VERSION,
+        ..VaultDepositor::default()
+    };
 
     // Save state
     VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
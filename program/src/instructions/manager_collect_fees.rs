@@ -24,11 +24,11 @@ pub fn manager_collect_fees<'info>(
 
     let mut account_info_iter = &mut accounts.iter();
 
-    let manager = next_account_info(&mut account_info_iter)?;
+    let fee_recipient = next_account_info(&mut account_info_iter)?;
     let vault_account = next_account_info(&mut account_info_iter)?;
     let treasury_account = next_account_info(&mut account_info_iter)?;
 
-    let manager_token_account = next_account_info(&mut account_info_iter)?;
+    let fee_recipient_token_account = next_account_info(&mut account_info_iter)?;
     let treasury_token_account = next_account_info(&mut account_info_iter)?;
     let mint = next_account_info(&mut account_info_iter)?;
 
@@ -36,26 +36,26 @@ pub fn manager_collect_fees<'info>(
 
     log_accounts(&[
         // Vault accounts
-        (manager, "Manager"),
+        (fee_recipient, "Fee Recipient"),
         (vault_account, "Vault"),
         (treasury_account, "Treasury"),
         // Token accounts
-        (manager_token_account, "Manager Token Account"),
+        (fee_recipient_token_account, "Fee Recipient Token Account"),
         (treasury_token_account, "Treasury Token Account"),
         (mint, "Mint"),
         // System accounts
         (token_program, "Token Program"),
     ]);
 
-    if !manager.is_signer {
+    if !fee_recipient.is_signer {
         msg!("Missing required signature");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut vault = Vault::get(vault_account);
+    let mut vault = Vault::get(vault_account)?;
 
-    if vault.manager != *manager.key {
-        msg!("Invalid Vault Manager");
+    if vault.fee_recipient != *fee_recipient.key {
+        msg!("Invalid Vault Fee Recipient");
         return Err(ProgramError::InvalidArgument);
     }
 
@@ -78,7 +78,7 @@ pub fn manager_collect_fees<'info>(
     transfer_to_user_from_treasury(
         amount,
         token_program,
-        manager_token_account,
+        fee_recipient_token_account,
         treasury_token_account,
         treasury_account,
         mint,
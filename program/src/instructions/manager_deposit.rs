@@ -1,9 +1,3 @@
-use std::collections::BTreeSet;
-
-use drift::{
-    instructions::optional_accounts::{load_maps, AccountMaps},
-    state::{spot_market_map::get_writable_spot_market_set, user::User},
-};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -14,7 +8,7 @@ use solana_program::{
 };
 
 use crate::{
-    common::{deserialize_zero_copy, log_accounts, transfer_to_vault}, error::wrap_drift_error, instructions::drift_deposit, state::Vault
+    common::{calculate_vault_equity, get_market_accounts, log_accounts, transfer_to_vault, update_vault_fuel, validate_vault_not_in_liquidation}, instructions::drift_deposit, state::Vault
 };
 
 pub fn manager_deposit<'info>(
@@ -77,45 +71,33 @@ pub fn manager_deposit<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let (vault_pda, vault_bump_seed) = Vault::get_pda(&name, program_id);
+    let (vault_pda, _) = Vault::get_pda(&name, program_id);
 
     if vault_pda != *vault_account.key {
         msg!("Invalid seeds for Vault PDA");
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
-
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
-
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[7..9].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
-        clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
-    };
-
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+    let mut vault = Vault::get(vault_account)?;
 
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, &accounts[13..])?;
 
-    vault.manager_deposit(amount, vault_equity, clock.unix_timestamp)?;
+    // Fuel earned so far goes to the shares that earned it
+    update_vault_fuel(&mut vault, drift_user_stats)?;
 
-    Vault::save(&vault, vault_account)?;
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[7..9],
+        &accounts[13..],
+        clock.slot,
+    )?;
 
-    transfer_to_vault(
+    // Shares are issued for the amount the vault received, less than the amount sent for mints
+    // with a transfer fee
+    let received_amount = transfer_to_vault(
         amount,
         token_program,
         manager_token_account,
@@ -124,11 +106,14 @@ pub fn manager_deposit<'info>(
         mint,
     )?;
 
+    vault.manager_deposit(received_amount, vault_equity, clock.unix_timestamp)?;
+
+    Vault::save(&vault, vault_account)?;
+
     drift_deposit(
         &vault,
-        amount,
-        name,
-        vault_bump_seed,
+        vault.spot_market_index,
+        received_amount,
         drift_state,
         drift_user,
         drift_user_stats,
@@ -136,8 +121,8 @@ pub fn manager_deposit<'info>(
         drift_spot_market_vault,
         vault_token_account,
         token_program,
-        drift_oracle,
-        drift_spot_market,
+        get_market_accounts(&vault, &accounts[7..9], &accounts[13..]),
+        Some(mint),
         drift_program,
     )?;
 
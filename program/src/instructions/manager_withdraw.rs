@@ -1,9 +1,3 @@
-use std::collections::BTreeSet;
-
-use drift::{
-    instructions::optional_accounts::{load_maps, AccountMaps},
-    state::{spot_market_map::get_writable_spot_market_set, user::User},
-};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -14,8 +8,10 @@ use solana_program::{
 };
 
 use crate::{
-    common::{deserialize_zero_copy, log_accounts, transfer_to_user_from_vault},
-    error::wrap_drift_error,
+    common::{
+        calculate_vault_equity, get_market_accounts, log_accounts, transfer_to_user_from_vault,
+        update_vault_fuel, validate_vault_not_in_liquidation,
+    },
     instructions::drift_withdraw,
     state::Vault,
 };
@@ -79,32 +75,32 @@ pub fn manager_withdraw<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
+    let mut vault = Vault::get(vault_account)?;
 
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
 
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[7..9].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
-        clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
-    };
+    if vault.manager != *manager.key {
+        msg!("Invalid Vault Manager");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, &accounts[14..])?;
 
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+    // Fuel earned so far goes to the shares that earned it
+    update_vault_fuel(&mut vault, drift_user_stats)?;
 
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[7..9],
+        &accounts[14..],
+        clock.slot,
+    )?;
 
     vault.manager_withdraw(amount, vault_equity, clock.unix_timestamp)?;
 
@@ -112,7 +108,9 @@ pub fn manager_withdraw<'info>(
 
     drift_withdraw(
         &vault,
+        vault.spot_market_index,
         amount,
+        false,
         drift_program,
         drift_state,
         drift_user,
@@ -122,8 +120,8 @@ pub fn manager_withdraw<'info>(
         drift_signer,
         vault_token_account,
         token_program,
-        drift_oracle,
-        drift_spot_market,
+        get_market_accounts(&vault, &accounts[7..9], &accounts[14..]),
+        Some(mint),
     )?;
 
     transfer_to_user_from_vault(
@@ -0,0 +1,89 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{common::log_accounts, error::VaultErrorCode, state::Vault};
+
+/// Reallocates a vault created before the vault roles were added to the current layout, gives
+/// the manager every role and registers its drift user as primary sub account 0. Anybody can pay
+/// for it
+pub fn migrate_vault<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo<'a>]) -> ProgramResult {
+    msg!("Migrating vault...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (payer, "Payer"),
+        (system_program, "System Program"),
+    ]);
+
+    if !payer.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if vault_account.owner != program_id {
+        msg!("Vault is not owned by the program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let current_size = vault_account.data_len();
+
+    if !Vault::is_legacy(current_size) {
+        msg!("Vault is already migrated");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut vault = Vault::get_legacy(&vault_account.data.borrow())?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Vault is not initialized");
+        return Err(VaultErrorCode::UninitializedAccount.into());
+    }
+
+    msg!("Current size: {}, New size: {}", current_size, Vault::SIZE);
+
+    let rent = Rent::get()?;
+    let lamports_diff = rent
+        .minimum_balance(Vault::SIZE)
+        .saturating_sub(vault_account.lamports());
+
+    if lamports_diff > 0 {
+        msg!("Transferring {} lamports for realloc rent", lamports_diff);
+        invoke(
+            &system_instruction::transfer(payer.key, vault_account.key, lamports_diff),
+            &[payer.clone(), vault_account.clone(), system_program.clone()],
+        )?;
+    }
+
+    vault_account.realloc(Vault::SIZE, true)?;
+
+    vault.admin = vault.manager;
+    vault.operator = vault.manager;
+    vault.fee_recipient = vault.manager;
+
+    // Legacy vaults trade through drift sub account 0 only
+    let delegate = vault.delegate;
+    vault.add_sub_account(vault.user, 0)?;
+    vault.sub_accounts[0].delegate = delegate;
+
+    Vault::save(&vault, vault_account)?;
+
+    msg!("Vault migrated: {}", vault_account.key);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::log_accounts,
+    error::VaultErrorCode,
+    instructions::resize_vault_depositor_account,
+    state::{DepositInfo, LegacyVaultDepositor, Vault, VaultDepositor},
+};
+
+/// Rewrites a vault depositor written before deposits had a cost basis with the current layout,
+/// reallocating the account for the larger deposits. Anybody can pay for it
+pub fn migrate_vault_depositor<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> ProgramResult {
+    msg!("Migrating vault depositor...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (payer, "Payer"),
+        (system_program, "System Program"),
+    ]);
+
+    if !payer.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if vault_depositor_account.owner != program_id {
+        msg!("Vault Depositor is not owned by the program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if !VaultDepositor::is_legacy(&vault_depositor_account.data.borrow()) {
+        msg!("Vault Depositor is already migrated");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let legacy_vault_depositor = LegacyVaultDepositor::get(&vault_depositor_account.data.borrow())?;
+
+    if legacy_vault_depositor.pubkey != *vault_depositor_account.key {
+        msg!("Vault Depositor is not initialized");
+        return Err(VaultErrorCode::UninitializedAccount.into());
+    }
+
+    let (vault_depositor_pda, _) = VaultDepositor::get_pda(
+        vault_account.key,
+        &legacy_vault_depositor.authority,
+        program_id,
+    );
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault_depositor = legacy_vault_depositor.into_vault_depositor(&vault)?;
+
+    let account_size = vault_depositor_account.data_len();
+
+    if vault_depositor.does_need_resize(account_size) {
+        // At most one deposit more than needed
+        let additional_items = (vault_depositor.get_current_size() + DepositInfo::SIZE
+            - account_size)
+            / DepositInfo::SIZE
+            + 1;
+
+        resize_vault_depositor_account(
+            vault_depositor_account,
+            payer,
+            system_program,
+            additional_items,
+        )?;
+    }
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+
+    msg!(
+        "Vault Depositor migrated: {}, deposits: {}",
+        vault_depositor_account.key,
+        vault_depositor.deposits.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use mpl_token_metadata::{
+    instructions::{CreateMasterEditionV3CpiBuilder, CreateMetadataAccountV3CpiBuilder},
+    types::DataV2,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+
+use crate::{
+    common::{bytes32_to_string, get_metaplex_symbol_and_uri, log_accounts, mint_shares_to_user},
+    state::{LotReceipt, Vault, VaultDepositor},
+};
+
+/// Moves a locked lot of the authority's vault depositor into a receipt NFT minted to the
+/// authority's associated token account. The receipt's metadata uses the vault's name and, if the
+/// vault has metadata, its symbol and uri. The lot's shares and unlock timestamp are in the
+/// lot receipt pda of the mint
+pub fn mint_lot_receipt<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    deposit_ts: i64,
+) -> ProgramResult {
+    msg!("Minting lot receipt...");
+    msg!("deposit_ts: {}", deposit_ts);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let receipt_mint = next_account_info(account_info_iter)?;
+    let receipt_token_account = next_account_info(account_info_iter)?;
+    let lot_receipt_account = next_account_info(account_info_iter)?;
+    let vault_metadata_account = next_account_info(account_info_iter)?;
+    let metadata = next_account_info(account_info_iter)?;
+    let master_edition = next_account_info(account_info_iter)?;
+
+    let token_program = next_account_info(account_info_iter)?;
+    let associated_token_program = next_account_info(account_info_iter)?;
+    let token_metadata_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (authority, "Authority"),
+        (receipt_mint, "Receipt Mint"),
+        (receipt_token_account, "Receipt Token Account"),
+        (lot_receipt_account, "Lot Receipt"),
+        (vault_metadata_account, "Vault Metadata"),
+        (metadata, "Metadata"),
+        (master_edition, "Master Edition"),
+        (token_program, "Token Program"),
+        (associated_token_program, "Associated Token Program"),
+        (token_metadata_program, "Token Metadata Program"),
+        (system_program, "System Program"),
+    ]);
+
+    if !authority.is_signer || !receipt_mint.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if spl_token::id() != *token_program.key {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if spl_associated_token_account::id() != *associated_token_program.key {
+        msg!("Invalid Associated Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if mpl_token_metadata::ID != *token_metadata_program.key {
+        msg!("Invalid Token Metadata Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, authority.key, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (lot_receipt_pda, lot_receipt_bump_seed) =
+        LotReceipt::get_pda(receipt_mint.key, program_id);
+
+    if lot_receipt_pda != *lot_receipt_account.key {
+        msg!("Invalid seeds for Lot Receipt PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if get_associated_token_address(authority.key, receipt_mint.key) != *receipt_token_account.key {
+        msg!("Invalid Receipt Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    let (lot, net_deposits) =
+        vault_depositor.take_lot(deposit_ts, &mut vault, clock.unix_timestamp)?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    Vault::save(&vault, vault_account)?;
+
+    let lot_receipt = LotReceipt {
+        vault: vault.pubkey,
+        pubkey: lot_receipt_pda,
+        mint: *receipt_mint.key,
+        shares: lot.shares,
+        ts: lot.ts,
+        unlock_ts: lot.ts.saturating_add(vault.lock_in_period as i64),
+        net_deposits,
+        cost_basis: lot.cost_basis,
+        bump: lot_receipt_bump_seed,
+    };
+
+    let rent = Rent::get()?;
+
+    let binding = [lot_receipt_bump_seed];
+    let signature_seeds =
+        LotReceipt::get_lot_receipt_signer_seeds(receipt_mint.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            authority.key,
+            lot_receipt_account.key,
+            rent.minimum_balance(LotReceipt::SIZE),
+            LotReceipt::SIZE.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            authority.clone(),
+            lot_receipt_account.clone(),
+            system_program.clone(),
+        ],
+        &[&signature_seeds],
+    )?;
+
+    lot_receipt.save(lot_receipt_account)?;
+
+    // The receipt is a 0 decimals mint with the vault as mint authority until the master
+    // edition takes it over, which caps the supply at the one token minted here
+    invoke(
+        &system_instruction::create_account(
+            authority.key,
+            receipt_mint.key,
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN.try_into().unwrap(),
+            token_program.key,
+        ),
+        &[
+            authority.clone(),
+            receipt_mint.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_mint2(
+            token_program.key,
+            receipt_mint.key,
+            vault_account.key,
+            None,
+            0,
+        )?,
+        &[receipt_mint.clone(), token_program.clone()],
+    )?;
+
+    invoke(
+        &create_associated_token_account(
+            authority.key,
+            authority.key,
+            receipt_mint.key,
+            token_program.key,
+        ),
+        &[
+            authority.clone(),
+            receipt_token_account.clone(),
+            authority.clone(),
+            receipt_mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+
+    mint_shares_to_user(
+        &vault,
+        1,
+        token_program,
+        receipt_mint,
+        receipt_token_account,
+        vault_account,
+    )?;
+
+    let (symbol, uri) =
+        get_metaplex_symbol_and_uri(vault_account, vault_metadata_account, program_id);
+
+    let name = bytes32_to_string(vault.name);
+    let bump = [vault.bump];
+    let vault_signature_seeds = Vault::get_vault_signer_seeds(&name, &bump);
+
+    CreateMetadataAccountV3CpiBuilder::new(token_metadata_program)
+        .metadata(metadata)
+        .mint(receipt_mint)
+        .mint_authority(vault_account)
+        .payer(authority)
+        .update_authority(vault_account, true)
+        .system_program(system_program)
+        .data(DataV2 {
+            name: name.clone(),
+            symbol,
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        })
+        .is_mutable(false)
+        .invoke_signed(&[&vault_signature_seeds])?;
+
+    CreateMasterEditionV3CpiBuilder::new(token_metadata_program)
+        .edition(master_edition)
+        .mint(receipt_mint)
+        .update_authority(vault_account)
+        .mint_authority(vault_account)
+        .payer(authority)
+        .metadata(metadata)
+        .token_program(token_program)
+        .system_program(system_program)
+        .max_supply(0)
+        .invoke_signed(&[&vault_signature_seeds])?;
+
+    msg!(
+        "Lot receipt minted: {}, shares: {}, unlock_ts: {}",
+        receipt_mint.key,
+        lot_receipt.shares,
+        lot_receipt.unlock_ts
+    );
+
+    Ok(())
+}
@@ -1,24 +1,96 @@
+pub mod add_insurance_fund_stake;
+pub mod cancel_orders;
 pub mod cancel_withdraw_request;
 pub mod deposit;
+pub mod deposit_collateral;
 pub mod initialize_drift_vault_with_bulk;
+pub mod initialize_insurance_fund_stake;
+pub mod initialize_referrer_name;
+pub mod initialize_share_mint;
+pub mod initialize_sub_account;
 pub mod initialize_vault_depositor;
 pub mod manager_collect_fees;
 pub mod manager_deposit;
 pub mod manager_withdraw;
+pub mod migrate_vault;
+pub mod migrate_vault_depositor;
+pub mod mint_lot_receipt;
+pub mod place_orders;
+pub mod preview;
+pub mod record_vault_liquidation;
+pub mod redeem_lot_receipt;
+pub mod refresh_depositor_stats;
+pub mod refresh_share_price;
+pub mod remove_insurance_fund_stake;
+pub mod request_remove_insurance_fund_stake;
 pub mod request_withdraw;
 pub mod reset_delegate;
+pub mod set_primary_sub_account;
+pub mod settle_vault_pnl;
+pub mod swap_collateral;
+pub mod sweep_referrer_rewards;
+pub mod transfer_depositor_position;
+pub mod unwrap_shares;
+pub mod update_collateral_markets;
 pub mod update_delegate;
+pub mod update_depositor_cap;
+pub mod update_fuel_bonus;
+pub mod update_high_leverage_mode;
+pub mod update_lot_receipts;
+pub mod update_margin_ratio;
+pub mod update_margin_trading_enabled;
+pub mod update_nav_history;
+pub mod update_trading_policy;
 pub mod update_vault;
+pub mod update_vault_metadata;
+pub mod update_vault_roles;
 pub mod withdraw;
+pub mod wrap_shares;
 
+pub use add_insurance_fund_stake::*;
+pub use cancel_orders::*;
 pub use cancel_withdraw_request::*;
 pub use deposit::*;
+pub use deposit_collateral::*;
 pub use initialize_drift_vault_with_bulk::*;
+pub use initialize_insurance_fund_stake::*;
+pub use initialize_referrer_name::*;
+pub use initialize_share_mint::*;
+pub use initialize_sub_account::*;
 pub use initialize_vault_depositor::*;
 pub use manager_collect_fees::*;
 pub use manager_withdraw::*;
+pub use migrate_vault::*;
+pub use migrate_vault_depositor::*;
+pub use mint_lot_receipt::*;
+pub use place_orders::*;
+pub use preview::*;
+pub use record_vault_liquidation::*;
+pub use redeem_lot_receipt::*;
+pub use refresh_depositor_stats::*;
+pub use refresh_share_price::*;
+pub use remove_insurance_fund_stake::*;
+pub use request_remove_insurance_fund_stake::*;
 pub use request_withdraw::*;
 pub use reset_delegate::*;
+pub use set_primary_sub_account::*;
+pub use settle_vault_pnl::*;
+pub use swap_collateral::*;
+pub use sweep_referrer_rewards::*;
+pub use transfer_depositor_position::*;
+pub use unwrap_shares::*;
+pub use update_collateral_markets::*;
 pub use update_delegate::*;
+pub use update_depositor_cap::*;
+pub use update_fuel_bonus::*;
+pub use update_high_leverage_mode::*;
+pub use update_lot_receipts::*;
+pub use update_margin_ratio::*;
+pub use update_margin_trading_enabled::*;
+pub use update_nav_history::*;
+pub use update_trading_policy::*;
 pub use update_vault::*;
+pub use update_vault_metadata::*;
+pub use update_vault_roles::*;
 pub use withdraw::*;
+pub use wrap_shares::*;
@@ -0,0 +1,179 @@
+use std::collections::BTreeSet;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use drift::{
+    instructions::optional_accounts::{load_maps, AccountMaps},
+    state::{order_params::OrderParams, user::User},
+};
+use drift_interface::{
+    place_orders_ix_with_program_id, place_orders_verify_account_keys,
+    place_orders_verify_writable_privileges, PlaceOrdersAccounts, PlaceOrdersIxArgs,
+    PlaceOrdersKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        deserialize_zero_copy, drift_account_key_error, drift_account_privilege_error,
+        invoke_drift_signed, log_accounts,
+    },
+    state::{TradingPolicy, Vault},
+};
+
+pub fn place_orders<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    params: Vec<OrderParams>,
+) -> ProgramResult {
+    msg!("Placing {} orders...", params.len());
+
+    let clock = &Clock::get()?;
+
+    let mut account_info_iter = accounts.iter();
+
+    let trader = next_account_info(&mut account_info_iter)?;
+    let vault_account = next_account_info(&mut account_info_iter)?;
+    let trading_policy_account = next_account_info(&mut account_info_iter)?;
+
+    let drift_program = next_account_info(&mut account_info_iter)?;
+    let drift_state = next_account_info(&mut account_info_iter)?;
+    let drift_user = next_account_info(&mut account_info_iter)?;
+
+    // Oracles, spot markets and perp markets for drift and the policy checks
+    let remaining_accounts = &accounts[6..];
+
+    log_accounts(&[
+        (trader, "Trader"),
+        (vault_account, "Vault"),
+        (trading_policy_account, "Trading Policy"),
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+    ]);
+
+    if !trader.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let sub_account = match vault.get_sub_account_by_user(drift_user.key) {
+        Some(sub_account) => sub_account,
+        None => {
+            msg!("Drift User is not a vault sub account");
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    if sub_account.delegate != *trader.key && vault.operator != *trader.key {
+        msg!("Trader must be the sub account delegate or vault operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (trading_policy_pda, _) = TradingPolicy::get_pda(vault_account.key, program_id);
+
+    if trading_policy_pda != *trading_policy_account.key {
+        msg!("Invalid seeds for Trading Policy PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let trading_policy = TradingPolicy::get(trading_policy_account);
+
+    {
+        // Load maps with proper account references and types
+        let AccountMaps {
+            perp_market_map,
+            spot_market_map,
+            mut oracle_map,
+        } = match load_maps(
+            &mut remaining_accounts.iter().peekable(),
+            &BTreeSet::new(),
+            &BTreeSet::new(),
+            clock.slot,
+            None,
+        ) {
+            Ok(maps) => maps,
+            Err(e) => return Err(ProgramError::Custom(e as u32)),
+        };
+
+        // User details
+        let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+
+        trading_policy.validate_orders(
+            &params,
+            &user,
+            &perp_market_map,
+            &spot_market_map,
+            &mut oracle_map,
+        )?;
+    }
+
+    drift_place_orders(
+        &vault,
+        params,
+        drift_program,
+        drift_state,
+        drift_user,
+        vault_account,
+        remaining_accounts,
+    )
+}
+
+/// Places orders on drift for the vault's user
+pub fn drift_place_orders<'a>(
+    vault: &Vault,
+    params: Vec<OrderParams>,
+    // Individual accounts
+    drift_program: &AccountInfo<'a>,
+    drift_state: &AccountInfo<'a>,
+    drift_user: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    remaining_accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Place orders CPI to Drift...");
+
+    let accounts = PlaceOrdersAccounts {
+        state: drift_state,
+        user: drift_user,
+        authority: vault_account,
+    };
+
+    place_orders_verify_account_keys(
+        accounts,
+        PlaceOrdersKeys {
+            authority: vault.pubkey,
+            ..accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    place_orders_verify_writable_privileges(accounts).map_err(drift_account_privilege_error)?;
+
+    // drift_interface generates its order params from the drift idl, the borsh layout is the same
+    let params = Vec::<drift_interface::OrderParams>::try_from_slice(&params.try_to_vec()?)?;
+
+    let ix = place_orders_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        PlaceOrdersIxArgs { params },
+    )?;
+
+    invoke_drift_signed(ix, accounts, remaining_accounts, drift_program, vault)
+}
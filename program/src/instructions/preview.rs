@@ -0,0 +1,395 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use drift::state::spot_market::SpotMarket;
+use serde::{Deserialize, Serialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        calculate_amount_to_shares, calculate_vault_equity, deserialize_zero_copy,
+        get_share_token_balance, get_transfer_fee, log_accounts, log_params,
+        validate_vault_not_in_liquidation,
+    },
+    error::VaultErrorCode,
+    state::{Vault, VaultDepositor},
+};
+
+/// Returned by PreviewDeposit
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct DepositPreview {
+    /// The shares the deposit issues
+    pub shares: u128,
+    /// The amount credited after transfer fees and the management fee
+    pub amount: u64,
+    /// The management fee taken from the deposit
+    pub management_fee: u64,
+    /// The vault equity the shares are priced at
+    pub vault_equity: u64,
+}
+
+/// Returned by PreviewWithdraw
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct WithdrawPreview {
+    /// The shares a withdraw request for the amount locks
+    pub shares: u128,
+    /// The amount paid out after fees
+    pub amount: u64,
+    /// The management fee taken from the withdraw
+    pub management_fee: u64,
+    /// The profit share taken from the withdraw
+    pub profit_share: u64,
+    /// The vault equity the shares are priced at
+    pub vault_equity: u64,
+}
+
+/// Returned by ConvertToShares and ConvertToAssets
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct Conversion {
+    /// The shares converted from or to
+    pub shares: u128,
+    /// The amount of the vault's token converted from or to
+    pub amount: u64,
+    /// The vault equity the shares are priced at
+    pub vault_equity: u64,
+}
+
+/// Returns the shares a deposit of amount would issue to the depositor and the management fee it
+/// pays. Runs Deposit's checks and share accounting on copies of the vault and the depositor, with
+/// the amount reduced by the mint's transfer fees the way Deposit credits it. A depositor that is
+/// not initialized yet previews as a new one. Read only, meant to be simulated
+pub fn preview_deposit<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+) -> ProgramResult {
+    msg!("Previewing deposit...");
+    msg!("amount: {}", amount);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault(vault_account)?;
+    let mut vault_depositor =
+        load_vault_depositor(program_id, vault_account, vault_depositor_account)?;
+
+    let market_accounts = &accounts[3..];
+
+    let vault_equity = load_vault_equity(&vault, market_accounts)?;
+
+    validate_preview_accounts(&vault, mint, market_accounts)?;
+
+    // The vault receives the amount less the transfer fee, and drift what the vault received less
+    // the transfer fee again
+    let received_amount = amount.saturating_sub(get_transfer_fee(mint, amount)?);
+    let drift_transfer_fee = get_transfer_fee(mint, received_amount)?;
+
+    let vault_shares_before = vault_depositor.vault_shares;
+
+    let (deposit_amount, management_fee) = vault_depositor.deposit(
+        received_amount.saturating_sub(drift_transfer_fee),
+        vault_equity,
+        &mut vault,
+        clock.unix_timestamp,
+    )?;
+
+    return_preview(&DepositPreview {
+        shares: vault_depositor
+            .vault_shares
+            .saturating_sub(vault_shares_before),
+        amount: deposit_amount,
+        management_fee,
+        vault_equity,
+    })
+}
+
+/// Returns what Withdraw pays the depositor for its pending withdraw request once the redeem
+/// period ends, or for a request of amount made now when amount is not 0, after the management
+/// fee, the depositor's profit share and the mint's transfer fees. Runs RequestWithdraw's and
+/// Withdraw's share accounting on copies of the vault and the depositor at the current vault
+/// equity. Withdraw settles the perp pnl first, which moves it into the quote balance without
+/// changing the equity, so it is not simulated. Read only, meant to be simulated
+pub fn preview_withdraw<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+) -> ProgramResult {
+    msg!("Previewing withdraw...");
+    msg!("amount: {}", amount);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault(vault_account)?;
+    let mut vault_depositor =
+        load_vault_depositor(program_id, vault_account, vault_depositor_account)?;
+
+    // Vaults with a share mint take the authority's share token account, the share tokens can be
+    // requested on top of the unlocked shares
+    let (token_shares, market_accounts) = if vault.has_share_mint() {
+        let share_token_account = next_account_info(account_info_iter)?;
+
+        log_accounts(&[(share_token_account, "Share Token Account")]);
+
+        let token_shares =
+            get_share_token_balance(&vault, share_token_account, &vault_depositor.authority)?
+                as u128;
+
+        (token_shares, &accounts[4..])
+    } else {
+        (0, &accounts[3..])
+    };
+
+    let vault_equity = load_vault_equity(&vault, market_accounts)?;
+
+    validate_preview_accounts(&vault, mint, market_accounts)?;
+
+    if amount > 0 {
+        vault_depositor.request_withdraw(
+            amount,
+            vault_equity,
+            token_shares,
+            &mut vault,
+            clock.unix_timestamp,
+        )?;
+    }
+
+    // The request is paid out once its redeem period ends
+    let now = clock.unix_timestamp.max(
+        vault_depositor
+            .last_withdraw_request
+            .ts
+            .saturating_add(vault.get_redeem_period() as i64),
+    );
+
+    // The requested shares the ledger doesn't hold are share tokens Withdraw burns and unwraps
+    if vault.has_share_mint() {
+        let token_shares = vault_depositor
+            .last_withdraw_request
+            .shares
+            .saturating_sub(vault_depositor.vault_shares);
+
+        if token_shares > 0 {
+            let amount = u64::try_from(token_shares).map_err(|_| VaultErrorCode::MathError)?;
+
+            vault_depositor.unwrap_shares(amount, &mut vault, now)?;
+        }
+    }
+
+    let shares = vault_depositor.last_withdraw_request.shares;
+    let manager_total_fee_before = vault.manager_total_fee;
+    let manager_total_profit_share_before = vault.manager_total_profit_share;
+
+    let (withdraw_amount, total_deductions) =
+        vault_depositor.withdraw(vault_equity, &mut vault, now)?;
+
+    // Mints with a transfer fee deliver less from drift to the vault and again from the vault to
+    // the depositor, the depositor bears both
+    let drift_withdraw_amount = withdraw_amount.saturating_add(total_deductions);
+    let received_amount = drift_withdraw_amount
+        .saturating_sub(get_transfer_fee(mint, drift_withdraw_amount)?)
+        .saturating_sub(total_deductions);
+    let amount = received_amount.saturating_sub(get_transfer_fee(mint, received_amount)?);
+
+    return_preview(&WithdrawPreview {
+        shares,
+        amount,
+        management_fee: vault
+            .manager_total_fee
+            .saturating_sub(manager_total_fee_before),
+        profit_share: vault
+            .manager_total_profit_share
+            .saturating_sub(manager_total_profit_share_before),
+        vault_equity,
+    })
+}
+
+/// Returns the shares amount is worth at the current vault equity, without fees
+pub fn convert_to_shares<'info>(
+    _program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+) -> ProgramResult {
+    msg!("Converting to shares...");
+    msg!("amount: {}", amount);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let (vault, vault_equity) = load_vault_and_equity(vault_account, &accounts[1..])?;
+
+    let shares = calculate_amount_to_shares(amount, vault.total_shares, vault_equity)?;
+
+    return_preview(&Conversion {
+        shares,
+        amount,
+        vault_equity,
+    })
+}
+
+/// Returns the amount shares are worth at the current vault equity, without fees
+pub fn convert_to_assets<'info>(
+    _program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    shares: u128,
+) -> ProgramResult {
+    msg!("Converting to assets...");
+    msg!("shares: {}", shares);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let (vault, vault_equity) = load_vault_and_equity(vault_account, &accounts[1..])?;
+
+    let amount =
+        VaultDepositor::calculate_shares_to_amount(shares, vault.total_shares, vault_equity)?;
+
+    return_preview(&Conversion {
+        shares,
+        amount,
+        vault_equity,
+    })
+}
+
+/// Loads the vault and its equity the way deposits and withdrawals do. The accounts are the drift
+/// user of the primary sub account, the vault's oracle and spot market and then the remaining
+/// accounts of calculate_vault_equity
+fn load_vault_and_equity<'info>(
+    vault_account: &AccountInfo,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<(Vault, u64), ProgramError> {
+    let vault = load_vault(vault_account)?;
+    let vault_equity = load_vault_equity(&vault, accounts)?;
+
+    Ok((vault, vault_equity))
+}
+
+fn load_vault(vault_account: &AccountInfo) -> Result<Vault, ProgramError> {
+    log_accounts(&[(vault_account, "Vault")]);
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(vault)
+}
+
+/// Loads the vault depositor, a depositor that is not initialized yet is a new one
+fn load_vault_depositor(
+    program_id: &Pubkey,
+    vault_account: &AccountInfo,
+    vault_depositor_account: &AccountInfo,
+) -> Result<VaultDepositor, ProgramError> {
+    log_accounts(&[(vault_depositor_account, "Vault Depositor")]);
+
+    if vault_depositor_account.data_is_empty() {
+        return Ok(VaultDepositor::default());
+    }
+
+    if vault_depositor_account.owner != program_id {
+        msg!("Vault Depositor is not owned by the program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, &vault_depositor.authority, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(vault_depositor)
+}
+
+/// Loads the vault equity from the drift user of the primary sub account, the vault's oracle and
+/// spot market and then the remaining accounts of calculate_vault_equity
+fn load_vault_equity<'info>(
+    vault: &Vault,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<u64, ProgramError> {
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (drift_user, "Drift User"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+    ]);
+
+    let vault_equity = calculate_vault_equity(
+        vault,
+        drift_user,
+        &accounts[1..3],
+        &accounts[3..],
+        clock.slot,
+    )?;
+
+    msg!("vault_equity: {}", vault_equity);
+
+    Ok(vault_equity)
+}
+
+/// Fails like deposits and withdrawals do while drift liquidates the vault, and checks the mint is
+/// the token of the vault's spot market so its transfer fee is the one charged
+fn validate_preview_accounts(
+    vault: &Vault,
+    mint: &AccountInfo,
+    market_accounts: &[AccountInfo],
+) -> ProgramResult {
+    log_accounts(&[(mint, "Mint")]);
+
+    validate_vault_not_in_liquidation(vault, &market_accounts[0], &market_accounts[3..])?;
+
+    let drift_spot_market = &market_accounts[2];
+
+    if *drift_spot_market.owner != drift::ID {
+        msg!("Drift Spot Market is not owned by the drift program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let spot_market = deserialize_zero_copy::<SpotMarket>(&*drift_spot_market.try_borrow_data()?);
+
+    if spot_market.market_index != vault.spot_market_index || spot_market.mint != *mint.key {
+        msg!("Invalid Mint");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn return_preview<T: BorshSerialize + Serialize>(preview: &T) -> ProgramResult {
+    log_params(preview);
+
+    set_return_data(&preview.try_to_vec()?);
+
+    Ok(())
+}
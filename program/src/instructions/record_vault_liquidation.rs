@@ -0,0 +1,121 @@
+use drift::state::user::User;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        deserialize_zero_copy, log_accounts, log_data, log_params, validate_sub_account_users,
+    },
+    state::{Vault, VaultLiquidationAction, VaultLiquidationRecord},
+};
+
+/// Records on the vault when a drift liquidation of one of its sub accounts starts, when a sub
+/// account goes bankrupt and when the liquidation ends. Permissionless, only logs a record when
+/// the status changed
+pub fn record_vault_liquidation<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Recording vault liquidation...");
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+
+    // The drift users of all sub accounts
+    let drift_users = &accounts[1..];
+
+    log_accounts(&[(vault_account, "Vault")]);
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    let mut records = vec![];
+
+    let mut in_liquidation = false;
+
+    for (sub_account, drift_user) in vault.get_sub_accounts().iter().zip(drift_users.iter()) {
+        let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+
+        if !user.is_being_liquidated() && !user.is_bankrupt() {
+            continue;
+        }
+
+        msg!(
+            "sub account {}: being_liquidated: {}, bankrupt: {}",
+            sub_account.sub_account_id,
+            user.is_being_liquidated(),
+            user.is_bankrupt()
+        );
+
+        if !in_liquidation && vault.liquidation_start_ts == 0 {
+            records.push((VaultLiquidationAction::Started, *sub_account));
+        }
+
+        if user.is_bankrupt() && !vault.bankrupt {
+            records.push((VaultLiquidationAction::Bankrupt, *sub_account));
+        }
+
+        in_liquidation = true;
+    }
+
+    if !in_liquidation && vault.liquidation_start_ts != 0 {
+        if let Some(sub_account) = vault.get_sub_account(vault.primary_sub_account_id) {
+            records.push((VaultLiquidationAction::Ended, *sub_account));
+        }
+    }
+
+    if records.is_empty() {
+        msg!("Vault liquidation status unchanged");
+        return Ok(());
+    }
+
+    for (action, sub_account) in records.into_iter() {
+        match action {
+            VaultLiquidationAction::Started => {
+                vault.liquidation_start_ts = clock.unix_timestamp as u64;
+            }
+            VaultLiquidationAction::Bankrupt => {
+                vault.bankrupt = true;
+            }
+            VaultLiquidationAction::Ended => {}
+        }
+
+        let ended = matches!(action, VaultLiquidationAction::Ended);
+
+        let record = VaultLiquidationRecord {
+            ts: clock.unix_timestamp,
+            vault: vault.pubkey,
+            action,
+            sub_account_id: sub_account.sub_account_id,
+            user: sub_account.user,
+            liquidation_start_ts: vault.liquidation_start_ts,
+        };
+
+        log_data(&record)?;
+        log_params(&record);
+
+        if ended {
+            vault.liquidation_start_ts = 0;
+            vault.bankrupt = false;
+        }
+    }
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
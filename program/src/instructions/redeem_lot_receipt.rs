@@ -0,0 +1,127 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{burn_shares_from_user, close_account, log_accounts},
+    custom_validate,
+    error::VaultErrorCode,
+    instructions::resize_vault_depositor_account,
+    state::{LotReceipt, Vault, VaultDepositor},
+};
+
+/// Burns a receipt NFT held by the authority and moves its lot into the authority's vault
+/// depositor, keeping the lot's deposit timestamp. The lot receipt is closed and its rent goes to
+/// the authority
+pub fn redeem_lot_receipt<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> ProgramResult {
+    msg!("Redeeming lot receipt...");
+
+    let clock = &Clock::get()?;
+
+    let mut account_info_iter = accounts.iter();
+
+    let vault_account = next_account_info(&mut account_info_iter)?;
+    let vault_depositor_account = next_account_info(&mut account_info_iter)?;
+    let authority = next_account_info(&mut account_info_iter)?;
+    let receipt_mint = next_account_info(&mut account_info_iter)?;
+    let receipt_token_account = next_account_info(&mut account_info_iter)?;
+    let lot_receipt_account = next_account_info(&mut account_info_iter)?;
+
+    let token_program = next_account_info(&mut account_info_iter)?;
+    let system_program = next_account_info(&mut account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (authority, "Authority"),
+        (receipt_mint, "Receipt Mint"),
+        (receipt_token_account, "Receipt Token Account"),
+        (lot_receipt_account, "Lot Receipt"),
+        (token_program, "Token Program"),
+        (system_program, "System Program"),
+    ]);
+
+    if !authority.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if spl_token::id() != *token_program.key {
+        msg!("Invalid Token Program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, authority.key, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (lot_receipt_pda, _) = LotReceipt::get_pda(receipt_mint.key, program_id);
+
+    if lot_receipt_pda != *lot_receipt_account.key {
+        msg!("Invalid seeds for Lot Receipt PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let lot_receipt = LotReceipt::get(lot_receipt_account);
+
+    custom_validate!(
+        lot_receipt.pubkey == *lot_receipt_account.key && lot_receipt.mint == *receipt_mint.key,
+        VaultErrorCode::InvalidLotReceipt,
+        "lot receipt {} is not initialized",
+        lot_receipt_account.key
+    )?;
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    if vault_depositor.pubkey != *vault_depositor_account.key {
+        msg!("Vault Depositor is not initialized");
+        return Err(VaultErrorCode::UninitializedAccount.into());
+    }
+
+    if vault_depositor.does_need_resize(vault_depositor_account.data_len()) {
+        resize_vault_depositor_account(vault_depositor_account, authority, system_program, 2)?;
+    }
+
+    // The token program fails the burn unless the authority holds the receipt
+    burn_shares_from_user(
+        1,
+        token_program,
+        receipt_mint,
+        receipt_token_account,
+        authority,
+    )?;
+
+    vault_depositor.redeem_lot(&lot_receipt, &mut vault, clock.unix_timestamp)?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    Vault::save(&vault, vault_account)?;
+
+    close_account(lot_receipt_account, authority)?;
+
+    msg!(
+        "Lot receipt redeemed: {}, shares: {}",
+        receipt_mint.key,
+        lot_receipt.shares
+    );
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{calculate_vault_equity, log_accounts},
+    error::VaultErrorCode,
+    state::{Vault, VaultDepositor},
+};
+
+/// Values a depositor's shares at the current vault equity and stores its unrealized pnl against
+/// the cost basis of its lots. Anybody can crank it
+pub fn refresh_depositor_stats<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> ProgramResult {
+    msg!("Refreshing depositor stats...");
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (drift_user, "Drift User"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+    ]);
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    if vault_depositor.pubkey != *vault_depositor_account.key {
+        msg!("Vault Depositor is not initialized");
+        return Err(VaultErrorCode::UninitializedAccount.into());
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, &vault_depositor.authority, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[3..5],
+        &accounts[5..],
+        clock.slot,
+    )?;
+
+    msg!("vault_equity: {}", vault_equity);
+
+    vault_depositor.update_unrealized_pnl(vault_equity, &vault, clock.unix_timestamp)?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+
+use crate::{
+    common::{
+        calculate_vault_equity_and_oracle_confidence, log_accounts, update_share_price,
+        validate_vault_not_in_liquidation,
+    },
+    state::{NavHistory, NavSnapshot, SharePrice, Vault},
+};
+
+/// Publishes the vault's share price at the current vault equity and appends it to the vault's nav
+/// history once its interval passed. Anybody can crank it, the first call creates the share price
+/// pda paid by the payer
+pub fn refresh_share_price<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> ProgramResult {
+    msg!("Refreshing share price...");
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let payer = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let share_price_account = next_account_info(account_info_iter)?;
+
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+    let nav_history_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (payer, "Payer"),
+        (vault_account, "Vault"),
+        (share_price_account, "Share Price"),
+        (drift_user, "Drift User"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+        (system_program, "System Program"),
+        (nav_history_account, "Nav History"),
+    ]);
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (share_price_pda, share_price_bump_seed) =
+        SharePrice::get_pda(vault_account.key, program_id);
+
+    if share_price_pda != *share_price_account.key {
+        msg!("Invalid seeds for Share Price PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if share_price_account.data_is_empty() {
+        if !payer.is_signer {
+            msg!("Missing required signature");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        initialize_share_price(
+            program_id,
+            payer,
+            vault_account,
+            share_price_account,
+            system_program,
+            share_price_bump_seed,
+        )?;
+    }
+
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, &accounts[8..])?;
+
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let (vault_equity, oracle_confidence) = calculate_vault_equity_and_oracle_confidence(
+        &vault,
+        drift_user,
+        &accounts[4..6],
+        &accounts[8..],
+        clock.slot,
+    )?;
+
+    update_share_price(
+        program_id,
+        &vault,
+        share_price_account,
+        vault_equity,
+        oracle_confidence,
+        clock.slot,
+        clock.unix_timestamp,
+    )?;
+
+    let (nav_history_pda, _) = NavHistory::get_pda(vault_account.key, program_id);
+
+    if nav_history_pda != *nav_history_account.key {
+        msg!("Invalid seeds for Nav History PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The vault admin opts into the history with UpdateNavHistory
+    if !nav_history_account.data_is_empty() {
+        let mut nav_history = NavHistory::get(nav_history_account);
+
+        if nav_history.append(NavSnapshot {
+            ts: clock.unix_timestamp,
+            equity: vault_equity,
+            total_shares: vault.total_shares,
+            net_deposits: vault.net_deposits,
+        }) {
+            nav_history.save(nav_history_account)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_share_price<'a>(
+    program_id: &Pubkey,
+    payer: &'a AccountInfo<'a>,
+    vault_account: &'a AccountInfo<'a>,
+    share_price_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    share_price_bump_seed: u8,
+) -> ProgramResult {
+    let account_len: usize = SharePrice::SIZE;
+
+    let rent_cal = Rent::get()?;
+    let rent_lamports = rent_cal.minimum_balance(account_len);
+
+    let binding = [share_price_bump_seed];
+    let signature_seeds =
+        SharePrice::get_share_price_signer_seeds(vault_account.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            share_price_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            share_price_account.clone(),
+            system_program.clone(),
+        ],
+        &[&signature_seeds],
+    )?;
+
+    let share_price = SharePrice {
+        vault: *vault_account.key,
+        pubkey: *share_price_account.key,
+        nav_per_share: 0,
+        confidence: 0,
+        total_equity: 0,
+        total_shares: 0,
+        last_update_slot: 0,
+        last_update_ts: 0,
+        bump: share_price_bump_seed,
+    };
+
+    share_price.save(share_price_account)
+}
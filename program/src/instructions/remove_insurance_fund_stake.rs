@@ -0,0 +1,162 @@
+use drift_interface::{
+    remove_insurance_fund_stake_ix_with_program_id,
+    remove_insurance_fund_stake_verify_account_keys,
+    remove_insurance_fund_stake_verify_writable_privileges, RemoveInsuranceFundStakeAccounts,
+    RemoveInsuranceFundStakeIxArgs, RemoveInsuranceFundStakeKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, get_token_balance,
+        invoke_drift_signed, log_accounts,
+    },
+    error::VaultErrorCode,
+    instructions::drift_deposit,
+    state::Vault,
+};
+
+/// Removes the requested insurance fund stake once the unstaking period passed and deposits it
+/// back into the vault's primary drift user. Permissionless so depositors waiting on the
+/// unstaking period don't depend on the operator
+pub fn remove_insurance_fund_stake<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Removing insurance fund stake...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_spot_market_vault = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+    let drift_signer = next_account_info(account_info_iter)?;
+    let insurance_fund_stake = next_account_info(account_info_iter)?;
+    let insurance_fund_vault = next_account_info(account_info_iter)?;
+
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        // Drift accounts
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_spot_market_vault, "Drift Spot Market Vault"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+        (drift_signer, "Drift Signer"),
+        (insurance_fund_stake, "Insurance Fund Stake"),
+        (insurance_fund_vault, "Insurance Fund Vault"),
+        // Token accounts
+        (vault_token_account, "Vault Token Account"),
+        (mint, "Mint"),
+        (token_program, "Token Program"),
+    ]);
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.token_account != *vault_token_account.key {
+        msg!("Invalid Vault Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.insurance_fund_stake != *insurance_fund_stake.key {
+        msg!("Invalid Insurance Fund Stake");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    if vault.user != *drift_user.key {
+        msg!("Drift User is not the vault's primary sub account");
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    let balance_before = get_token_balance(vault_token_account)?;
+
+    let cpi_accounts = RemoveInsuranceFundStakeAccounts {
+        state: drift_state,
+        spot_market: drift_spot_market,
+        insurance_fund_stake,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        insurance_fund_vault,
+        drift_signer,
+        user_token_account: vault_token_account,
+        token_program,
+    };
+
+    remove_insurance_fund_stake_verify_account_keys(
+        cpi_accounts,
+        RemoveInsuranceFundStakeKeys {
+            insurance_fund_stake: vault.insurance_fund_stake,
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    remove_insurance_fund_stake_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = remove_insurance_fund_stake_ix_with_program_id(
+        *drift_program.key,
+        cpi_accounts.into(),
+        RemoveInsuranceFundStakeIxArgs {
+            market_index: vault.spot_market_index,
+        },
+    )?;
+
+    invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)?;
+
+    let balance_after = get_token_balance(vault_token_account)?;
+    let amount = balance_after.saturating_sub(balance_before);
+
+    msg!("removed amount: {}", amount);
+
+    // The markets for the primary sub account's positions follow the fixed accounts
+    let market_accounts = match &accounts[14..] {
+        [] => &accounts[6..8],
+        market_accounts => market_accounts,
+    };
+
+    drift_deposit(
+        &vault,
+        vault.spot_market_index,
+        amount,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        drift_spot_market_vault,
+        vault_token_account,
+        token_program,
+        market_accounts,
+        Some(mint),
+        drift_program,
+    )
+}
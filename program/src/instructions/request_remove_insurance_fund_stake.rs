@@ -0,0 +1,111 @@
+use drift_interface::{
+    request_remove_insurance_fund_stake_ix_with_program_id,
+    request_remove_insurance_fund_stake_verify_account_keys,
+    request_remove_insurance_fund_stake_verify_writable_privileges,
+    RequestRemoveInsuranceFundStakeAccounts, RequestRemoveInsuranceFundStakeIxArgs,
+    RequestRemoveInsuranceFundStakeKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+    },
+    error::VaultErrorCode,
+    state::Vault,
+};
+
+pub fn request_remove_insurance_fund_stake<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    amount: u64,
+) -> ProgramResult {
+    msg!("Requesting insurance fund stake removal...");
+    msg!("amount: {}", amount);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+    let insurance_fund_stake = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let insurance_fund_vault = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_spot_market, "Drift Spot Market"),
+        (insurance_fund_stake, "Insurance Fund Stake"),
+        (drift_user_stats, "Drift User Stats"),
+        (insurance_fund_vault, "Insurance Fund Vault"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.insurance_fund_stake != *insurance_fund_stake.key {
+        msg!("Invalid Insurance Fund Stake");
+        return Err(VaultErrorCode::InvalidInsuranceFundStake.into());
+    }
+
+    let cpi_accounts = RequestRemoveInsuranceFundStakeAccounts {
+        spot_market: drift_spot_market,
+        insurance_fund_stake,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        insurance_fund_vault,
+    };
+
+    request_remove_insurance_fund_stake_verify_account_keys(
+        cpi_accounts,
+        RequestRemoveInsuranceFundStakeKeys {
+            insurance_fund_stake: vault.insurance_fund_stake,
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..cpi_accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    request_remove_insurance_fund_stake_verify_writable_privileges(cpi_accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = request_remove_insurance_fund_stake_ix_with_program_id(
+        *drift_program.key,
+        cpi_accounts.into(),
+        RequestRemoveInsuranceFundStakeIxArgs {
+            market_index: vault.spot_market_index,
+            amount,
+        },
+    )?;
+
+    invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)
+}
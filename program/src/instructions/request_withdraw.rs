@@ -1,9 +1,3 @@
-use std::collections::BTreeSet;
-
-use drift::{
-    instructions::optional_accounts::{load_maps, AccountMaps},
-    state::{spot_market_map::get_writable_spot_market_set, user::User},
-};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -14,8 +8,7 @@ use solana_program::{
 };
 
 use crate::{
-    common::{deserialize_zero_copy, log_accounts},
-    error::wrap_drift_error,
+    common::{calculate_vault_equity, get_share_token_balance, log_accounts},
     state::{Vault, VaultDepositor},
 };
 
@@ -63,39 +56,36 @@ pub fn request_withdraw<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
-    let mut vault_depositor = VaultDepositor::get(vault_depositor_account);
+    let mut vault = Vault::get(vault_account)?;
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
 
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    // Vaults with a share mint take the authority's share token account, the share tokens can be
+    // requested on top of the unlocked shares
+    let (token_shares, remaining_accounts) = if vault.has_share_mint() {
+        let share_token_account = next_account_info(&mut account_info_iter)?;
+        let token_shares =
+            get_share_token_balance(&vault, share_token_account, authority.key)? as u128;
 
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[6..8].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
-        clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
+        (token_shares, &accounts[9..])
+    } else {
+        (0, &accounts[8..])
     };
 
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
-
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[6..8],
+        remaining_accounts,
+        clock.slot,
+    )?;
 
     msg!("vault_equity: {:?}", vault_equity);
 
     vault_depositor.request_withdraw(
         withdraw_amount,
         vault_equity,
+        token_shares,
         &mut vault,
         clock.unix_timestamp,
     )?;
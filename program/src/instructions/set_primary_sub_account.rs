@@ -0,0 +1,54 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{common::log_accounts, state::Vault};
+
+pub fn set_primary_sub_account<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    sub_account_id: u16,
+) -> ProgramResult {
+    msg!("Setting primary sub account...");
+    msg!("sub_account_id: {}", sub_account_id);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[(operator, "Operator"), (vault_account, "Vault")]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!(
+        "primary sub account: {} -> {}",
+        vault.primary_sub_account_id,
+        sub_account_id
+    );
+
+    vault.set_primary_sub_account(sub_account_id)?;
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
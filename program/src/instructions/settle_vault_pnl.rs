@@ -0,0 +1,159 @@
+use drift_interface::{
+    settle_multiple_pnls_ix_with_program_id, settle_multiple_pnls_verify_account_keys,
+    settle_multiple_pnls_verify_writable_privileges, settle_pnl_ix_with_program_id,
+    settle_pnl_verify_account_keys, settle_pnl_verify_writable_privileges,
+    SettleMultiplePnlsAccounts, SettleMultiplePnlsIxArgs, SettleMultiplePnlsKeys,
+    SettlePnlAccounts, SettlePnlIxArgs, SettlePnlKeys, SettlePnlMode,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+    },
+    state::Vault,
+};
+
+pub fn settle_vault_pnl<'info>(
+    _program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    market_indexes: Vec<u16>,
+) -> ProgramResult {
+    msg!("Settling vault pnl...");
+    msg!("market_indexes: {:?}", market_indexes);
+
+    let mut account_info_iter = accounts.iter();
+
+    let vault_account = next_account_info(&mut account_info_iter)?;
+
+    let drift_program = next_account_info(&mut account_info_iter)?;
+    let drift_state = next_account_info(&mut account_info_iter)?;
+    let drift_user = next_account_info(&mut account_info_iter)?;
+    let drift_spot_market_vault = next_account_info(&mut account_info_iter)?;
+
+    // Oracles, spot markets and perp markets for drift
+    let market_accounts = &accounts[5..];
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+        (drift_spot_market_vault, "Drift Spot Market Vault"),
+    ]);
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.get_sub_account_by_user(drift_user.key).is_none() {
+        msg!("Drift User is not a vault sub account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if market_indexes.is_empty() {
+        msg!("No markets to settle");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    drift_settle_pnl(
+        &vault,
+        market_indexes,
+        SettlePnlMode::MustSettle,
+        drift_program,
+        drift_state,
+        drift_user,
+        vault_account,
+        drift_spot_market_vault,
+        market_accounts,
+    )
+}
+
+/// Settles the pnl of perp positions on drift for a vault user
+pub fn drift_settle_pnl<'a>(
+    vault: &Vault,
+    market_indexes: Vec<u16>,
+    mode: SettlePnlMode,
+    // Individual accounts
+    drift_program: &AccountInfo<'a>,
+    drift_state: &AccountInfo<'a>,
+    drift_user: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    drift_spot_market_vault: &AccountInfo<'a>,
+    // Oracles, spot markets and perp markets for drift
+    market_accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Settle pnl CPI to Drift...");
+
+    if market_indexes.len() == 1 && mode == SettlePnlMode::MustSettle {
+        let accounts = SettlePnlAccounts {
+            state: drift_state,
+            user: drift_user,
+            authority: vault_account,
+            spot_market_vault: drift_spot_market_vault,
+        };
+
+        settle_pnl_verify_account_keys(
+            accounts,
+            SettlePnlKeys {
+                authority: vault.pubkey,
+                ..accounts.into()
+            },
+        )
+        .map_err(drift_account_key_error)?;
+        settle_pnl_verify_writable_privileges(accounts).map_err(drift_account_privilege_error)?;
+
+        let ix = settle_pnl_ix_with_program_id(
+            *drift_program.key,
+            accounts.into(),
+            SettlePnlIxArgs {
+                market_index: market_indexes[0],
+            },
+        )?;
+
+        return invoke_drift_signed(ix, accounts, market_accounts, drift_program, vault);
+    }
+
+    let accounts = SettleMultiplePnlsAccounts {
+        state: drift_state,
+        user: drift_user,
+        authority: vault_account,
+        spot_market_vault: drift_spot_market_vault,
+    };
+
+    settle_multiple_pnls_verify_account_keys(
+        accounts,
+        SettleMultiplePnlsKeys {
+            authority: vault.pubkey,
+            ..accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    settle_multiple_pnls_verify_writable_privileges(accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = settle_multiple_pnls_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        SettleMultiplePnlsIxArgs {
+            market_indexes,
+            mode,
+        },
+    )?;
+
+    invoke_drift_signed(ix, accounts, market_accounts, drift_program, vault)
+}
@@ -0,0 +1,369 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        bytes32_to_string, calculate_vault_equity, calculate_vault_equity_and_swap_bounds,
+        get_market_accounts, get_token_balance, log_accounts, unpack_token_account,
+    },
+    constants::{JUPITER_V6_PROGRAM_ID, PERCENTAGE_PRECISION},
+    custom_validate,
+    error::VaultErrorCode,
+    instructions::{drift_deposit, drift_withdraw},
+    state::{TradingPolicy, Vault},
+};
+
+/// Swaps a deposit of the vault's primary sub account into another drift spot market.
+///
+/// Drift's begin_swap rejects being invoked through CPI: it reads the instructions sysvar and
+/// requires the executing top level instruction to be its own, so the vault program can't wrap
+/// it to enforce the trading policy. Instead the deposit is withdrawn reduce only from drift into
+/// a vault token account, swapped by the swap program with the vault signing, and the output is
+/// deposited back. Like end_swap, the amount out must be at least the amount in at oracle prices
+/// less the trading policy's max_swap_slippage, and the vault equity drop is capped by it too.
+pub fn swap_collateral<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    in_market_index: u16,
+    out_market_index: u16,
+    amount_in: u64,
+    num_swap_accounts: u8,
+    swap_data: Vec<u8>,
+) -> ProgramResult {
+    msg!("Swapping collateral...");
+    msg!("in_market_index: {}", in_market_index);
+    msg!("out_market_index: {}", out_market_index);
+    msg!("amount_in: {}", amount_in);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let trading_policy_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_signer = next_account_info(account_info_iter)?;
+    let in_spot_market_vault = next_account_info(account_info_iter)?;
+    let out_spot_market_vault = next_account_info(account_info_iter)?;
+
+    let in_token_account = next_account_info(account_info_iter)?;
+    let out_token_account = next_account_info(account_info_iter)?;
+    let in_mint = next_account_info(account_info_iter)?;
+    let out_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    let swap_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (trading_policy_account, "Trading Policy"),
+        // Drift accounts
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_signer, "Drift Signer"),
+        (in_spot_market_vault, "In Spot Market Vault"),
+        (out_spot_market_vault, "Out Spot Market Vault"),
+        // Token accounts
+        (in_token_account, "In Token Account"),
+        (out_token_account, "Out Token Account"),
+        (in_mint, "In Mint"),
+        (out_mint, "Out Mint"),
+        (token_program, "Token Program"),
+        (swap_program, "Swap Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if JUPITER_V6_PROGRAM_ID != *swap_program.key {
+        msg!("Invalid Swap Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.user != *drift_user.key {
+        msg!("Drift User is not the vault's primary sub account");
+        return Err(VaultErrorCode::InvalidSubAccount.into());
+    }
+
+    let (trading_policy_pda, _) = TradingPolicy::get_pda(vault_account.key, program_id);
+
+    if trading_policy_pda != *trading_policy_account.key {
+        msg!("Invalid seeds for Trading Policy PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let trading_policy = TradingPolicy::get(trading_policy_account);
+
+    custom_validate!(
+        trading_policy.max_swap_slippage > 0,
+        VaultErrorCode::InvalidSwap,
+        "swaps are disabled by the trading policy"
+    )?;
+
+    custom_validate!(
+        in_market_index != out_market_index && amount_in > 0,
+        VaultErrorCode::InvalidSwap,
+        "swap must be between two markets and amount_in must be > 0"
+    )?;
+
+    custom_validate!(
+        out_market_index == vault.spot_market_index
+            || vault.is_collateral_market(out_market_index)
+            || trading_policy
+                .allowed_spot_markets
+                .contains(&out_market_index),
+        VaultErrorCode::MarketNotAllowed,
+        "spot market {} is not allowed",
+        out_market_index
+    )?;
+
+    // Drift doesn't check who owns the token account it withdraws to
+    for (token_account, mint) in [(in_token_account, in_mint), (out_token_account, out_mint)] {
+        let token_account_data = unpack_token_account(token_account)?;
+
+        if token_account_data.owner != vault.pubkey {
+            msg!(
+                "Token account {} is not owned by the vault",
+                token_account.key
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if token_account_data.mint != *mint.key {
+            msg!("Invalid Mint for token account {}", token_account.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    // The sub account users and the markets for all their positions and both swap markets, then
+    // the swap accounts
+    let remaining_accounts = &accounts[16..];
+    let num_remaining_accounts = remaining_accounts
+        .len()
+        .checked_sub(num_swap_accounts as usize)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (remaining_accounts, swap_accounts) = remaining_accounts.split_at(num_remaining_accounts);
+
+    // Both swap markets must be passed, so there are no default market accounts to fall back to
+    let no_default_market_accounts: &[AccountInfo] = &[];
+
+    let (equity_before, amount_in, value_in, min_amount_out) =
+        calculate_vault_equity_and_swap_bounds(
+            &vault,
+            &trading_policy,
+            drift_user,
+            remaining_accounts,
+            in_token_account,
+            out_token_account,
+            in_market_index,
+            out_market_index,
+            amount_in,
+            clock.slot,
+        )?;
+
+    msg!(
+        "equity_before: {}, amount_in: {}, value_in: {}, min_amount_out: {}",
+        equity_before,
+        amount_in,
+        value_in,
+        min_amount_out
+    );
+
+    let market_accounts =
+        get_market_accounts(&vault, no_default_market_accounts, remaining_accounts);
+
+    let in_balance_before = get_token_balance(in_token_account)?;
+    let out_balance_before = get_token_balance(out_token_account)?;
+
+    drift_withdraw(
+        &vault,
+        in_market_index,
+        amount_in,
+        true,
+        drift_program,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        in_spot_market_vault,
+        drift_signer,
+        in_token_account,
+        token_program,
+        market_accounts,
+        Some(in_mint),
+    )?;
+
+    invoke_swap(
+        &vault,
+        vault_account,
+        swap_program,
+        swap_accounts,
+        swap_data,
+    )?;
+
+    let in_balance_after = get_token_balance(in_token_account)?;
+
+    // The vault signs the swap, so it could spend tokens the account held before the withdraw
+    custom_validate!(
+        in_balance_after >= in_balance_before,
+        VaultErrorCode::InvalidSwap,
+        "swap spent {} more than amount_in",
+        in_balance_before - in_balance_after
+    )?;
+
+    let amount_out = get_token_balance(out_token_account)?.saturating_sub(out_balance_before);
+    let amount_unused = in_balance_after - in_balance_before;
+
+    msg!(
+        "amount_out: {}, amount_unused: {}",
+        amount_out,
+        amount_unused
+    );
+
+    custom_validate!(
+        amount_out > 0 && amount_out >= min_amount_out,
+        VaultErrorCode::SwapSlippageExceeded,
+        "swap returned {} < min amount out {}",
+        amount_out,
+        min_amount_out
+    )?;
+
+    drift_deposit(
+        &vault,
+        out_market_index,
+        amount_out,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        out_spot_market_vault,
+        out_token_account,
+        token_program,
+        market_accounts,
+        Some(out_mint),
+        drift_program,
+    )?;
+
+    if amount_unused > 0 {
+        drift_deposit(
+            &vault,
+            in_market_index,
+            amount_unused,
+            drift_state,
+            drift_user,
+            drift_user_stats,
+            vault_account,
+            in_spot_market_vault,
+            in_token_account,
+            token_program,
+            market_accounts,
+            Some(in_mint),
+            drift_program,
+        )?;
+    }
+
+    let equity_after = calculate_vault_equity(
+        &vault,
+        drift_user,
+        no_default_market_accounts,
+        remaining_accounts,
+        clock.slot,
+    )?;
+
+    let loss = equity_before.saturating_sub(equity_after) as u128;
+    let max_loss = (value_in as u128)
+        .saturating_mul(trading_policy.max_swap_slippage as u128)
+        .checked_div(PERCENTAGE_PRECISION)
+        .ok_or(VaultErrorCode::MathError)?;
+
+    msg!(
+        "equity_after: {}, loss: {}, max_loss: {}",
+        equity_after,
+        loss,
+        max_loss
+    );
+
+    custom_validate!(
+        loss <= max_loss,
+        VaultErrorCode::SwapSlippageExceeded,
+        "swap lost {} of vault equity > {}",
+        loss,
+        max_loss
+    )?;
+
+    Ok(())
+}
+
+/// Invokes the swap program with the given accounts and data, signed by the vault
+fn invoke_swap<'a>(
+    vault: &Vault,
+    vault_account: &AccountInfo<'a>,
+    swap_program: &AccountInfo<'a>,
+    swap_accounts: &[AccountInfo<'a>],
+    swap_data: Vec<u8>,
+) -> ProgramResult {
+    msg!("Swap CPI...");
+
+    let accounts_meta = swap_accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer || account.key == vault_account.key,
+            is_writable: account.is_writable,
+        })
+        .collect::<Vec<AccountMeta>>();
+
+    let mut account_infos = swap_accounts.to_vec();
+    account_infos.push(swap_program.clone());
+
+    let ix = Instruction {
+        program_id: *swap_program.key,
+        accounts: accounts_meta,
+        data: swap_data,
+    };
+
+    invoke_signed(
+        &ix,
+        &account_infos,
+        &[&[
+            b"vault",
+            bytes32_to_string(vault.name).as_ref(),
+            &[vault.bump],
+        ]],
+    )
+}
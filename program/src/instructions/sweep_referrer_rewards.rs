@@ -0,0 +1,164 @@
+use drift::{math::constants::QUOTE_SPOT_MARKET_INDEX, state::user::UserStats};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{bytes32_to_string, deserialize_zero_copy, log_accounts, transfer_to_user_from_vault},
+    error::VaultErrorCode,
+    instructions::drift_withdraw,
+    state::{Treasury, Vault},
+};
+
+/// Moves the drift referrer rewards earned since the last sweep from sub account 0 to the
+/// treasury. Permissionless, the rewards count toward vault equity until they are swept
+pub fn sweep_referrer_rewards<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Sweeping referrer rewards...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+    let treasury_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_user = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+    let drift_spot_market_vault = next_account_info(account_info_iter)?;
+    let drift_oracle = next_account_info(account_info_iter)?;
+    let drift_spot_market = next_account_info(account_info_iter)?;
+    let drift_signer = next_account_info(account_info_iter)?;
+
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let treasury_token_account = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+
+    let token_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (treasury_account, "Treasury"),
+        // Drift accounts
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user, "Drift User"),
+        (drift_user_stats, "Drift User Stats"),
+        (drift_spot_market_vault, "Drift Spot Market Vault"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+        (drift_signer, "Drift Signer"),
+        // Token accounts
+        (vault_token_account, "Vault Token Account"),
+        (treasury_token_account, "Treasury Token Account"),
+        (mint, "Mint"),
+        // System accounts
+        (token_program, "Token Program"),
+    ]);
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Referrer rewards are paid in the quote market, the vault token account only holds the
+    // vault's spot market token
+    if vault.spot_market_index != QUOTE_SPOT_MARKET_INDEX {
+        msg!("Referrer rewards can only be swept for quote vaults");
+        return Err(VaultErrorCode::InvalidReferrer.into());
+    }
+
+    match vault.get_sub_account(0) {
+        Some(sub_account) if sub_account.user == *drift_user.key => {}
+        _ => {
+            msg!("Drift User is not the vault's sub account 0");
+            return Err(VaultErrorCode::InvalidReferrer.into());
+        }
+    }
+
+    if vault.user_stats != *drift_user_stats.key {
+        msg!("Invalid Drift User Stats");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (treasury_pda, _) = Treasury::get_pda(&bytes32_to_string(vault.name), program_id);
+
+    if treasury_pda != *treasury_account.key {
+        msg!("Invalid seeds for Treasury PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let treasury = Treasury::get(treasury_account);
+
+    if treasury.token_account != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let total_referrer_reward = {
+        let user_stats = deserialize_zero_copy::<UserStats>(&*drift_user_stats.try_borrow_data()?);
+        user_stats.fees.total_referrer_reward
+    };
+
+    let amount = total_referrer_reward.saturating_sub(vault.referrer_rewards_swept);
+
+    msg!("amount: {}", amount);
+
+    if amount == 0 {
+        msg!("No referrer rewards to sweep");
+        return Ok(());
+    }
+
+    vault.referrer_rewards_swept = total_referrer_reward;
+
+    Vault::save(&vault, vault_account)?;
+
+    // The markets for sub account 0's positions follow the fixed accounts
+    let market_accounts = match &accounts[14..] {
+        [] => &accounts[7..9],
+        market_accounts => market_accounts,
+    };
+
+    drift_withdraw(
+        &vault,
+        QUOTE_SPOT_MARKET_INDEX,
+        amount,
+        false,
+        drift_program,
+        drift_state,
+        drift_user,
+        drift_user_stats,
+        vault_account,
+        drift_spot_market_vault,
+        drift_signer,
+        vault_token_account,
+        token_program,
+        market_accounts,
+        Some(mint),
+    )?;
+
+    transfer_to_user_from_vault(
+        &vault,
+        amount,
+        token_program,
+        treasury_token_account,
+        vault_token_account,
+        vault_account,
+        mint,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{calculate_vault_equity, log_accounts},
+    error::VaultErrorCode,
+    instructions::resize_vault_depositor_account,
+    state::{DepositInfo, Vault, VaultDepositor},
+};
+
+pub fn transfer_depositor_position<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+) -> ProgramResult {
+    msg!("Transferring depositor position...");
+
+    let clock = &Clock::get()?;
+
+    let mut account_info_iter = accounts.iter();
+
+    let vault_account = next_account_info(&mut account_info_iter)?;
+    let vault_depositor_account = next_account_info(&mut account_info_iter)?;
+    let authority = next_account_info(&mut account_info_iter)?;
+    let destination_vault_depositor_account = next_account_info(&mut account_info_iter)?;
+    let destination_authority = next_account_info(&mut account_info_iter)?;
+
+    let system_program = next_account_info(&mut account_info_iter)?;
+
+    let drift_user = next_account_info(&mut account_info_iter)?;
+    let drift_oracle = next_account_info(&mut account_info_iter)?;
+    let drift_spot_market = next_account_info(&mut account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (authority, "Authority"),
+        (
+            destination_vault_depositor_account,
+            "Destination Vault Depositor",
+        ),
+        (destination_authority, "Destination Authority"),
+        (system_program, "System Program"),
+        (drift_user, "Drift User"),
+        (drift_oracle, "Drift Oracle"),
+        (drift_spot_market, "Drift Spot Market"),
+    ]);
+
+    if !authority.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, authority.key, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (destination_vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, destination_authority.key, program_id);
+
+    if destination_vault_depositor_pda != *destination_vault_depositor_account.key {
+        msg!("Invalid seeds for Destination Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Same rule initialize_vault_depositor applies when creating the destination
+    if vault.permissioned && vault.manager != *destination_authority.key {
+        msg!("Destination is not allowed to hold a position in a permissioned vault");
+        return Err(VaultErrorCode::PermissionedVault.into());
+    }
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+    let mut destination_vault_depositor = VaultDepositor::get(destination_vault_depositor_account)?;
+
+    if destination_vault_depositor.pubkey != *destination_vault_depositor_account.key
+        || destination_vault_depositor.authority != *destination_authority.key
+    {
+        msg!("Destination Vault Depositor is not initialized");
+        return Err(VaultErrorCode::UninitializedAccount.into());
+    }
+
+    let lots = vault_depositor.deposits.len();
+    let required_size = destination_vault_depositor.get_current_size() + DepositInfo::SIZE * lots;
+
+    if destination_vault_depositor_account.data_len() < required_size {
+        resize_vault_depositor_account(
+            destination_vault_depositor_account,
+            authority,
+            system_program,
+            lots,
+        )?;
+    }
+
+    // The destination's cap applies to the value of the combined position. The sub account users
+    // and then the markets for all their positions follow the fixed accounts
+    let vault_equity = calculate_vault_equity(
+        &vault,
+        drift_user,
+        &accounts[7..9],
+        &accounts[9..],
+        clock.slot,
+    )?;
+
+    vault_depositor.transfer_position(
+        &mut destination_vault_depositor,
+        &vault,
+        vault_equity,
+        clock.unix_timestamp,
+    )?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    VaultDepositor::save(
+        &destination_vault_depositor,
+        destination_vault_depositor_account,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{common::log_accounts, state::Vault};
+
+pub fn update_collateral_markets<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    spot_market_indexes: Vec<u16>,
+) -> ProgramResult {
+    msg!("Updating collateral markets...");
+    msg!("spot_market_indexes: {:?}", spot_market_indexes);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[(admin, "Admin"), (vault_account, "Vault")]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vault.set_collateral_markets(&spot_market_indexes)?;
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
@@ -1,16 +1,23 @@
-use solana_program::instruction::{AccountMeta, Instruction};
+use drift_interface::{
+    update_user_delegate_ix_with_program_id, update_user_delegate_verify_account_keys,
+    update_user_delegate_verify_writable_privileges, UpdateUserDelegateAccounts,
+    UpdateUserDelegateIxArgs, UpdateUserDelegateKeys,
+};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
 };
 use std::str::FromStr;
 
-use crate::drift::{UpdateUserDelegateIxArgs, UpdateUserDelegateIxData};
-use crate::state::Vault;
+use crate::common::{
+    drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_data,
+    log_params,
+};
+use crate::state::{DelegateAction, DelegateChangedRecord, Vault};
 
 pub fn update_vault_delegate<'a>(
     program_id: &Pubkey,
@@ -25,27 +32,29 @@ pub fn update_vault_delegate<'a>(
     msg!("delegate: {}", delegate);
     msg!("sub account: {}", sub_account);
 
+    let clock = &Clock::get()?;
+
     // Extract accounts in expected order
     let account_info_iter = &mut accounts.iter();
-    let manager = next_account_info(account_info_iter)?;
+    let operator = next_account_info(account_info_iter)?;
     let vault_account = next_account_info(account_info_iter)?;
     let drift_program = next_account_info(account_info_iter)?;
     let drift_user = next_account_info(account_info_iter)?;
 
     // Log account addresses for debugging
-    msg!("1. initializer: {}", manager.key);
+    msg!("1. initializer: {}", operator.key);
     msg!("2. vault: {}", vault_account.key);
     msg!("3. drift_program: {}", drift_program.key);
     msg!("4. drift_user: {}", drift_user.key);
 
-    // Verify manager signature
-    if !manager.is_signer {
+    // Verify operator signature
+    if !operator.is_signer {
         msg!("Missing required signature");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
     // Verify vault PDA
-    let (vault_pda, vault_bump_seed) = Vault::get_pda(&name, program_id);
+    let (vault_pda, _) = Vault::get_pda(&name, program_id);
     if vault_pda != *vault_account.key {
         msg!("Invalid seeds for Vault PDA");
         return Err(ProgramError::InvalidArgument);
@@ -61,76 +70,94 @@ pub fn update_vault_delegate<'a>(
     };
 
     // Update vault state
-    let mut vault = Vault::get(vault_account);
+    let mut vault = Vault::get(vault_account)?;
 
-    if vault.manager != *manager.key {
-        msg!("Invalid Manager Account");
+    if vault.operator != *operator.key {
+        msg!("Invalid Operator Account");
         return Err(ProgramError::InvalidArgument);
     }
 
-    vault.delegate = delegate_pubkey;
+    let delegate_before = match vault.get_sub_account(sub_account) {
+        Some(vault_sub_account) if vault_sub_account.user == *drift_user.key => {
+            vault_sub_account.delegate
+        }
+        _ => {
+            msg!("Drift User is not the vault's sub account {}", sub_account);
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    vault.set_sub_account_delegate(sub_account, delegate_pubkey)?;
     Vault::save(&vault, vault_account)?;
 
+    // With a trading policy the delegate trades through PlaceOrders, so drift gets no raw delegate
+    let drift_delegate = if vault.trading_policy_enabled {
+        msg!("Trading policy enabled, delegate can only trade through the vault program");
+        Pubkey::default()
+    } else {
+        delegate_pubkey
+    };
+
     // Update delegate through CPI
     update_delegate(
+        &vault,
         drift_program,
         drift_user,
         vault_account,
-        name,
         sub_account,
-        delegate_pubkey,
-        vault_bump_seed,
+        drift_delegate,
     )?;
 
+    let record = DelegateChangedRecord {
+        ts: clock.unix_timestamp,
+        vault: vault.pubkey,
+        operator: *operator.key,
+        action: DelegateAction::Update,
+        sub_account_id: sub_account,
+        delegate_before,
+        delegate_after: delegate_pubkey,
+    };
+
+    log_data(&record)?;
+    log_params(&record);
+
     Ok(())
 }
 
-fn update_delegate<'a>(
+pub fn update_delegate<'a>(
+    vault: &Vault,
     drift_program: &'a AccountInfo<'a>,
     drift_user: &'a AccountInfo<'a>,
     vault_account: &'a AccountInfo<'a>,
-    name: String,
     sub_account: u16,
     delegate_pubkey: Pubkey,
-    vault_bump_seed: u8,
 ) -> ProgramResult {
     msg!("Update delegate CPI to Drift...");
 
-    // Prepare CPI accounts
-    let accounts_meta = vec![
-        AccountMeta {
-            pubkey: *drift_user.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_account.key,
-            is_signer: true,
-            is_writable: true,
-        },
-    ];
-
-    // Prepare instruction data
-    let args = UpdateUserDelegateIxArgs {
-        sub_account_id: sub_account,
-        delegate: delegate_pubkey,
-    };
-    let data: UpdateUserDelegateIxData = args.into();
-
-    // Create and execute CPI
-    let ix = Instruction {
-        program_id: *drift_program.key,
-        accounts: accounts_meta,
-        data: data.try_to_vec()?,
+    let accounts = UpdateUserDelegateAccounts {
+        user: drift_user,
+        authority: vault_account,
     };
 
-    invoke_signed(
-        &ix,
-        &[
-            drift_user.clone(),
-            vault_account.clone(),
-            drift_program.clone(),
-        ],
-        &[&[b"vault", name.as_bytes(), &[vault_bump_seed]]],
+    update_user_delegate_verify_account_keys(
+        accounts,
+        UpdateUserDelegateKeys {
+            authority: vault.pubkey,
+            ..accounts.into()
+        },
     )
+    .map_err(drift_account_key_error)?;
+    update_user_delegate_verify_writable_privileges(accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = update_user_delegate_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        UpdateUserDelegateIxArgs {
+            sub_account_id: sub_account,
+            delegate: delegate_pubkey,
+        },
+    )?;
+
+    invoke_drift_signed(ix, accounts, &[], drift_program, vault)
 }
@@ -0,0 +1,87 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{log_accounts, log_data, log_params},
+    state::{DepositorCapUpdatedRecord, Vault, VaultDepositor},
+};
+
+pub fn update_depositor_cap<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    deposit_cap: Option<u64>,
+) -> ProgramResult {
+    msg!("Updating depositor cap...");
+    msg!("deposit_cap: {:?}", deposit_cap);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_depositor_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (admin, "Admin"),
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+    ]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    if vault_depositor.pubkey != *vault_depositor_account.key
+        || vault_depositor.vault != *vault_account.key
+    {
+        msg!("Invalid Vault Depositor");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!(
+        "deposit_cap_override: {:?} -> {:?}",
+        vault_depositor.deposit_cap_override,
+        deposit_cap
+    );
+
+    let record = DepositorCapUpdatedRecord {
+        ts: clock.unix_timestamp,
+        vault: vault.pubkey,
+        admin: *admin.key,
+        depositor_authority: vault_depositor.authority,
+        deposit_cap_before: vault_depositor.deposit_cap_override,
+        deposit_cap_after: deposit_cap,
+    };
+
+    // None falls back to the vault wide max_deposit_per_depositor, Some(0) lifts any cap
+    vault_depositor.deposit_cap_override = deposit_cap;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+
+    log_data(&record)?;
+    log_params(&record);
+
+    Ok(())
+}
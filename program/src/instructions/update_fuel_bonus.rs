@@ -0,0 +1,101 @@
+use drift_interface::{
+    update_user_fuel_bonus_ix_with_program_id, update_user_fuel_bonus_verify_account_keys,
+    update_user_fuel_bonus_verify_writable_privileges, UpdateUserFuelBonusAccounts,
+    UpdateUserFuelBonusKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+        update_vault_fuel, validate_sub_account_users,
+    },
+    state::Vault,
+};
+
+/// Refreshes the drift fuel bonus of all sub accounts and spreads the fuel the vault earned since
+/// the last update over the vault shares. Permissionless
+pub fn update_fuel_bonus<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    msg!("Updating fuel bonus...");
+
+    let account_info_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_user_stats = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (drift_user_stats, "Drift User Stats"),
+    ]);
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The drift users of all sub accounts, then the markets for their positions which drift
+    // values the position fuel with
+    let num_sub_accounts = vault.get_sub_accounts().len();
+
+    if accounts.len() < 4 + num_sub_accounts {
+        msg!("Expected drift users for {} sub accounts", num_sub_accounts);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let (drift_users, market_accounts) = accounts[4..].split_at(num_sub_accounts);
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    for drift_user in drift_users.iter() {
+        let cpi_accounts = UpdateUserFuelBonusAccounts {
+            state: drift_state,
+            authority: vault_account,
+            user: drift_user,
+            user_stats: drift_user_stats,
+        };
+
+        update_user_fuel_bonus_verify_account_keys(
+            cpi_accounts,
+            UpdateUserFuelBonusKeys {
+                authority: vault.pubkey,
+                user_stats: vault.user_stats,
+                ..cpi_accounts.into()
+            },
+        )
+        .map_err(drift_account_key_error)?;
+        update_user_fuel_bonus_verify_writable_privileges(cpi_accounts)
+            .map_err(drift_account_privilege_error)?;
+
+        let ix =
+            update_user_fuel_bonus_ix_with_program_id(*drift_program.key, cpi_accounts.into())?;
+
+        invoke_drift_signed(ix, cpi_accounts, market_accounts, drift_program, &vault)?;
+    }
+
+    update_vault_fuel(&mut vault, drift_user_stats)?;
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use drift_interface::{
+    disable_user_high_leverage_mode_ix_with_program_id,
+    disable_user_high_leverage_mode_verify_account_keys,
+    disable_user_high_leverage_mode_verify_writable_privileges,
+    enable_user_high_leverage_mode_ix_with_program_id,
+    enable_user_high_leverage_mode_verify_account_keys,
+    enable_user_high_leverage_mode_verify_writable_privileges, DisableUserHighLeverageModeAccounts,
+    DisableUserHighLeverageModeKeys, EnableUserHighLeverageModeAccounts,
+    EnableUserHighLeverageModeIxArgs, EnableUserHighLeverageModeKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+        validate_sub_account_users,
+    },
+    state::Vault,
+};
+
+/// Enables or disables drift high leverage mode on all sub accounts. The vault's custom margin
+/// ratio still caps the leverage in high leverage mode
+pub fn update_high_leverage_mode<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    enabled: bool,
+) -> ProgramResult {
+    msg!("Updating high leverage mode...");
+    msg!("enabled: {}", enabled);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+    let drift_state = next_account_info(account_info_iter)?;
+    let drift_high_leverage_mode_config = next_account_info(account_info_iter)?;
+
+    // The drift users of all sub accounts
+    let drift_users = &accounts[5..];
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+        (drift_state, "Drift State"),
+        (
+            drift_high_leverage_mode_config,
+            "Drift High Leverage Mode Config",
+        ),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    vault.high_leverage_mode = enabled;
+
+    Vault::save(&vault, vault_account)?;
+
+    for (sub_account, drift_user) in vault.get_sub_accounts().iter().zip(drift_users.iter()) {
+        if enabled {
+            let cpi_accounts = EnableUserHighLeverageModeAccounts {
+                state: drift_state,
+                user: drift_user,
+                authority: vault_account,
+                high_leverage_mode_config: drift_high_leverage_mode_config,
+            };
+
+            enable_user_high_leverage_mode_verify_account_keys(
+                cpi_accounts,
+                EnableUserHighLeverageModeKeys {
+                    authority: vault.pubkey,
+                    ..cpi_accounts.into()
+                },
+            )
+            .map_err(drift_account_key_error)?;
+            enable_user_high_leverage_mode_verify_writable_privileges(cpi_accounts)
+                .map_err(drift_account_privilege_error)?;
+
+            let ix = enable_user_high_leverage_mode_ix_with_program_id(
+                *drift_program.key,
+                cpi_accounts.into(),
+                EnableUserHighLeverageModeIxArgs {
+                    sub_account_id: sub_account.sub_account_id,
+                },
+            )?;
+
+            invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)?;
+        } else {
+            let cpi_accounts = DisableUserHighLeverageModeAccounts {
+                state: drift_state,
+                authority: vault_account,
+                user: drift_user,
+                high_leverage_mode_config: drift_high_leverage_mode_config,
+            };
+
+            disable_user_high_leverage_mode_verify_account_keys(
+                cpi_accounts,
+                DisableUserHighLeverageModeKeys {
+                    authority: vault.pubkey,
+                    ..cpi_accounts.into()
+                },
+            )
+            .map_err(drift_account_key_error)?;
+            disable_user_high_leverage_mode_verify_writable_privileges(cpi_accounts)
+                .map_err(drift_account_privilege_error)?;
+
+            let ix = disable_user_high_leverage_mode_ix_with_program_id(
+                *drift_program.key,
+                cpi_accounts.into(),
+            )?;
+
+            invoke_drift_signed(ix, cpi_accounts, &[], drift_program, &vault)?;
+        }
+    }
+
+    Ok(())
+}
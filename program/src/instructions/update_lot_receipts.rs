@@ -0,0 +1,50 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{common::log_accounts, state::Vault};
+
+/// Enables or disables minting receipt NFTs for locked lots. Receipts already minted can always
+/// be redeemed
+pub fn update_lot_receipts<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    enabled: bool,
+) -> ProgramResult {
+    msg!("Updating lot receipts...");
+    msg!("enabled: {}", enabled);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[(admin, "Admin"), (vault_account, "Vault")]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vault.lot_receipts_enabled = enabled;
+
+    Vault::save(&vault, vault_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use drift_interface::{
+    update_user_custom_margin_ratio_ix_with_program_id,
+    update_user_custom_margin_ratio_verify_account_keys,
+    update_user_custom_margin_ratio_verify_writable_privileges,
+    UpdateUserCustomMarginRatioAccounts, UpdateUserCustomMarginRatioIxArgs,
+    UpdateUserCustomMarginRatioKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+        validate_sub_account_users,
+    },
+    state::Vault,
+};
+
+pub fn update_margin_ratio<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    margin_ratio: u32,
+) -> ProgramResult {
+    msg!("Updating margin ratio...");
+    msg!("margin_ratio: {}", margin_ratio);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+
+    // The drift users of all sub accounts
+    let drift_users = &accounts[3..];
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    msg!(
+        "custom_margin_ratio: {} -> {}",
+        vault.custom_margin_ratio,
+        margin_ratio
+    );
+
+    let apply = vault.update_custom_margin_ratio(margin_ratio, clock.unix_timestamp)?;
+
+    Vault::save(&vault, vault_account)?;
+
+    if !apply {
+        return Ok(());
+    }
+
+    for (sub_account, drift_user) in vault.get_sub_accounts().iter().zip(drift_users.iter()) {
+        drift_update_user_custom_margin_ratio(
+            &vault,
+            sub_account.sub_account_id,
+            margin_ratio,
+            drift_program,
+            drift_user,
+            vault_account,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Sets the custom margin ratio of a vault sub account on drift
+pub fn drift_update_user_custom_margin_ratio<'a>(
+    vault: &Vault,
+    sub_account_id: u16,
+    margin_ratio: u32,
+    drift_program: &AccountInfo<'a>,
+    drift_user: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    let accounts = UpdateUserCustomMarginRatioAccounts {
+        user: drift_user,
+        authority: vault_account,
+    };
+
+    update_user_custom_margin_ratio_verify_account_keys(
+        accounts,
+        UpdateUserCustomMarginRatioKeys {
+            authority: vault.pubkey,
+            ..accounts.into()
+        },
+    )
+    .map_err(drift_account_key_error)?;
+    update_user_custom_margin_ratio_verify_writable_privileges(accounts)
+        .map_err(drift_account_privilege_error)?;
+
+    let ix = update_user_custom_margin_ratio_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        UpdateUserCustomMarginRatioIxArgs {
+            sub_account_id,
+            margin_ratio,
+        },
+    )?;
+
+    invoke_drift_signed(ix, accounts, &[], drift_program, vault)
+}
@@ -0,0 +1,114 @@
+use drift_interface::{
+    update_user_margin_trading_enabled_ix_with_program_id,
+    update_user_margin_trading_enabled_verify_account_keys,
+    update_user_margin_trading_enabled_verify_writable_privileges,
+    UpdateUserMarginTradingEnabledAccounts, UpdateUserMarginTradingEnabledIxArgs,
+    UpdateUserMarginTradingEnabledKeys,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    common::{
+        drift_account_key_error, drift_account_privilege_error, invoke_drift_signed, log_accounts,
+        validate_sub_account_users,
+    },
+    state::Vault,
+};
+
+pub fn update_margin_trading_enabled<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    enabled: bool,
+) -> ProgramResult {
+    msg!("Updating margin trading enabled...");
+    msg!("enabled: {}", enabled);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (drift_program, "Drift Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The drift users of all sub accounts, then the markets for their spot positions which drift
+    // checks when margin trading is disabled
+    let num_sub_accounts = vault.get_sub_accounts().len();
+
+    if accounts.len() < 3 + num_sub_accounts {
+        msg!("Expected drift users for {} sub accounts", num_sub_accounts);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let (drift_users, market_accounts) = accounts[3..].split_at(num_sub_accounts);
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    vault.margin_trading_enabled = enabled;
+
+    Vault::save(&vault, vault_account)?;
+
+    for (sub_account, drift_user) in vault.get_sub_accounts().iter().zip(drift_users.iter()) {
+        let cpi_accounts = UpdateUserMarginTradingEnabledAccounts {
+            user: drift_user,
+            authority: vault_account,
+        };
+
+        update_user_margin_trading_enabled_verify_account_keys(
+            cpi_accounts,
+            UpdateUserMarginTradingEnabledKeys {
+                authority: vault.pubkey,
+                ..cpi_accounts.into()
+            },
+        )
+        .map_err(drift_account_key_error)?;
+        update_user_margin_trading_enabled_verify_writable_privileges(cpi_accounts)
+            .map_err(drift_account_privilege_error)?;
+
+        let ix = update_user_margin_trading_enabled_ix_with_program_id(
+            *drift_program.key,
+            cpi_accounts.into(),
+            UpdateUserMarginTradingEnabledIxArgs {
+                sub_account_id: sub_account.sub_account_id,
+                margin_trading_enabled: enabled,
+            },
+        )?;
+
+        invoke_drift_signed(ix, cpi_accounts, market_accounts, drift_program, &vault)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    common::log_accounts,
+    constants::NAV_HISTORY_LENGTH,
+    state::{NavHistory, NavSnapshot, Vault},
+};
+
+/// Sets the min seconds between two snapshots of the vault's nav history. The first call creates
+/// the nav history pda paid by the admin
+pub fn update_nav_history<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    interval: u64,
+) -> ProgramResult {
+    msg!("Updating nav history...");
+    msg!("interval: {}", interval);
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let nav_history_account = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (admin, "Admin"),
+        (vault_account, "Vault"),
+        (nav_history_account, "Nav History"),
+        (system_program, "System Program"),
+    ]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (nav_history_pda, nav_history_bump_seed) =
+        NavHistory::get_pda(vault_account.key, program_id);
+
+    if nav_history_pda != *nav_history_account.key {
+        msg!("Invalid seeds for Nav History PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if nav_history_account.data_is_empty() {
+        initialize_nav_history(
+            program_id,
+            admin,
+            vault_account,
+            nav_history_account,
+            system_program,
+            nav_history_bump_seed,
+        )?;
+    }
+
+    let mut nav_history = NavHistory::get(nav_history_account);
+
+    nav_history.interval = interval;
+
+    nav_history.save(nav_history_account)?;
+
+    Ok(())
+}
+
+fn initialize_nav_history<'a>(
+    program_id: &Pubkey,
+    payer: &'a AccountInfo<'a>,
+    vault_account: &'a AccountInfo<'a>,
+    nav_history_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    nav_history_bump_seed: u8,
+) -> ProgramResult {
+    let account_len: usize = NavHistory::SIZE;
+
+    let rent_cal = Rent::get()?;
+    let rent_lamports = rent_cal.minimum_balance(account_len);
+
+    let binding = [nav_history_bump_seed];
+    let signature_seeds =
+        NavHistory::get_nav_history_signer_seeds(vault_account.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            nav_history_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            nav_history_account.clone(),
+            system_program.clone(),
+        ],
+        &[&signature_seeds],
+    )?;
+
+    let nav_history = NavHistory {
+        vault: *vault_account.key,
+        pubkey: *nav_history_account.key,
+        interval: 0,
+        head: 0,
+        len: 0,
+        bump: nav_history_bump_seed,
+        snapshots: [NavSnapshot::default(); NAV_HISTORY_LENGTH],
+    };
+
+    nav_history.save(nav_history_account)
+}
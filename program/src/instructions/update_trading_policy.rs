@@ -0,0 +1,198 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+
+use crate::{
+    common::{log_accounts, log_data, log_params, validate_sub_account_users},
+    instructions::update_delegate,
+    state::{
+        TradingPolicy, TradingPolicyAction, TradingPolicyParams, TradingPolicyUpdatedRecord, Vault,
+    },
+};
+
+/// Updates the vault's trading policy. Loosening changes wait for the vault's timelock period and
+/// are applied by sending the same params again once it passed
+pub fn update_trading_policy<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    params: TradingPolicyParams,
+) -> ProgramResult {
+    msg!("Updating trading policy...");
+    log_params(&params);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let operator = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let trading_policy_account = next_account_info(account_info_iter)?;
+
+    let drift_program = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+
+    // The drift users of all sub accounts, in registration order
+    let drift_users = &accounts[5..];
+
+    log_accounts(&[
+        (operator, "Operator"),
+        (vault_account, "Vault"),
+        (trading_policy_account, "Trading Policy"),
+        (drift_program, "Drift Program"),
+        (system_program, "System Program"),
+    ]);
+
+    if !operator.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if drift::ID != *drift_program.key {
+        msg!("Invalid Drift Program");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.operator != *operator.key {
+        msg!("Invalid Vault Operator");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    validate_sub_account_users(&vault, drift_users)?;
+
+    let (trading_policy_pda, trading_policy_bump_seed) =
+        TradingPolicy::get_pda(vault_account.key, program_id);
+
+    if trading_policy_pda != *trading_policy_account.key {
+        msg!("Invalid seeds for Trading Policy PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if trading_policy_account.data_is_empty() {
+        initialize_trading_policy(
+            program_id,
+            operator,
+            vault_account,
+            trading_policy_account,
+            system_program,
+            trading_policy_bump_seed,
+        )?;
+    }
+
+    let mut trading_policy = TradingPolicy::get(trading_policy_account);
+
+    msg!("Before Updating trading policy...");
+    log_params(&trading_policy);
+
+    let params_before = trading_policy.get_params();
+
+    let apply = trading_policy.update_params(
+        params.clone(),
+        vault.get_timelock_period(),
+        clock.unix_timestamp,
+    )?;
+
+    trading_policy.save(trading_policy_account)?;
+
+    msg!("After Updating trading policy...");
+    log_params(&trading_policy);
+
+    let record = TradingPolicyUpdatedRecord {
+        ts: clock.unix_timestamp,
+        vault: vault.pubkey,
+        operator: *operator.key,
+        action: if apply {
+            TradingPolicyAction::Applied
+        } else {
+            TradingPolicyAction::Scheduled
+        },
+        params_before,
+        params_after: params,
+        pending_params_ts: trading_policy.pending_params_ts,
+    };
+
+    log_data(&record)?;
+    log_params(&record);
+
+    if !apply {
+        return Ok(());
+    }
+
+    vault.trading_policy_enabled = trading_policy.enabled;
+    Vault::save(&vault, vault_account)?;
+
+    if vault.trading_policy_enabled {
+        // Any raw delegate on any sub account would bypass the policy
+        for (sub_account, drift_user) in vault.get_sub_accounts().iter().zip(drift_users.iter()) {
+            update_delegate(
+                &vault,
+                drift_program,
+                drift_user,
+                vault_account,
+                sub_account.sub_account_id,
+                Pubkey::default(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_trading_policy<'a>(
+    program_id: &Pubkey,
+    payer: &'a AccountInfo<'a>,
+    vault_account: &'a AccountInfo<'a>,
+    trading_policy_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    trading_policy_bump_seed: u8,
+) -> ProgramResult {
+    let account_len: usize = TradingPolicy::SIZE;
+
+    let rent_cal = Rent::get()?;
+    let rent_lamports = rent_cal.minimum_balance(account_len);
+
+    let binding = [trading_policy_bump_seed];
+    let signature_seeds =
+        TradingPolicy::get_trading_policy_signer_seeds(vault_account.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            trading_policy_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            trading_policy_account.clone(),
+            system_program.clone(),
+        ],
+        &[&signature_seeds],
+    )?;
+
+    msg!("Trading Policy created: {}", trading_policy_account.key);
+
+    let mut trading_policy = TradingPolicy::get(trading_policy_account);
+    trading_policy.vault = *vault_account.key;
+    trading_policy.pubkey = *trading_policy_account.key;
+    trading_policy.bump = trading_policy_bump_seed;
+
+    trading_policy.save(trading_policy_account)?;
+
+    Ok(())
+}
@@ -5,13 +5,14 @@ use solana_program::{
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
 };
 
 use crate::{
-    common::{log_accounts, log_params},
+    common::{log_accounts, log_data, log_params},
     constants::{PERCENTAGE_PRECISION, PERCENTAGE_PRECISION_U64},
     error::VaultErrorCode,
-    state::Vault,
+    state::{Vault, VaultParamsUpdatedRecord},
 };
 
 pub fn update_vault<'a>(
@@ -22,25 +23,29 @@ pub fn update_vault<'a>(
     msg!("Updating vault...");
     log_params(&params);
 
+    let clock = &Clock::get()?;
+
     let account_info_iter = &mut accounts.iter();
 
-    let manager = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
     let vault_account = next_account_info(account_info_iter)?;
 
-    log_accounts(&[(manager, "Manager"), (vault_account, "Vault Account")]);
+    log_accounts(&[(admin, "Admin"), (vault_account, "Vault Account")]);
 
-    if !manager.is_signer {
+    if !admin.is_signer {
         msg!("Missing required signature");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut vault = Vault::get(vault_account);
+    let mut vault = Vault::get(vault_account)?;
 
     msg!("Before Updating vault...");
     log_params(&vault);
 
-    if vault.manager != *manager.key {
-        msg!("Invalid Vault Manager");
+    let params_before = vault.get_params_snapshot();
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
         return Err(ProgramError::InvalidArgument);
     }
 
@@ -56,6 +61,11 @@ pub fn update_vault<'a>(
     vault.management_fee = params.management_fee;
     vault.min_deposit_amount = params.min_deposit_amount;
 
+    // Clients that don't send the cap leave it unchanged
+    if let Some(max_deposit_per_depositor) = params.max_deposit_per_depositor {
+        vault.max_deposit_per_depositor = max_deposit_per_depositor;
+    }
+
     if params.profit_share >= PERCENTAGE_PRECISION as u32 {
         msg!("profit share must be < 100%");
         return Err(VaultErrorCode::InvalidVaultInitialization.into());
@@ -68,6 +78,17 @@ pub fn update_vault<'a>(
     msg!("After Updating vault...");
     log_params(&vault);
 
+    let record = VaultParamsUpdatedRecord {
+        ts: clock.unix_timestamp,
+        vault: vault.pubkey,
+        admin: *admin.key,
+        params_before,
+        params_after: vault.get_params_snapshot(),
+    };
+
+    log_data(&record)?;
+    log_params(&record);
+
     Ok(())
 }
 
@@ -81,4 +102,5 @@ pub struct UpdateVaultParams {
     pub profit_share: u32,
     pub hurdle_rate: u32,
     pub permissioned: bool,
+    pub max_deposit_per_depositor: Option<u64>,
 }
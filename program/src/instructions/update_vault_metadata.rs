@@ -0,0 +1,190 @@
+use mpl_token_metadata::{accounts::Metadata, instructions::UpdateMetadataAccountV2CpiBuilder};
+use serde::Serialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+
+use crate::{
+    common::{bytes32_to_string, get_share_mint_metadata_data, log_accounts, log_params},
+    state::{Vault, VaultMetadata},
+};
+
+/// Updates the vault's metadata. Once shares are tokenized the Metaplex metadata of the share
+/// mint is updated with the new symbol and uri, signed by the vault as its update authority
+pub fn update_vault_metadata<'a>(
+    program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    params: VaultMetadataParams,
+) -> ProgramResult {
+    msg!("Updating vault metadata...");
+    log_params(&params);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_metadata_account = next_account_info(account_info_iter)?;
+
+    let system_program = next_account_info(account_info_iter)?;
+
+    log_accounts(&[
+        (admin, "Admin"),
+        (vault_account, "Vault"),
+        (vault_metadata_account, "Vault Metadata"),
+        (system_program, "System Program"),
+    ]);
+
+    if !admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_metadata_pda, vault_metadata_bump_seed) =
+        VaultMetadata::get_pda(vault_account.key, program_id);
+
+    if vault_metadata_pda != *vault_metadata_account.key {
+        msg!("Invalid seeds for Vault Metadata PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault_metadata_account.data_is_empty() {
+        initialize_vault_metadata(
+            program_id,
+            admin,
+            vault_account,
+            vault_metadata_account,
+            system_program,
+            vault_metadata_bump_seed,
+        )?;
+    }
+
+    let mut vault_metadata = VaultMetadata::get(vault_metadata_account);
+
+    vault_metadata.set_params(
+        params.symbol,
+        params.description,
+        params.tags,
+        params.website,
+        params.risk_rating,
+        params.uri,
+        clock.unix_timestamp,
+    )?;
+
+    vault_metadata.save(vault_metadata_account)?;
+
+    msg!("After Updating vault metadata...");
+    log_params(&vault_metadata);
+
+    if vault.has_share_mint() {
+        // Once shares are tokenized the share mint's metadata and the token metadata program follow
+        let metadata = next_account_info(account_info_iter)?;
+        let token_metadata_program = next_account_info(account_info_iter)?;
+
+        log_accounts(&[
+            (metadata, "Share Mint Metadata"),
+            (token_metadata_program, "Token Metadata Program"),
+        ]);
+
+        if mpl_token_metadata::ID != *token_metadata_program.key {
+            msg!("Invalid Token Metadata Program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if Metadata::find_pda(&vault.share_mint).0 != *metadata.key {
+            msg!("Invalid Share Mint Metadata");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let name = bytes32_to_string(vault.name);
+        let bump = [vault.bump];
+        let vault_signature_seeds = Vault::get_vault_signer_seeds(&name, &bump);
+
+        UpdateMetadataAccountV2CpiBuilder::new(token_metadata_program)
+            .metadata(metadata)
+            .update_authority(vault_account)
+            .data(get_share_mint_metadata_data(
+                &vault,
+                vault_metadata.symbol.clone(),
+                vault_metadata.uri.clone(),
+            ))
+            .invoke_signed(&[&vault_signature_seeds])?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct VaultMetadataParams {
+    pub symbol: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub website: String,
+    pub risk_rating: u8,
+    pub uri: String,
+}
+
+fn initialize_vault_metadata<'a>(
+    program_id: &Pubkey,
+    payer: &'a AccountInfo<'a>,
+    vault_account: &'a AccountInfo<'a>,
+    vault_metadata_account: &'a AccountInfo<'a>,
+    system_program: &'a AccountInfo<'a>,
+    vault_metadata_bump_seed: u8,
+) -> ProgramResult {
+    let account_len: usize = VaultMetadata::SIZE;
+
+    let rent_cal = Rent::get()?;
+    let rent_lamports = rent_cal.minimum_balance(account_len);
+
+    let binding = [vault_metadata_bump_seed];
+    let signature_seeds =
+        VaultMetadata::get_vault_metadata_signer_seeds(vault_account.key.as_ref(), &binding);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            vault_metadata_account.key,
+            rent_lamports,
+            account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            vault_metadata_account.clone(),
+            system_program.clone(),
+        ],
+        &[&signature_seeds],
+    )?;
+
+    msg!("Vault Metadata created: {}", vault_metadata_account.key);
+
+    let mut vault_metadata = VaultMetadata::get(vault_metadata_account);
+    vault_metadata.vault = *vault_account.key;
+    vault_metadata.pubkey = *vault_metadata_account.key;
+    vault_metadata.bump = vault_metadata_bump_seed;
+
+    vault_metadata.save(vault_metadata_account)?;
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+use std::str::FromStr;
+
+use crate::{
+    common::{log_accounts, log_data, log_params},
+    state::{Vault, VaultRolesUpdatedRecord},
+};
+
+pub fn update_vault_roles<'a>(
+    _program_id: &Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    admin: String,
+    operator: String,
+    fee_recipient: String,
+) -> ProgramResult {
+    msg!("Updating vault roles...");
+    msg!("admin: {}", admin);
+    msg!("operator: {}", operator);
+    msg!("fee_recipient: {}", fee_recipient);
+
+    let clock = &Clock::get()?;
+
+    let account_info_iter = &mut accounts.iter();
+
+    let current_admin = next_account_info(account_info_iter)?;
+    let vault_account = next_account_info(account_info_iter)?;
+
+    log_accounts(&[(current_admin, "Admin"), (vault_account, "Vault")]);
+
+    if !current_admin.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let admin_pubkey = parse_role(&admin, "Admin")?;
+    let operator_pubkey = parse_role(&operator, "Operator")?;
+    let fee_recipient_pubkey = parse_role(&fee_recipient, "Fee recipient")?;
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.admin != *current_admin.key {
+        msg!("Invalid Vault Admin");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let record = VaultRolesUpdatedRecord {
+        ts: clock.unix_timestamp,
+        vault: vault.pubkey,
+        admin_before: vault.admin,
+        admin_after: admin_pubkey,
+        operator_before: vault.operator,
+        operator_after: operator_pubkey,
+        fee_recipient_before: vault.fee_recipient,
+        fee_recipient_after: fee_recipient_pubkey,
+    };
+
+    vault.admin = admin_pubkey;
+    vault.operator = operator_pubkey;
+    vault.fee_recipient = fee_recipient_pubkey;
+
+    Vault::save(&vault, vault_account)?;
+
+    log_data(&record)?;
+    log_params(&record);
+
+    Ok(())
+}
+
+fn parse_role(role: &str, label: &str) -> Result<Pubkey, ProgramError> {
+    let pubkey = match Pubkey::from_str(role) {
+        Ok(pubkey) => pubkey,
+        Err(_) => {
+            msg!("{} string is not a valid Pubkey: {}", label, role);
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    // A default key would lock the role forever
+    if pubkey == Pubkey::default() {
+        msg!("{} can not be the default Pubkey", label);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(pubkey)
+}
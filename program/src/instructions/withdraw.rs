@@ -1,24 +1,25 @@
-use std::collections::BTreeSet;
-
 use crate::{
     common::{
-        bytes32_to_string, deserialize_zero_copy, log_accounts, transfer_fees_from_vault,
-        transfer_to_user_from_vault,
+        burn_shares_from_user, bytes32_to_string, calculate_vault_equity_and_oracle_confidence,
+        deserialize_zero_copy, drift_account_key_error, drift_account_privilege_error,
+        get_market_accounts, get_token_balance, invoke_drift_signed, log_accounts,
+        transfer_fees_from_vault, transfer_to_user_from_vault, update_share_price,
+        update_vault_fuel, validate_vault_not_in_liquidation,
     },
-    drift::{WithdrawIxArgs, WithdrawIxData},
-    error::wrap_drift_error,
-    state::{Vault, VaultDepositor},
+    custom_validate,
+    error::VaultErrorCode,
+    instructions::drift_settle_pnl,
+    state::{Treasury, Vault, VaultDepositor},
 };
-use drift::{
-    instructions::optional_accounts::{load_maps, AccountMaps},
-    state::{spot_market_map::get_writable_spot_market_set, user::User},
+use drift::{math::constants::QUOTE_SPOT_MARKET_INDEX, state::user::User};
+use drift_interface::{
+    withdraw_ix_with_program_id, withdraw_verify_account_keys, withdraw_verify_writable_privileges,
+    SettlePnlMode, WithdrawAccounts, WithdrawIxArgs, WithdrawKeys,
 };
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
-    instruction::{AccountMeta, Instruction},
     msg,
-    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::{clock::Clock, Sysvar},
@@ -53,6 +54,8 @@ pub fn withdraw<'info>(
 
     let token_program = next_account_info(&mut account_info_iter)?;
 
+    let share_price = next_account_info(&mut account_info_iter)?;
+
     log_accounts(&[
         // Vault accounts
         (vault_account, "Vault"),
@@ -75,6 +78,7 @@ pub fn withdraw<'info>(
         (mint, "Mint"),
         // System accounts
         (token_program, "Token Program"),
+        (share_price, "Share Price"),
     ]);
 
     if !authority.is_signer {
@@ -95,36 +99,112 @@ pub fn withdraw<'info>(
         return Err(ProgramError::InvalidArgument);
     }
 
-    let mut vault = Vault::get(vault_account);
-    let mut vault_depositor = VaultDepositor::get(vault_depositor_account);
+    let mut vault = Vault::get(vault_account)?;
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
 
-    let writable_spot_market_set = get_writable_spot_market_set(vault.spot_market_index);
+    let (treasury_pda, _) = Treasury::get_pda(&bytes32_to_string(vault.name), program_id);
 
-    // Load maps with proper account references and types
-    let AccountMaps {
-        perp_market_map,
-        spot_market_map,
-        mut oracle_map,
-    } = match load_maps(
-        &mut accounts[9..11].iter().peekable(),
-        &BTreeSet::new(),
-        &writable_spot_market_set,
-        clock.slot,
-        None,
-    ) {
-        Ok(maps) => maps,
-        Err(e) => return Err(ProgramError::Custom(e as u32)),
+    if treasury_pda != *treasury.key {
+        msg!("Invalid seeds for Treasury PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if Treasury::get(treasury).token_account != *treasury_token_account.key {
+        msg!("Invalid Treasury Token Account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Vaults with a share mint take the share mint, the authority's share token account the
+    // requested share tokens are burned from and the spl token program
+    let share_accounts = if vault.has_share_mint() {
+        let share_mint = next_account_info(&mut account_info_iter)?;
+        let share_token_account = next_account_info(&mut account_info_iter)?;
+        let share_token_program = next_account_info(&mut account_info_iter)?;
+
+        custom_validate!(
+            vault.share_mint == *share_mint.key,
+            VaultErrorCode::InvalidShareMint,
+            "vault share mint is {}",
+            vault.share_mint
+        )?;
+
+        Some((share_mint, share_token_account, share_token_program))
+    } else {
+        None
     };
 
-    // User details
-    let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+    let remaining_accounts_start = if share_accounts.is_some() { 21 } else { 18 };
+    let remaining_accounts = &accounts[remaining_accounts_start..];
+
+    // Share prices are unreliable while drift liquidates the vault
+    validate_vault_not_in_liquidation(&vault, drift_user, remaining_accounts)?;
+
+    // Fuel earned so far goes to the shares that earned it
+    update_vault_fuel(&mut vault, drift_user_stats)?;
+
+    let market_accounts_passed =
+        accounts.len() > remaining_accounts_start + vault.get_num_remaining_vault_accounts();
 
-    let vault_equity = vault
-        .calculate_total_equity(&user, &perp_market_map, &spot_market_map, &mut oracle_map)
-        .map_err(wrap_drift_error)?;
+    // Realize the primary sub account's perp pnl in quote first so it can pay the withdraw,
+    // needs the perp market accounts
+    if vault.spot_market_index == QUOTE_SPOT_MARKET_INDEX && market_accounts_passed {
+        let perp_market_indexes = {
+            let user = deserialize_zero_copy::<User>(&*drift_user.try_borrow_data()?);
+            user.perp_positions
+                .iter()
+                .filter(|position| !position.is_available())
+                .map(|position| position.market_index)
+                .collect::<Vec<u16>>()
+        };
+
+        if !perp_market_indexes.is_empty() {
+            drift_settle_pnl(
+                &vault,
+                perp_market_indexes,
+                SettlePnlMode::TrySettle,
+                drift_program,
+                drift_state,
+                drift_user,
+                vault_account,
+                drift_spot_market_vault,
+                get_market_accounts(&vault, &accounts[9..11], remaining_accounts),
+            )?;
+        }
+    }
+
+    // The sub account users and then the markets for all their positions follow the fixed accounts
+    let (vault_equity, oracle_confidence) = calculate_vault_equity_and_oracle_confidence(
+        &vault,
+        drift_user,
+        &accounts[9..11],
+        remaining_accounts,
+        clock.slot,
+    )?;
 
     msg!("vault_equity: {:?}", vault_equity);
 
+    // The requested shares the ledger doesn't hold are burned from the authority's share tokens
+    if let Some((share_mint, share_token_account, share_token_program)) = share_accounts {
+        let token_shares = vault_depositor
+            .last_withdraw_request
+            .shares
+            .saturating_sub(vault_depositor.vault_shares);
+
+        if token_shares > 0 {
+            let amount = u64::try_from(token_shares).map_err(|_| VaultErrorCode::MathError)?;
+
+            burn_shares_from_user(
+                amount,
+                share_token_program,
+                share_mint,
+                share_token_account,
+                authority,
+            )?;
+
+            vault_depositor.unwrap_shares(amount, &mut vault, clock.unix_timestamp)?;
+        }
+    }
+
     let (user_withdraw_amount, total_deductions) =
         vault_depositor.withdraw(vault_equity, &mut vault, clock.unix_timestamp)?;
 
@@ -133,9 +213,23 @@ pub fn withdraw<'info>(
     Vault::save(&vault, vault_account)?;
     VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
 
+    update_share_price(
+        program_id,
+        &vault,
+        share_price,
+        vault_equity.saturating_sub(user_withdraw_amount + total_deductions),
+        oracle_confidence,
+        clock.slot,
+        clock.unix_timestamp,
+    )?;
+
+    let vault_token_balance_before = get_token_balance(vault_token_account)?;
+
     drift_withdraw(
         &vault,
+        vault.spot_market_index,
         user_withdraw_amount + total_deductions,
+        false,
         drift_program,
         drift_state,
         drift_user,
@@ -145,17 +239,25 @@ pub fn withdraw<'info>(
         drift_signer,
         vault_token_account,
         token_program,
-        drift_oracle,
-        drift_spot_market,
+        get_market_accounts(&vault, &accounts[9..11], remaining_accounts),
+        Some(mint),
     )?;
 
+    // Mints with a transfer fee deliver less than withdrawn from drift, the depositor bears the
+    // difference
+    let received_amount =
+        get_token_balance(vault_token_account)?.saturating_sub(vault_token_balance_before);
+    let user_withdraw_amount = received_amount.saturating_sub(total_deductions);
+
+    msg!("user_withdraw_amount received: {}", user_withdraw_amount);
+
     transfer_fees_from_vault(
         &vault,
         total_deductions,
         token_program,
-        user_token_account,
+        vault_token_account,
         treasury_token_account,
-        authority,
+        vault_account,
         mint,
     )?;
 
@@ -175,7 +277,10 @@ pub fn withdraw<'info>(
 /// Executes withdraw from Drift protocol
 pub fn drift_withdraw<'a>(
     vault: &Vault,
+    market_index: u16,
     amount: u64,
+    // Whether drift may only reduce a deposit, never borrow
+    reduce_only: bool,
     // Individual accounts
     drift_program: &AccountInfo<'a>,
     drift_state: &AccountInfo<'a>,
@@ -186,96 +291,47 @@ pub fn drift_withdraw<'a>(
     drift_signer: &AccountInfo<'a>,
     vault_token_account: &AccountInfo<'a>,
     token_program: &AccountInfo<'a>,
-    drift_oracle: &AccountInfo<'a>,
-    drift_spot_market: &AccountInfo<'a>,
+    // Oracles, spot markets and perp markets for drift
+    market_accounts: &[AccountInfo<'a>],
+    // The mint of the withdrawn token, drift needs it to transfer token-2022 tokens
+    mint: Option<&AccountInfo<'a>>,
 ) -> ProgramResult {
     msg!("Withdrawing from Drift to Vault...");
 
-    let accounts_meta = vec![
-        AccountMeta {
-            pubkey: *drift_state.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *drift_user.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_user_stats.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *vault_account.key,
-            is_signer: true,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_spot_market_vault.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_signer.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *vault_token_account.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *token_program.key,
-            is_signer: false,
-            is_writable: false,
-        },
-        AccountMeta {
-            pubkey: *drift_oracle.key,
-            is_signer: false,
-            is_writable: true,
-        },
-        AccountMeta {
-            pubkey: *drift_spot_market.key,
-            is_signer: false,
-            is_writable: true,
+    let accounts = WithdrawAccounts {
+        state: drift_state,
+        user: drift_user,
+        user_stats: drift_user_stats,
+        authority: vault_account,
+        spot_market_vault: drift_spot_market_vault,
+        drift_signer,
+        user_token_account: vault_token_account,
+        token_program,
+    };
+
+    withdraw_verify_account_keys(
+        accounts,
+        WithdrawKeys {
+            user_stats: vault.user_stats,
+            authority: vault.pubkey,
+            ..accounts.into()
         },
-    ];
+    )
+    .map_err(drift_account_key_error)?;
+    withdraw_verify_writable_privileges(accounts).map_err(drift_account_privilege_error)?;
 
-    let args = WithdrawIxArgs {
-        market_index: vault.spot_market_index,
-        amount,
-        reduce_only: false,
-    };
+    let ix = withdraw_ix_with_program_id(
+        *drift_program.key,
+        accounts.into(),
+        WithdrawIxArgs {
+            market_index,
+            amount,
+            reduce_only,
+        },
+    )?;
 
-    let data: WithdrawIxData = args.into();
+    let mut remaining_accounts = market_accounts.to_vec();
+    remaining_accounts.extend(mint.cloned());
 
-    let ix = Instruction {
-        program_id: *drift_program.key,
-        accounts: accounts_meta,
-        data: data.try_to_vec()?,
-    };
-    invoke_signed(
-        &ix,
-        &[
-            drift_state.clone(),
-            drift_user.clone(),
-            drift_user_stats.clone(),
-            vault_account.clone(),
-            drift_spot_market_vault.clone(),
-            drift_signer.clone(),
-            vault_token_account.clone(),
-            token_program.clone(),
-            drift_oracle.clone(),
-            drift_spot_market.clone(),
-            drift_program.clone(),
-        ],
-        &[&[
-            b"vault",
-            bytes32_to_string(vault.name).as_ref(),
-            &[vault.bump],
-        ]],
-    )
+    invoke_drift_signed(ix, accounts, &remaining_accounts, drift_program, vault)
 }
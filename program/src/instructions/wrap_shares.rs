@@ -0,0 +1,92 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::{
+    common::{log_accounts, mint_shares_to_user},
+    custom_validate,
+    error::VaultErrorCode,
+    state::{Vault, VaultDepositor},
+};
+
+/// Moves unlocked shares from the depositor's ledger into share tokens minted to any token
+/// account of the vault's share mint
+pub fn wrap_shares<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    shares: u128,
+) -> ProgramResult {
+    msg!("Wrapping shares...");
+    msg!("shares: {}", shares);
+
+    let clock = &Clock::get()?;
+
+    let mut account_info_iter = accounts.iter();
+
+    let vault_account = next_account_info(&mut account_info_iter)?;
+    let vault_depositor_account = next_account_info(&mut account_info_iter)?;
+    let authority = next_account_info(&mut account_info_iter)?;
+    let share_mint = next_account_info(&mut account_info_iter)?;
+    let share_token_account = next_account_info(&mut account_info_iter)?;
+
+    let token_program = next_account_info(&mut account_info_iter)?;
+
+    log_accounts(&[
+        (vault_account, "Vault"),
+        (vault_depositor_account, "Vault Depositor"),
+        (authority, "Authority"),
+        (share_mint, "Share Mint"),
+        (share_token_account, "Share Token Account"),
+        (token_program, "Token Program"),
+    ]);
+
+    if !authority.is_signer {
+        msg!("Missing required signature");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = Vault::get(vault_account)?;
+
+    if vault.pubkey != *vault_account.key {
+        msg!("Invalid seeds for Vault PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (vault_depositor_pda, _) =
+        VaultDepositor::get_pda(vault_account.key, authority.key, program_id);
+
+    if vault_depositor_pda != *vault_depositor_account.key {
+        msg!("Invalid seeds for Vault Depositor PDA");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    custom_validate!(
+        vault.has_share_mint() && vault.share_mint == *share_mint.key,
+        VaultErrorCode::InvalidShareMint,
+        "vault share mint is {}",
+        vault.share_mint
+    )?;
+
+    let mut vault_depositor = VaultDepositor::get(vault_depositor_account)?;
+
+    let amount = vault_depositor.wrap_shares(shares, &mut vault, clock.unix_timestamp)?;
+
+    VaultDepositor::save(&vault_depositor, vault_depositor_account)?;
+    Vault::save(&vault, vault_account)?;
+
+    mint_shares_to_user(
+        &vault,
+        amount,
+        token_program,
+        share_mint,
+        share_token_account,
+        vault_account,
+    )?;
+
+    Ok(())
+}
@@ -4,7 +4,6 @@ pub mod processor;
 pub mod state;
 pub mod error;
 pub mod instructions;
-pub mod drift;
 pub mod macros;
 pub mod common;
 pub mod constants;
\ No newline at end of file
@@ -1,10 +1,21 @@
 use crate::instruction::VaultInstruction;
 use crate::instructions::manager_deposit::manager_deposit;
 use crate::instructions::{
-    cancel_withdraw_request, deposit, initialize_drift_vault_with_bulk, initialize_vault_depositor,
-    manager_collect_fees, manager_withdraw, request_withdraw, reset_delegate, update_vault,
-    update_vault_delegate, withdraw, UpdateVaultParams, VaultParams,
+    add_insurance_fund_stake, cancel_orders, cancel_withdraw_request, convert_to_assets,
+    convert_to_shares, deposit, deposit_collateral, initialize_drift_vault_with_bulk,
+    initialize_insurance_fund_stake, initialize_referrer_name, initialize_share_mint,
+    initialize_sub_account, initialize_vault_depositor, manager_collect_fees, manager_withdraw,
+    migrate_vault, migrate_vault_depositor, mint_lot_receipt, place_orders, preview_deposit,
+    preview_withdraw, record_vault_liquidation, redeem_lot_receipt, refresh_depositor_stats,
+    refresh_share_price, remove_insurance_fund_stake, request_remove_insurance_fund_stake,
+    request_withdraw, reset_delegate, set_primary_sub_account, settle_vault_pnl, swap_collateral,
+    sweep_referrer_rewards, transfer_depositor_position, unwrap_shares, update_collateral_markets,
+    update_depositor_cap, update_fuel_bonus, update_high_leverage_mode, update_lot_receipts,
+    update_margin_ratio, update_margin_trading_enabled, update_nav_history, update_trading_policy,
+    update_vault, update_vault_delegate, update_vault_metadata, update_vault_roles, withdraw,
+    wrap_shares, UpdateVaultParams, VaultMetadataParams, VaultParams,
 };
+use crate::state::TradingPolicyParams;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 pub fn process_instruction<'a>(
@@ -25,6 +36,7 @@ pub fn process_instruction<'a>(
             hurdle_rate,
             spot_market_index,
             permissioned,
+            max_deposit_per_depositor,
         } => initialize_drift_vault_with_bulk(
             program_id,
             accounts,
@@ -39,6 +51,7 @@ pub fn process_instruction<'a>(
                 hurdle_rate,
                 spot_market_index,
                 permissioned,
+                max_deposit_per_depositor,
             },
         ),
         VaultInstruction::InitializeVaultDepositor {} => {
@@ -73,6 +86,7 @@ pub fn process_instruction<'a>(
             profit_share,
             hurdle_rate,
             permissioned,
+            max_deposit_per_depositor,
         } => update_vault(
             program_id,
             accounts,
@@ -85,8 +99,156 @@ pub fn process_instruction<'a>(
                 profit_share,
                 hurdle_rate,
                 permissioned,
+                max_deposit_per_depositor,
             },
         ),
         VaultInstruction::ResetDelegate {} => reset_delegate(program_id, accounts),
+        VaultInstruction::UpdateTradingPolicy {
+            allowed_perp_markets,
+            allowed_spot_markets,
+            max_leverage,
+            max_position_notional,
+            allowed_order_types,
+            max_swap_slippage,
+            enabled,
+        } => update_trading_policy(
+            program_id,
+            accounts,
+            TradingPolicyParams {
+                allowed_perp_markets,
+                allowed_spot_markets,
+                max_leverage,
+                max_position_notional,
+                allowed_order_types,
+                max_swap_slippage,
+                enabled,
+            },
+        ),
+        VaultInstruction::PlaceOrders { params } => place_orders(program_id, accounts, params),
+        VaultInstruction::CancelOrders {
+            market_type,
+            market_index,
+            direction,
+        } => cancel_orders(program_id, accounts, market_type, market_index, direction),
+        VaultInstruction::TransferDepositorPosition {} => {
+            transfer_depositor_position(program_id, accounts)
+        }
+        VaultInstruction::UpdateDepositorCap { deposit_cap } => {
+            update_depositor_cap(program_id, accounts, deposit_cap)
+        }
+        VaultInstruction::UpdateVaultRoles {
+            admin,
+            operator,
+            fee_recipient,
+        } => update_vault_roles(program_id, accounts, admin, operator, fee_recipient),
+        VaultInstruction::UpdateVaultMetadata {
+            symbol,
+            description,
+            tags,
+            website,
+            risk_rating,
+            uri,
+        } => update_vault_metadata(
+            program_id,
+            accounts,
+            VaultMetadataParams {
+                symbol,
+                description,
+                tags,
+                website,
+                risk_rating,
+                uri,
+            },
+        ),
+        VaultInstruction::InitializeSubAccount { sub_account_id } => {
+            initialize_sub_account(program_id, accounts, sub_account_id)
+        }
+        VaultInstruction::SetPrimarySubAccount { sub_account_id } => {
+            set_primary_sub_account(program_id, accounts, sub_account_id)
+        }
+        VaultInstruction::UpdateCollateralMarkets {
+            spot_market_indexes,
+        } => update_collateral_markets(program_id, accounts, spot_market_indexes),
+        VaultInstruction::DepositCollateral {
+            name,
+            market_index,
+            amount,
+        } => deposit_collateral(program_id, accounts, name, market_index, amount),
+        VaultInstruction::SettleVaultPnl { market_indexes } => {
+            settle_vault_pnl(program_id, accounts, market_indexes)
+        }
+        VaultInstruction::InitializeInsuranceFundStake {} => {
+            initialize_insurance_fund_stake(program_id, accounts)
+        }
+        VaultInstruction::AddInsuranceFundStake { amount } => {
+            add_insurance_fund_stake(program_id, accounts, amount)
+        }
+        VaultInstruction::RequestRemoveInsuranceFundStake { amount } => {
+            request_remove_insurance_fund_stake(program_id, accounts, amount)
+        }
+        VaultInstruction::RemoveInsuranceFundStake {} => {
+            remove_insurance_fund_stake(program_id, accounts)
+        }
+        VaultInstruction::SwapCollateral {
+            in_market_index,
+            out_market_index,
+            amount_in,
+            num_swap_accounts,
+            swap_data,
+        } => swap_collateral(
+            program_id,
+            accounts,
+            in_market_index,
+            out_market_index,
+            amount_in,
+            num_swap_accounts,
+            swap_data,
+        ),
+        VaultInstruction::InitializeReferrerName {} => {
+            initialize_referrer_name(program_id, accounts)
+        }
+        VaultInstruction::SweepReferrerRewards {} => sweep_referrer_rewards(program_id, accounts),
+        VaultInstruction::UpdateMarginRatio { margin_ratio } => {
+            update_margin_ratio(program_id, accounts, margin_ratio)
+        }
+        VaultInstruction::UpdateMarginTradingEnabled { enabled } => {
+            update_margin_trading_enabled(program_id, accounts, enabled)
+        }
+        VaultInstruction::UpdateHighLeverageMode { enabled } => {
+            update_high_leverage_mode(program_id, accounts, enabled)
+        }
+        VaultInstruction::RecordVaultLiquidation {} => {
+            record_vault_liquidation(program_id, accounts)
+        }
+        VaultInstruction::UpdateFuelBonus {} => update_fuel_bonus(program_id, accounts),
+        VaultInstruction::InitializeShareMint {} => initialize_share_mint(program_id, accounts),
+        VaultInstruction::WrapShares { shares } => wrap_shares(program_id, accounts, shares),
+        VaultInstruction::UnwrapShares { shares } => unwrap_shares(program_id, accounts, shares),
+        VaultInstruction::PreviewDeposit { amount } => {
+            preview_deposit(program_id, accounts, amount)
+        }
+        VaultInstruction::PreviewWithdraw { amount } => {
+            preview_withdraw(program_id, accounts, amount)
+        }
+        VaultInstruction::ConvertToShares { amount } => {
+            convert_to_shares(program_id, accounts, amount)
+        }
+        VaultInstruction::ConvertToAssets { shares } => {
+            convert_to_assets(program_id, accounts, shares)
+        }
+        VaultInstruction::UpdateLotReceipts { enabled } => {
+            update_lot_receipts(program_id, accounts, enabled)
+        }
+        VaultInstruction::MintLotReceipt { deposit_ts } => {
+            mint_lot_receipt(program_id, accounts, deposit_ts)
+        }
+        VaultInstruction::RedeemLotReceipt {} => redeem_lot_receipt(program_id, accounts),
+        VaultInstruction::RefreshSharePrice {} => refresh_share_price(program_id, accounts),
+        VaultInstruction::UpdateNavHistory { interval } => {
+            update_nav_history(program_id, accounts, interval)
+        }
+        VaultInstruction::RefreshDepositorStats {} => refresh_depositor_stats(program_id, accounts),
+        VaultInstruction::MigrateVaultDepositor {} => migrate_vault_depositor(program_id, accounts),
+        VaultInstruction::MigrateVault {} => migrate_vault(program_id, accounts),
     }
 }
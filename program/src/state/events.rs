@@ -2,6 +2,22 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_program::pubkey::Pubkey;
 
+use super::TradingPolicyParams;
+
+/// A record logged with log_data. The borsh data is prefixed with the first 8 bytes of
+/// sha256("event:<Name>"), the discriminator anchor event parsers expect
+pub trait Event: BorshSerialize {
+    const DISCRIMINATOR: [u8; 8];
+
+    /// The discriminator followed by the borsh serialized record
+    fn data(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Self::DISCRIMINATOR.to_vec();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
+
+pub const VAULT_DEPOSITOR_RECORD_EVENT_DISCM: [u8; 8] = [177, 172, 11, 74, 19, 216, 149, 11];
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
 pub struct VaultDepositorRecord {
     pub ts: i64,
@@ -35,4 +51,275 @@ pub enum VaultDepositorAction {
     CancelWithdrawRequest,
     Withdraw,
     CollectFees,
+    TransferPosition,
+    WrapShares,
+    UnwrapShares,
+    MintLotReceipt,
+    RedeemLotReceipt,
+}
+
+pub const VAULT_PARAMS_UPDATED_RECORD_EVENT_DISCM: [u8; 8] = [90, 78, 98, 11, 99, 75, 47, 33];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct VaultParamsUpdatedRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub params_before: VaultParamsSnapshot,
+    pub params_after: VaultParamsSnapshot,
+}
+
+/// The vault params that can be updated by the admin
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct VaultParamsSnapshot {
+    pub lock_in_period: u64,
+    pub redeem_period: u64,
+    pub max_tokens: u64,
+    pub management_fee: u64,
+    pub min_deposit_amount: u64,
+    pub max_deposit_per_depositor: u64,
+    pub profit_share: u32,
+    pub hurdle_rate: u32,
+    pub permissioned: bool,
+}
+
+pub const DELEGATE_CHANGED_RECORD_EVENT_DISCM: [u8; 8] = [238, 144, 75, 63, 87, 53, 200, 9];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct DelegateChangedRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub operator: Pubkey,
+    pub action: DelegateAction,
+    pub sub_account_id: u16,
+    pub delegate_before: Pubkey,
+    pub delegate_after: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub enum DelegateAction {
+    Update,
+    Reset,
+}
+
+pub const VAULT_ROLES_UPDATED_RECORD_EVENT_DISCM: [u8; 8] = [13, 36, 165, 49, 103, 69, 185, 202];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct VaultRolesUpdatedRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub admin_before: Pubkey,
+    pub admin_after: Pubkey,
+    pub operator_before: Pubkey,
+    pub operator_after: Pubkey,
+    pub fee_recipient_before: Pubkey,
+    pub fee_recipient_after: Pubkey,
+}
+
+pub const VAULT_LIQUIDATION_RECORD_EVENT_DISCM: [u8; 8] = [49, 93, 15, 15, 240, 123, 252, 229];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct VaultLiquidationRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub action: VaultLiquidationAction,
+    /// The sub account that is being liquidated or is bankrupt, the primary when ended
+    pub sub_account_id: u16,
+    pub user: Pubkey,
+    pub liquidation_start_ts: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub enum VaultLiquidationAction {
+    Started,
+    Bankrupt,
+    Ended,
+}
+
+pub const TRADING_POLICY_UPDATED_RECORD_EVENT_DISCM: [u8; 8] =
+    [83, 113, 187, 184, 10, 125, 121, 184];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct TradingPolicyUpdatedRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub operator: Pubkey,
+    pub action: TradingPolicyAction,
+    pub params_before: TradingPolicyParams,
+    pub params_after: TradingPolicyParams,
+    /// When scheduled params can be applied, 0 when applied
+    pub pending_params_ts: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub enum TradingPolicyAction {
+    Scheduled,
+    Applied,
+}
+
+pub const DEPOSITOR_CAP_UPDATED_RECORD_EVENT_DISCM: [u8; 8] = [216, 72, 57, 138, 125, 182, 58, 189];
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Serialize, Deserialize)]
+pub struct DepositorCapUpdatedRecord {
+    pub ts: i64,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub depositor_authority: Pubkey,
+    pub deposit_cap_before: Option<u64>,
+    pub deposit_cap_after: Option<u64>,
+}
+
+impl Event for VaultDepositorRecord {
+    const DISCRIMINATOR: [u8; 8] = VAULT_DEPOSITOR_RECORD_EVENT_DISCM;
+}
+
+impl Event for VaultParamsUpdatedRecord {
+    const DISCRIMINATOR: [u8; 8] = VAULT_PARAMS_UPDATED_RECORD_EVENT_DISCM;
+}
+
+impl Event for DelegateChangedRecord {
+    const DISCRIMINATOR: [u8; 8] = DELEGATE_CHANGED_RECORD_EVENT_DISCM;
+}
+
+impl Event for VaultRolesUpdatedRecord {
+    const DISCRIMINATOR: [u8; 8] = VAULT_ROLES_UPDATED_RECORD_EVENT_DISCM;
+}
+
+impl Event for VaultLiquidationRecord {
+    const DISCRIMINATOR: [u8; 8] = VAULT_LIQUIDATION_RECORD_EVENT_DISCM;
+}
+
+impl Event for TradingPolicyUpdatedRecord {
+    const DISCRIMINATOR: [u8; 8] = TRADING_POLICY_UPDATED_RECORD_EVENT_DISCM;
+}
+
+impl Event for DepositorCapUpdatedRecord {
+    const DISCRIMINATOR: [u8; 8] = DEPOSITOR_CAP_UPDATED_RECORD_EVENT_DISCM;
+}
+
+/// Every record the program logs. Deposits, withdraws, withdraw requests and fee collection are
+/// VaultDepositorRecords told apart by their action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VaultEvent {
+    VaultDepositorRecord(VaultDepositorRecord),
+    VaultParamsUpdatedRecord(VaultParamsUpdatedRecord),
+    DelegateChangedRecord(DelegateChangedRecord),
+    VaultRolesUpdatedRecord(VaultRolesUpdatedRecord),
+    VaultLiquidationRecord(VaultLiquidationRecord),
+    TradingPolicyUpdatedRecord(TradingPolicyUpdatedRecord),
+    DepositorCapUpdatedRecord(DepositorCapUpdatedRecord),
+}
+
+impl VaultEvent {
+    /// Decodes the data of a log_data log, discriminator first
+    pub fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let discriminator = <[u8; 8]>::deserialize(buf)?;
+
+        match discriminator {
+            VAULT_DEPOSITOR_RECORD_EVENT_DISCM => {
+                VaultDepositorRecord::deserialize(buf).map(Self::VaultDepositorRecord)
+            }
+            VAULT_PARAMS_UPDATED_RECORD_EVENT_DISCM => {
+                VaultParamsUpdatedRecord::deserialize(buf).map(Self::VaultParamsUpdatedRecord)
+            }
+            DELEGATE_CHANGED_RECORD_EVENT_DISCM => {
+                DelegateChangedRecord::deserialize(buf).map(Self::DelegateChangedRecord)
+            }
+            VAULT_ROLES_UPDATED_RECORD_EVENT_DISCM => {
+                VaultRolesUpdatedRecord::deserialize(buf).map(Self::VaultRolesUpdatedRecord)
+            }
+            VAULT_LIQUIDATION_RECORD_EVENT_DISCM => {
+                VaultLiquidationRecord::deserialize(buf).map(Self::VaultLiquidationRecord)
+            }
+            TRADING_POLICY_UPDATED_RECORD_EVENT_DISCM => {
+                TradingPolicyUpdatedRecord::deserialize(buf).map(Self::TradingPolicyUpdatedRecord)
+            }
+            DEPOSITOR_CAP_UPDATED_RECORD_EVENT_DISCM => {
+                DepositorCapUpdatedRecord::deserialize(buf).map(Self::DepositorCapUpdatedRecord)
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unknown event discriminator {:?}", discriminator),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::hash::hash;
+
+    fn get_event_discriminator(name: &str) -> [u8; 8] {
+        let mut discriminator = [0; 8];
+        discriminator.copy_from_slice(&hash(format!("event:{}", name).as_bytes()).to_bytes()[..8]);
+        discriminator
+    }
+
+    #[test]
+    pub fn test_event_discriminators() {
+        assert_eq!(
+            VaultDepositorRecord::DISCRIMINATOR,
+            get_event_discriminator("VaultDepositorRecord")
+        );
+        assert_eq!(
+            VaultParamsUpdatedRecord::DISCRIMINATOR,
+            get_event_discriminator("VaultParamsUpdatedRecord")
+        );
+        assert_eq!(
+            DelegateChangedRecord::DISCRIMINATOR,
+            get_event_discriminator("DelegateChangedRecord")
+        );
+        assert_eq!(
+            VaultRolesUpdatedRecord::DISCRIMINATOR,
+            get_event_discriminator("VaultRolesUpdatedRecord")
+        );
+        assert_eq!(
+            VaultLiquidationRecord::DISCRIMINATOR,
+            get_event_discriminator("VaultLiquidationRecord")
+        );
+        assert_eq!(
+            TradingPolicyUpdatedRecord::DISCRIMINATOR,
+            get_event_discriminator("TradingPolicyUpdatedRecord")
+        );
+        assert_eq!(
+            DepositorCapUpdatedRecord::DISCRIMINATOR,
+            get_event_discriminator("DepositorCapUpdatedRecord")
+        );
+
+        // Pinned so a renamed record can't silently change what parsers match on
+        assert_eq!(
+            VaultDepositorRecord::DISCRIMINATOR,
+            [177, 172, 11, 74, 19, 216, 149, 11]
+        );
+        assert_eq!(
+            DepositorCapUpdatedRecord::DISCRIMINATOR,
+            [216, 72, 57, 138, 125, 182, 58, 189]
+        );
+    }
+
+    #[test]
+    pub fn test_deserialize_vault_event() {
+        let record = DepositorCapUpdatedRecord {
+            ts: 1,
+            vault: Pubkey::new_unique(),
+            admin: Pubkey::new_unique(),
+            depositor_authority: Pubkey::new_unique(),
+            deposit_cap_before: None,
+            deposit_cap_after: Some(100),
+        };
+
+        let data = record.data().unwrap();
+
+        assert_eq!(data[..8], DepositorCapUpdatedRecord::DISCRIMINATOR);
+
+        match VaultEvent::deserialize(&mut &data[..]).unwrap() {
+            VaultEvent::DepositorCapUpdatedRecord(event) => {
+                assert_eq!(event.vault, record.vault);
+                assert_eq!(event.depositor_authority, record.depositor_authority);
+                assert_eq!(event.deposit_cap_before, None);
+                assert_eq!(event.deposit_cap_after, Some(100));
+            }
+            _ => panic!("decoded the wrong event"),
+        }
+
+        let mut data = data;
+        data[0] ^= 1;
+
+        assert!(VaultEvent::deserialize(&mut &data[..]).is_err());
+    }
 }
@@ -0,0 +1,53 @@
+use std::mem;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::pubkey::Pubkey;
+
+/// A deposit lot moved out of a depositor's ledger into a receipt NFT. Whoever holds the NFT owns
+/// the lot and can redeem it into their own vault depositor
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct LotReceipt {
+    /// The vault the lot's shares are in
+    pub vault: Pubkey,
+    /// The lot receipt's pubkey. It is a pda of mint
+    pub pubkey: Pubkey,
+    /// The receipt NFT mint, the vault is its update authority
+    pub mint: Pubkey,
+    /// The shares of the lot. They stay in the vault's user_shares and total_shares
+    pub shares: u128,
+    /// Timestamp of the lot's deposit, kept on redeem so the lock-in is not restarted
+    pub ts: i64,
+    /// When the lot's shares can be withdrawn, ts plus the lock-in period at mint
+    pub unlock_ts: i64,
+    /// The net deposits carried by the lot, moved to the depositor redeeming it
+    pub net_deposits: u64,
+    /// The token amount paid for the lot, kept as its cost basis on redeem
+    pub cost_basis: u64,
+    /// The bump for the lot receipt pda
+    pub bump: u8,
+}
+
+impl LotReceipt {
+    pub const SIZE: usize = mem::size_of::<LotReceipt>() + 8;
+
+    pub fn get_lot_receipt_signer_seeds<'a>(mint: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"lot_receipt", mint, bump]
+    }
+
+    pub fn get_pda<'a>(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"lot_receipt", mint.as_ref()], program_id)
+    }
+
+    pub fn get(account: &AccountInfo) -> Self {
+        try_from_slice_unchecked::<LotReceipt>(&account.data.borrow()).unwrap()
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
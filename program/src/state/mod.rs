@@ -3,9 +3,19 @@ pub mod vault_depositor;
 pub mod treasury;
 pub mod events;
 pub mod withdraw_request;
+pub mod trading_policy;
+pub mod vault_metadata;
+pub mod lot_receipt;
+pub mod share_price;
+pub mod nav_history;
 
 pub use vault::*;
 pub use vault_depositor::*;
 pub use treasury::*;
 pub use events::*;
-pub use withdraw_request::*;
\ No newline at end of file
+pub use withdraw_request::*;
+pub use trading_policy::*;
+pub use vault_metadata::*;
+pub use lot_receipt::*;
+pub use share_price::*;
+pub use nav_history::*;
\ No newline at end of file
@@ -0,0 +1,205 @@
+use std::mem;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use crate::constants::NAV_HISTORY_LENGTH;
+
+/// A ring buffer of the vault's equity and shares over time, appended to by RefreshSharePrice at
+/// most once per interval
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct NavHistory {
+    /// The vault the history is of
+    pub vault: Pubkey,
+    /// The nav history's pubkey. It is a pda of vault
+    pub pubkey: Pubkey,
+    /// The min seconds between two snapshots, set by the vault admin
+    pub interval: u64,
+    /// The index the next snapshot is written at
+    pub head: u16,
+    /// The number of snapshots written, at most NAV_HISTORY_LENGTH
+    pub len: u16,
+    /// The bump for the nav history pda
+    pub bump: u8,
+    /// The snapshots, the oldest is overwritten once the buffer is full
+    pub snapshots: [NavSnapshot; NAV_HISTORY_LENGTH],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct NavSnapshot {
+    /// Unix timestamp of the snapshot
+    pub ts: i64,
+    /// The vault equity in the vault's token
+    pub equity: u64,
+    /// The vault's total shares
+    pub total_shares: u128,
+    /// The vault's net deposits
+    pub net_deposits: u64,
+}
+
+impl NavHistory {
+    pub const SIZE: usize = mem::size_of::<NavHistory>() + 8;
+
+    pub fn get_nav_history_signer_seeds<'a>(vault: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"nav_history", vault, bump]
+    }
+
+    pub fn get_pda<'a>(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"nav_history", vault.as_ref()], program_id)
+    }
+
+    pub fn get(account: &AccountInfo) -> Self {
+        try_from_slice_unchecked::<NavHistory>(&account.data.borrow()).unwrap()
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    /// Returns the most recent snapshot, None if nothing was appended yet
+    pub fn get_latest(&self) -> Option<&NavSnapshot> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let index = (self.head as usize + NAV_HISTORY_LENGTH - 1) % NAV_HISTORY_LENGTH;
+        Some(&self.snapshots[index])
+    }
+
+    /// Returns the snapshots from oldest to newest
+    pub fn get_snapshots(&self) -> Vec<NavSnapshot> {
+        let start =
+            (self.head as usize + NAV_HISTORY_LENGTH - self.len as usize) % NAV_HISTORY_LENGTH;
+
+        (0..self.len as usize)
+            .map(|i| self.snapshots[(start + i) % NAV_HISTORY_LENGTH])
+            .collect()
+    }
+
+    /// Appends the snapshot unless the latest one is less than interval seconds older
+    ///
+    /// # Returns
+    /// * `bool` - Whether the snapshot was appended
+    pub fn append(&mut self, snapshot: NavSnapshot) -> bool {
+        if let Some(latest) = self.get_latest() {
+            let next_ts = latest.ts.saturating_add(self.interval as i64);
+
+            if snapshot.ts < next_ts {
+                msg!("Next nav snapshot at {}", next_ts);
+                return false;
+            }
+        }
+
+        self.snapshots[self.head as usize] = snapshot;
+        self.head = ((self.head as usize + 1) % NAV_HISTORY_LENGTH) as u16;
+        self.len = (self.len as usize + 1).min(NAV_HISTORY_LENGTH) as u16;
+
+        msg!(
+            "Nav snapshot appended: ts: {}, equity: {}, total_shares: {}, net_deposits: {}",
+            snapshot.ts,
+            snapshot.equity,
+            snapshot.total_shares,
+            snapshot.net_deposits
+        );
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_nav_history(interval: u64) -> NavHistory {
+        NavHistory {
+            vault: Pubkey::new_unique(),
+            pubkey: Pubkey::new_unique(),
+            interval,
+            head: 0,
+            len: 0,
+            bump: 0,
+            snapshots: [NavSnapshot::default(); NAV_HISTORY_LENGTH],
+        }
+    }
+
+    fn create_snapshot(ts: i64) -> NavSnapshot {
+        NavSnapshot {
+            ts,
+            equity: ts as u64,
+            ..NavSnapshot::default()
+        }
+    }
+
+    #[test]
+    pub fn test_append_interval() {
+        let mut nav_history = create_nav_history(10);
+
+        assert!(nav_history.get_latest().is_none());
+        assert!(nav_history.get_snapshots().is_empty());
+
+        assert!(nav_history.append(create_snapshot(100)));
+        // Less than interval seconds after the latest snapshot
+        assert!(!nav_history.append(create_snapshot(109)));
+        assert!(nav_history.append(create_snapshot(110)));
+
+        assert_eq!(nav_history.head, 2);
+        assert_eq!(nav_history.len, 2);
+        assert_eq!(nav_history.get_latest().unwrap().ts, 110);
+        assert_eq!(
+            nav_history
+                .get_snapshots()
+                .iter()
+                .map(|snapshot| snapshot.ts)
+                .collect::<Vec<i64>>(),
+            vec![100, 110]
+        );
+    }
+
+    #[test]
+    pub fn test_append_wraparound() {
+        let mut nav_history = create_nav_history(1);
+
+        // Fill the buffer exactly
+        for i in 0..NAV_HISTORY_LENGTH as i64 {
+            assert!(nav_history.append(create_snapshot(i)));
+        }
+
+        assert_eq!(nav_history.head, 0);
+        assert_eq!(nav_history.len as usize, NAV_HISTORY_LENGTH);
+        assert_eq!(
+            nav_history.get_latest().unwrap().ts,
+            NAV_HISTORY_LENGTH as i64 - 1
+        );
+
+        // Overwrite the three oldest snapshots
+        for i in 0..3 {
+            assert!(nav_history.append(create_snapshot(NAV_HISTORY_LENGTH as i64 + i)));
+        }
+
+        assert_eq!(nav_history.head, 3);
+        assert_eq!(nav_history.len as usize, NAV_HISTORY_LENGTH);
+        assert_eq!(
+            nav_history.get_latest().unwrap().ts,
+            NAV_HISTORY_LENGTH as i64 + 2
+        );
+
+        let snapshots = nav_history.get_snapshots();
+
+        assert_eq!(snapshots.len(), NAV_HISTORY_LENGTH);
+        // Oldest to newest, starting after the overwritten snapshots
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| snapshot.ts)
+                .collect::<Vec<i64>>(),
+            (3..NAV_HISTORY_LENGTH as i64 + 3).collect::<Vec<i64>>()
+        );
+        assert_eq!(snapshots[0].equity, 3);
+    }
+}
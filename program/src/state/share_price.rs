@@ -0,0 +1,127 @@
+use std::mem;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use drift::math::{casting::Cast, safe_math::SafeMath};
+use serde::{Deserialize, Serialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::pubkey::Pubkey;
+
+use super::Vault;
+use crate::constants::SHARE_PRICE_PRECISION;
+use crate::error::wrap_drift_error;
+
+/// The published share price of a vault, for other programs to price vault shares without
+/// calculating the vault equity. Updated by deposits, withdraws and the RefreshSharePrice crank.
+///
+/// The layout is fixed, little endian with no discriminator or padding:
+///
+/// | offset | size | field            |
+/// |--------|------|------------------|
+/// | 0      | 32   | vault            |
+/// | 32     | 32   | pubkey           |
+/// | 64     | 16   | nav_per_share    |
+/// | 80     | 16   | confidence       |
+/// | 96     | 8    | total_equity     |
+/// | 104    | 16   | total_shares     |
+/// | 120    | 8    | last_update_slot |
+/// | 128    | 8    | last_update_ts   |
+/// | 136    | 1    | bump             |
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct SharePrice {
+    /// The vault priced
+    pub vault: Pubkey,
+    /// The share price's pubkey. It is a pda of vault
+    pub pubkey: Pubkey,
+    /// The vault's token amount one share is worth: SHARE_PRICE_PRECISION
+    pub nav_per_share: u128,
+    /// Half the width of the band around nav_per_share from the confidence of the vault's spot
+    /// market oracle: SHARE_PRICE_PRECISION
+    pub confidence: u128,
+    /// The vault equity in the vault's token
+    pub total_equity: u64,
+    /// The vault's total shares
+    pub total_shares: u128,
+    /// The slot the price was last updated at. Readers should reject prices that are too old
+    pub last_update_slot: u64,
+    /// The unix timestamp the price was last updated at
+    pub last_update_ts: i64,
+    /// The bump for the share price pda
+    pub bump: u8,
+}
+
+impl SharePrice {
+    /// The length of the fixed layout
+    pub const LEN: usize = 137;
+
+    pub const SIZE: usize = mem::size_of::<SharePrice>() + 8;
+
+    pub fn get_share_price_signer_seeds<'a>(vault: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"share_price", vault, bump]
+    }
+
+    pub fn get_pda<'a>(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"share_price", vault.as_ref()], program_id)
+    }
+
+    pub fn get(account: &AccountInfo) -> Self {
+        try_from_slice_unchecked::<SharePrice>(&account.data.borrow()).unwrap()
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    /// Prices the vault's shares at vault_equity
+    ///
+    /// # Arguments
+    /// * `vault` - The vault priced, with its total shares matching vault_equity
+    /// * `vault_equity` - The vault equity in the vault's token
+    /// * `oracle_confidence` - The confidence of the vault's spot market oracle relative to its
+    ///   price: SHARE_PRICE_PRECISION
+    /// * `slot` - Current slot
+    /// * `now` - Current Unix timestamp
+    pub fn update(
+        &mut self,
+        vault: &Vault,
+        vault_equity: u64,
+        oracle_confidence: u128,
+        slot: u64,
+        now: i64,
+    ) -> ProgramResult {
+        let nav_per_share = if vault.total_shares == 0 {
+            SHARE_PRICE_PRECISION
+        } else {
+            vault_equity
+                .cast::<u128>()
+                .and_then(|vault_equity| vault_equity.safe_mul(SHARE_PRICE_PRECISION))
+                .and_then(|vault_equity| vault_equity.safe_div(vault.total_shares))
+                .map_err(wrap_drift_error)?
+        };
+
+        let confidence = nav_per_share
+            .safe_mul(oracle_confidence)
+            .and_then(|confidence| confidence.safe_div(SHARE_PRICE_PRECISION))
+            .map_err(wrap_drift_error)?;
+
+        self.nav_per_share = nav_per_share;
+        self.confidence = confidence;
+        self.total_equity = vault_equity;
+        self.total_shares = vault.total_shares;
+        self.last_update_slot = slot;
+        self.last_update_ts = now;
+
+        msg!(
+            "Share price updated: nav_per_share: {}, confidence: {}, total_equity: {}, total_shares: {}",
+            nav_per_share,
+            confidence,
+            vault_equity,
+            vault.total_shares
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,900 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use drift::controller::position::PositionDirection;
+use drift::math::casting::Cast;
+use drift::math::constants::BASE_PRECISION;
+use drift::math::margin::calculate_user_equity;
+use drift::state::oracle_map::OracleMap;
+use drift::state::order_params::OrderParams;
+use drift::state::perp_market_map::PerpMarketMap;
+use drift::state::spot_market_map::SpotMarketMap;
+use drift::state::user::{MarketType, OrderType, SpotPosition, User};
+use serde::{Deserialize, Serialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::constants::{MAX_TRADING_POLICY_MARKETS, PERCENTAGE_PRECISION};
+use crate::custom_validate;
+use crate::error::{wrap_drift_error, VaultErrorCode};
+use crate::state::calculate_collateral_value_from_prices;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct TradingPolicy {
+    /// The vault the policy applies to
+    pub vault: Pubkey,
+    /// The trading policy's pubkey. It is a pda of vault
+    pub pubkey: Pubkey,
+    /// The perp markets the vault is allowed to place orders in
+    pub allowed_perp_markets: Vec<u16>,
+    /// The spot markets the vault is allowed to place orders in
+    pub allowed_spot_markets: Vec<u16>,
+    /// Max worst case perp notional plus spot liabilities over vault equity: PERCENTAGE_PRECISION
+    pub max_leverage: u64,
+    /// Max worst case notional of a single position (in quote): QUOTE_PRECISION
+    pub max_position_notional: u64,
+    /// Bitmask of allowed order types, bit n set allows drift OrderType n
+    pub allowed_order_types: u8,
+    /// Max loss of vault equity a swap may cause, relative to the swapped value: PERCENTAGE_PRECISION.
+    /// 0 disables swaps
+    pub max_swap_slippage: u64,
+    /// Whether trading goes through the program and is checked against this policy
+    pub enabled: bool,
+    /// The bump for the trading policy pda
+    pub bump: u8,
+    /// Params loosening the policy, waiting for their timelock
+    pub pending_params: Option<TradingPolicyParams>,
+    /// When the pending params can be applied, 0 if none are pending
+    pub pending_params_ts: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TradingPolicyParams {
+    pub allowed_perp_markets: Vec<u16>,
+    pub allowed_spot_markets: Vec<u16>,
+    pub max_leverage: u64,
+    pub max_position_notional: u64,
+    pub allowed_order_types: u8,
+    pub max_swap_slippage: u64,
+    pub enabled: bool,
+}
+
+impl TradingPolicy {
+    pub const SIZE: usize = mem::size_of::<TradingPolicy>()
+        // The allowed markets of the policy and of the pending params
+        + mem::size_of::<u16>() * MAX_TRADING_POLICY_MARKETS * 4
+        + 8;
+
+    pub fn get_trading_policy_signer_seeds<'a>(vault: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"trading_policy", vault, bump]
+    }
+
+    pub fn get_pda<'a>(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"trading_policy", vault.as_ref()], program_id)
+    }
+
+    pub fn get(account: &AccountInfo) -> Self {
+        try_from_slice_unchecked::<TradingPolicy>(&account.data.borrow()).unwrap()
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    /// The params the policy is currently enforcing
+    pub fn get_params(&self) -> TradingPolicyParams {
+        TradingPolicyParams {
+            allowed_perp_markets: self.allowed_perp_markets.clone(),
+            allowed_spot_markets: self.allowed_spot_markets.clone(),
+            max_leverage: self.max_leverage,
+            max_position_notional: self.max_position_notional,
+            allowed_order_types: self.allowed_order_types,
+            max_swap_slippage: self.max_swap_slippage,
+            enabled: self.enabled,
+        }
+    }
+
+    pub fn order_type_mask(order_type: OrderType) -> u8 {
+        1 << (order_type as u8)
+    }
+
+    /// Updates the policy and returns whether the params apply now. Tightening the policy
+    /// applies immediately. Loosening it lets the vault take more risk, so it is scheduled and
+    /// applies when requested again after the timelock period, giving depositors time to exit
+    pub fn update_params(
+        &mut self,
+        params: TradingPolicyParams,
+        timelock_period: u64,
+        now: i64,
+    ) -> Result<bool, ProgramError> {
+        custom_validate!(
+            params.allowed_perp_markets.len() <= MAX_TRADING_POLICY_MARKETS
+                && params.allowed_spot_markets.len() <= MAX_TRADING_POLICY_MARKETS,
+            VaultErrorCode::InvalidTradingPolicy,
+            "at most {} perp and {} spot markets can be allowed",
+            MAX_TRADING_POLICY_MARKETS,
+            MAX_TRADING_POLICY_MARKETS
+        )?;
+
+        custom_validate!(
+            params.max_leverage > 0 && params.max_position_notional > 0,
+            VaultErrorCode::InvalidTradingPolicy,
+            "max leverage and max position notional must be > 0"
+        )?;
+
+        custom_validate!(
+            params.max_swap_slippage as u128 <= PERCENTAGE_PRECISION,
+            VaultErrorCode::InvalidTradingPolicy,
+            "max swap slippage must be <= 100%"
+        )?;
+
+        let loosening = self.is_loosened_by(&params);
+        let pending = self.pending_params_ts != 0 && self.pending_params.as_ref() == Some(&params);
+
+        if loosening && !pending {
+            self.pending_params = Some(params);
+            self.pending_params_ts = (now as u64).saturating_add(timelock_period);
+
+            msg!(
+                "trading policy update scheduled for {}",
+                self.pending_params_ts
+            );
+
+            return Ok(false);
+        }
+
+        if loosening {
+            custom_validate!(
+                now as u64 >= self.pending_params_ts,
+                VaultErrorCode::TradingPolicyTimelocked,
+                "trading policy update can be applied at {}",
+                self.pending_params_ts
+            )?;
+        }
+
+        self.allowed_perp_markets = params.allowed_perp_markets;
+        self.allowed_spot_markets = params.allowed_spot_markets;
+        self.max_leverage = params.max_leverage;
+        self.max_position_notional = params.max_position_notional;
+        self.allowed_order_types = params.allowed_order_types;
+        self.max_swap_slippage = params.max_swap_slippage;
+        self.enabled = params.enabled;
+        self.pending_params = None;
+        self.pending_params_ts = 0;
+
+        Ok(true)
+    }
+
+    /// Whether the params allow anything the policy in force does not. A disabled policy
+    /// restricts nothing, so nothing loosens it
+    pub fn is_loosened_by(&self, params: &TradingPolicyParams) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        !params.enabled
+            || params
+                .allowed_perp_markets
+                .iter()
+                .any(|market_index| !self.allowed_perp_markets.contains(market_index))
+            || params
+                .allowed_spot_markets
+                .iter()
+                .any(|market_index| !self.allowed_spot_markets.contains(market_index))
+            || params.max_leverage > self.max_leverage
+            || params.max_position_notional > self.max_position_notional
+            || params.allowed_order_types & !self.allowed_order_types != 0
+            || params.max_swap_slippage > self.max_swap_slippage
+    }
+
+    /// Validates a batch of orders against the policy
+    ///
+    /// Each order must be in an allowed market and of an allowed order type. The worst case
+    /// notional of every position the batch touches, assuming all open and new orders fill,
+    /// must stay within `max_position_notional`, and the worst case perp notional plus spot
+    /// liabilities across all positions must stay within `max_leverage` of the user's equity.
+    ///
+    /// # Arguments
+    /// * `orders` - The orders about to be placed
+    /// * `user` - The vault's drift user the orders are placed for
+    /// * `perp_market_map` - Perp markets for every perp position and order
+    /// * `spot_market_map` - Spot markets for every spot position and order
+    /// * `oracle_map` - Oracles for the markets above
+    pub fn validate_orders(
+        &self,
+        orders: &[OrderParams],
+        user: &User,
+        perp_market_map: &PerpMarketMap,
+        spot_market_map: &SpotMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> ProgramResult {
+        custom_validate!(
+            self.enabled,
+            VaultErrorCode::TradingPolicyDisabled,
+            "trading policy is not enabled for vault"
+        )?;
+
+        // New (bids, asks) in base per market touched by the batch
+        let mut new_perp_orders: BTreeMap<u16, (i128, i128)> = BTreeMap::new();
+        let mut new_spot_orders: BTreeMap<u16, (i128, i128)> = BTreeMap::new();
+
+        for order in orders.iter() {
+            self.validate_order_params(order)?;
+
+            let new_orders = match order.market_type {
+                MarketType::Perp => new_perp_orders.entry(order.market_index).or_insert((0, 0)),
+                MarketType::Spot => new_spot_orders.entry(order.market_index).or_insert((0, 0)),
+            };
+
+            let base_asset_amount = order
+                .base_asset_amount
+                .cast::<i128>()
+                .map_err(wrap_drift_error)?;
+            match order.direction {
+                PositionDirection::Long => new_orders.0 += base_asset_amount,
+                PositionDirection::Short => new_orders.1 -= base_asset_amount,
+            }
+        }
+
+        let mut total_perp_notional: u128 = 0;
+
+        for perp_position in user.perp_positions.iter() {
+            if perp_position.is_available() {
+                continue;
+            }
+
+            let (new_bids, new_asks) = new_perp_orders
+                .remove(&perp_position.market_index)
+                .unwrap_or((0, 0));
+
+            let notional = self.calculate_perp_notional(
+                perp_position.market_index,
+                perp_position
+                    .base_asset_amount
+                    .cast::<i128>()
+                    .map_err(wrap_drift_error)?,
+                perp_position
+                    .open_bids
+                    .cast::<i128>()
+                    .map_err(wrap_drift_error)?
+                    + new_bids,
+                perp_position
+                    .open_asks
+                    .cast::<i128>()
+                    .map_err(wrap_drift_error)?
+                    + new_asks,
+                perp_market_map,
+                oracle_map,
+            )?;
+
+            total_perp_notional = total_perp_notional.saturating_add(notional);
+        }
+
+        // Markets the batch opens a new position in
+        for (market_index, (new_bids, new_asks)) in new_perp_orders.into_iter() {
+            let notional = self.calculate_perp_notional(
+                market_index,
+                0,
+                new_bids,
+                new_asks,
+                perp_market_map,
+                oracle_map,
+            )?;
+
+            total_perp_notional = total_perp_notional.saturating_add(notional);
+        }
+
+        // Borrows are leverage too, so the worst case spot liabilities count towards it
+        let mut total_spot_liabilities: u128 = 0;
+
+        for spot_position in user.spot_positions.iter() {
+            if spot_position.is_available() {
+                continue;
+            }
+
+            let new_orders = new_spot_orders.remove(&spot_position.market_index);
+
+            let liability = self.calculate_spot_liability(
+                spot_position.market_index,
+                Some(spot_position),
+                new_orders.unwrap_or((0, 0)),
+                new_orders.is_some(),
+                spot_market_map,
+                oracle_map,
+            )?;
+
+            total_spot_liabilities = total_spot_liabilities.saturating_add(liability);
+        }
+
+        // Markets the batch opens a new position in
+        for (market_index, new_orders) in new_spot_orders.into_iter() {
+            let liability = self.calculate_spot_liability(
+                market_index,
+                None,
+                new_orders,
+                true,
+                spot_market_map,
+                oracle_map,
+            )?;
+
+            total_spot_liabilities = total_spot_liabilities.saturating_add(liability);
+        }
+
+        let (equity, all_oracles_valid) =
+            calculate_user_equity(user, perp_market_map, spot_market_map, oracle_map)
+                .map_err(wrap_drift_error)?;
+
+        custom_validate!(all_oracles_valid, VaultErrorCode::InvalidOracle)?;
+
+        custom_validate!(
+            equity > 0,
+            VaultErrorCode::InvalidEquityValue,
+            "vault equity must be positive to trade"
+        )?;
+
+        let leverage = total_perp_notional
+            .saturating_add(total_spot_liabilities)
+            .saturating_mul(PERCENTAGE_PRECISION)
+            .checked_div(equity.unsigned_abs())
+            .ok_or(VaultErrorCode::MathError)?;
+
+        msg!(
+            "worst case perp notional: {}, spot liabilities: {}, equity: {}, leverage: {}",
+            total_perp_notional,
+            total_spot_liabilities,
+            equity,
+            leverage
+        );
+
+        custom_validate!(
+            leverage <= self.max_leverage as u128,
+            VaultErrorCode::LeverageExceeded,
+            "worst case leverage {} > {}",
+            leverage,
+            self.max_leverage
+        )?;
+
+        Ok(())
+    }
+
+    /// Calculates the least amount of the out market's token a swap may return: the amount in
+    /// converted at oracle prices, less max_swap_slippage
+    pub fn calculate_min_swap_amount_out(
+        &self,
+        in_market_index: u16,
+        amount_in: u64,
+        out_market_index: u16,
+        spot_market_map: &SpotMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> Result<u64, ProgramError> {
+        let in_market = spot_market_map
+            .get_ref(&in_market_index)
+            .map_err(wrap_drift_error)?;
+        let in_price = oracle_map
+            .get_price_data(&in_market.oracle)
+            .map_err(wrap_drift_error)?
+            .price;
+
+        let out_market = spot_market_map
+            .get_ref(&out_market_index)
+            .map_err(wrap_drift_error)?;
+        let out_price = oracle_map
+            .get_price_data(&out_market.oracle)
+            .map_err(wrap_drift_error)?
+            .price;
+
+        let amount_out = calculate_collateral_value_from_prices(
+            amount_in,
+            in_price,
+            in_market.get_precision().cast().map_err(wrap_drift_error)?,
+            out_price,
+            out_market
+                .get_precision()
+                .cast()
+                .map_err(wrap_drift_error)?,
+        )
+        .map_err(wrap_drift_error)?;
+
+        calculate_min_amount_out(amount_out, self.max_swap_slippage)
+    }
+
+    /// Validates that an order is in an allowed market and of an allowed order type
+    pub fn validate_order_params(&self, order: &OrderParams) -> ProgramResult {
+        custom_validate!(
+            self.allowed_order_types & Self::order_type_mask(order.order_type) != 0,
+            VaultErrorCode::OrderTypeNotAllowed,
+            "order type {:?} is not allowed",
+            order.order_type
+        )?;
+
+        match order.market_type {
+            MarketType::Perp => custom_validate!(
+                self.allowed_perp_markets.contains(&order.market_index),
+                VaultErrorCode::MarketNotAllowed,
+                "perp market {} is not allowed",
+                order.market_index
+            ),
+            MarketType::Spot => custom_validate!(
+                self.allowed_spot_markets.contains(&order.market_index),
+                VaultErrorCode::MarketNotAllowed,
+                "spot market {} is not allowed",
+                order.market_index
+            ),
+        }
+    }
+
+    fn calculate_perp_notional(
+        &self,
+        market_index: u16,
+        base_asset_amount: i128,
+        open_bids: i128,
+        open_asks: i128,
+        perp_market_map: &PerpMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> Result<u128, ProgramError> {
+        let oracle = perp_market_map
+            .get_ref(&market_index)
+            .map_err(wrap_drift_error)?
+            .amm
+            .oracle;
+
+        let oracle_price = oracle_map
+            .get_price_data(&oracle)
+            .map_err(wrap_drift_error)?
+            .price;
+
+        let notional = calculate_worst_case_notional(
+            base_asset_amount,
+            open_bids,
+            open_asks,
+            oracle_price,
+            BASE_PRECISION,
+        )?;
+
+        custom_validate!(
+            notional <= self.max_position_notional as u128,
+            VaultErrorCode::PositionNotionalExceeded,
+            "perp market {} worst case notional {} > {}",
+            market_index,
+            notional,
+            self.max_position_notional
+        )?;
+
+        Ok(notional)
+    }
+
+    /// Calculates the worst case liability of a spot position. Only positions the batch trades
+    /// are held to max_position_notional, since the vault's collateral is a spot position too
+    fn calculate_spot_liability(
+        &self,
+        market_index: u16,
+        spot_position: Option<&SpotPosition>,
+        (new_bids, new_asks): (i128, i128),
+        traded: bool,
+        spot_market_map: &SpotMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> Result<u128, ProgramError> {
+        let spot_market = spot_market_map
+            .get_ref(&market_index)
+            .map_err(wrap_drift_error)?;
+
+        let (token_amount, open_bids, open_asks) = match spot_position {
+            Some(spot_position) => (
+                spot_position
+                    .get_signed_token_amount(&spot_market)
+                    .map_err(wrap_drift_error)?,
+                spot_position
+                    .open_bids
+                    .cast::<i128>()
+                    .map_err(wrap_drift_error)?,
+                spot_position
+                    .open_asks
+                    .cast::<i128>()
+                    .map_err(wrap_drift_error)?,
+            ),
+            None => (0, 0, 0),
+        };
+
+        let oracle_price = oracle_map
+            .get_price_data(&spot_market.oracle)
+            .map_err(wrap_drift_error)?
+            .price;
+
+        let precision = spot_market
+            .get_precision()
+            .cast::<u128>()
+            .map_err(wrap_drift_error)?;
+
+        if traded {
+            let notional = calculate_worst_case_notional(
+                token_amount,
+                open_bids + new_bids,
+                open_asks + new_asks,
+                oracle_price,
+                precision,
+            )?;
+
+            custom_validate!(
+                notional <= self.max_position_notional as u128,
+                VaultErrorCode::PositionNotionalExceeded,
+                "spot market {} worst case notional {} > {}",
+                market_index,
+                notional,
+                self.max_position_notional
+            )?;
+        }
+
+        calculate_worst_case_liability(token_amount, open_asks + new_asks, oracle_price, precision)
+    }
+}
+
+/// Calculates the quote notional of a position if either all bids or all asks fill
+///
+/// # Arguments
+/// * `amount` - Signed position size
+/// * `open_bids` - Sum of open bids (positive)
+/// * `open_asks` - Sum of open asks (negative)
+/// * `oracle_price` - Oracle price: PRICE_PRECISION
+/// * `precision` - Precision of `amount`
+fn calculate_worst_case_notional(
+    amount: i128,
+    open_bids: i128,
+    open_asks: i128,
+    oracle_price: i64,
+    precision: u128,
+) -> Result<u128, ProgramError> {
+    let worst_case_amount = amount
+        .saturating_add(open_bids)
+        .unsigned_abs()
+        .max(amount.saturating_add(open_asks).unsigned_abs());
+
+    let notional = worst_case_amount
+        .checked_mul(oracle_price.unsigned_abs() as u128)
+        .ok_or(VaultErrorCode::MathError)?
+        .checked_div(precision)
+        .ok_or(VaultErrorCode::MathError)?;
+
+    Ok(notional)
+}
+
+/// Takes the max slippage off the amount out at oracle prices
+///
+/// # Arguments
+/// * `amount_out` - Amount out at oracle prices
+/// * `max_swap_slippage` - Max slippage: PERCENTAGE_PRECISION
+fn calculate_min_amount_out(amount_out: u64, max_swap_slippage: u64) -> Result<u64, ProgramError> {
+    let min_amount_out = (amount_out as u128)
+        .checked_mul(PERCENTAGE_PRECISION.saturating_sub(max_swap_slippage as u128))
+        .ok_or(VaultErrorCode::MathError)?
+        .checked_div(PERCENTAGE_PRECISION)
+        .ok_or(VaultErrorCode::MathError)?;
+
+    Ok(min_amount_out as u64)
+}
+
+/// Calculates the quote value of a spot position's borrow if all asks fill
+///
+/// # Arguments
+/// * `amount` - Signed token amount, negative for a borrow
+/// * `open_asks` - Sum of open asks (negative)
+/// * `oracle_price` - Oracle price: PRICE_PRECISION
+/// * `precision` - Precision of `amount`
+fn calculate_worst_case_liability(
+    amount: i128,
+    open_asks: i128,
+    oracle_price: i64,
+    precision: u128,
+) -> Result<u128, ProgramError> {
+    let worst_case_borrow = amount.saturating_add(open_asks).min(0).unsigned_abs();
+
+    let liability = worst_case_borrow
+        .checked_mul(oracle_price.unsigned_abs() as u128)
+        .ok_or(VaultErrorCode::MathError)?
+        .checked_div(precision)
+        .ok_or(VaultErrorCode::MathError)?;
+
+    Ok(liability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use drift::math::constants::{PRICE_PRECISION_I64, QUOTE_PRECISION, QUOTE_PRECISION_U64};
+
+    fn create_trading_policy() -> TradingPolicy {
+        TradingPolicy {
+            vault: Pubkey::default(),
+            pubkey: Pubkey::default(),
+            allowed_perp_markets: vec![0, 1],
+            allowed_spot_markets: vec![1],
+            max_leverage: 2 * PERCENTAGE_PRECISION as u64,
+            max_position_notional: 100_000 * QUOTE_PRECISION_U64,
+            allowed_order_types: TradingPolicy::order_type_mask(OrderType::Limit),
+            max_swap_slippage: 10_000,
+            enabled: true,
+            bump: 0,
+            pending_params: None,
+            pending_params_ts: 0,
+        }
+    }
+
+    #[test]
+    pub fn test_validate_order_params() {
+        let trading_policy = create_trading_policy();
+
+        let order = OrderParams {
+            order_type: OrderType::Limit,
+            market_type: MarketType::Perp,
+            market_index: 1,
+            ..OrderParams::default()
+        };
+        assert_eq!(trading_policy.validate_order_params(&order), Ok(()));
+
+        let market_order = OrderParams {
+            order_type: OrderType::Market,
+            ..order
+        };
+        assert_eq!(
+            trading_policy.validate_order_params(&market_order),
+            Err(VaultErrorCode::OrderTypeNotAllowed.into())
+        );
+
+        let perp_order = OrderParams {
+            market_index: 2,
+            ..order
+        };
+        assert_eq!(
+            trading_policy.validate_order_params(&perp_order),
+            Err(VaultErrorCode::MarketNotAllowed.into())
+        );
+
+        // Market 0 is an allowed perp market but not an allowed spot market
+        let spot_order = OrderParams {
+            market_type: MarketType::Spot,
+            market_index: 0,
+            ..order
+        };
+        assert_eq!(
+            trading_policy.validate_order_params(&spot_order),
+            Err(VaultErrorCode::MarketNotAllowed.into())
+        );
+    }
+
+    #[test]
+    pub fn test_validate_orders_policy_disabled() {
+        let mut trading_policy = create_trading_policy();
+        trading_policy.enabled = false;
+
+        assert_eq!(
+            trading_policy.validate_orders(
+                &[],
+                &User::default(),
+                &PerpMarketMap::empty(),
+                &SpotMarketMap::empty(),
+                &mut OracleMap::empty(),
+            ),
+            Err(VaultErrorCode::TradingPolicyDisabled.into())
+        );
+    }
+
+    #[test]
+    pub fn test_worst_case_notional() {
+        // Long 10 with 5 more bid and 20 asked, filling the bids is the worst case
+        let notional = calculate_worst_case_notional(
+            10 * BASE_PRECISION as i128,
+            5 * BASE_PRECISION as i128,
+            -20 * BASE_PRECISION as i128,
+            100 * PRICE_PRECISION_I64,
+            BASE_PRECISION,
+        )
+        .unwrap();
+        assert_eq!(notional, 1_500 * QUOTE_PRECISION);
+
+        // Filling the asks flips it to a larger short
+        let notional = calculate_worst_case_notional(
+            10 * BASE_PRECISION as i128,
+            0,
+            -30 * BASE_PRECISION as i128,
+            100 * PRICE_PRECISION_I64,
+            BASE_PRECISION,
+        )
+        .unwrap();
+        assert_eq!(notional, 2_000 * QUOTE_PRECISION);
+    }
+
+    #[test]
+    pub fn test_min_amount_out() {
+        assert_eq!(
+            calculate_min_amount_out(1_000_000, PERCENTAGE_PRECISION as u64 / 100),
+            Ok(990_000)
+        );
+        assert_eq!(calculate_min_amount_out(1_000_000, 0), Ok(1_000_000));
+        assert_eq!(
+            calculate_min_amount_out(1_000_000, PERCENTAGE_PRECISION as u64),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    pub fn test_worst_case_liability() {
+        let precision = 1_000_000;
+
+        // Asks covered by the deposit don't borrow
+        let liability = calculate_worst_case_liability(
+            5_000_000,
+            -5_000_000,
+            20 * PRICE_PRECISION_I64,
+            precision,
+        )
+        .unwrap();
+        assert_eq!(liability, 0);
+
+        // Selling 8 out of 5 borrows 3
+        let liability = calculate_worst_case_liability(
+            5_000_000,
+            -8_000_000,
+            20 * PRICE_PRECISION_I64,
+            precision,
+        )
+        .unwrap();
+        assert_eq!(liability, 60 * QUOTE_PRECISION);
+
+        // An existing borrow counts without any open orders
+        let liability =
+            calculate_worst_case_liability(-2_000_000, 0, 20 * PRICE_PRECISION_I64, precision)
+                .unwrap();
+        assert_eq!(liability, 40 * QUOTE_PRECISION);
+    }
+
+    #[test]
+    pub fn test_is_loosened_by() {
+        let trading_policy = create_trading_policy();
+        let params = trading_policy.get_params();
+
+        assert!(!trading_policy.is_loosened_by(&params));
+
+        let tighter = TradingPolicyParams {
+            allowed_perp_markets: vec![0],
+            max_leverage: PERCENTAGE_PRECISION as u64,
+            ..params.clone()
+        };
+        assert!(!trading_policy.is_loosened_by(&tighter));
+
+        for looser in [
+            TradingPolicyParams {
+                enabled: false,
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                allowed_perp_markets: vec![0, 1, 2],
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                allowed_spot_markets: vec![1, 2],
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                max_leverage: 3 * PERCENTAGE_PRECISION as u64,
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                max_position_notional: params.max_position_notional + 1,
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                allowed_order_types: params.allowed_order_types
+                    | TradingPolicy::order_type_mask(OrderType::Market),
+                ..params.clone()
+            },
+            TradingPolicyParams {
+                max_swap_slippage: params.max_swap_slippage + 1,
+                ..params.clone()
+            },
+        ] {
+            assert!(trading_policy.is_loosened_by(&looser));
+        }
+
+        // A disabled policy restricts nothing, enabling it tightens it
+        let mut disabled_policy = create_trading_policy();
+        disabled_policy.enabled = false;
+        assert!(!disabled_policy.is_loosened_by(&TradingPolicyParams {
+            max_leverage: 10 * PERCENTAGE_PRECISION as u64,
+            ..params
+        }));
+    }
+
+    #[test]
+    pub fn test_update_params_tightening_applies_immediately() {
+        let mut trading_policy = create_trading_policy();
+
+        let params = TradingPolicyParams {
+            max_leverage: PERCENTAGE_PRECISION as u64,
+            ..trading_policy.get_params()
+        };
+
+        assert_eq!(trading_policy.update_params(params, 100, 1_000), Ok(true));
+        assert_eq!(trading_policy.max_leverage, PERCENTAGE_PRECISION as u64);
+        assert!(trading_policy.pending_params.is_none());
+        assert_eq!(trading_policy.pending_params_ts, 0);
+    }
+
+    #[test]
+    pub fn test_update_params_loosening_is_timelocked() {
+        let mut trading_policy = create_trading_policy();
+
+        let params = TradingPolicyParams {
+            max_leverage: 5 * PERCENTAGE_PRECISION as u64,
+            ..trading_policy.get_params()
+        };
+
+        assert_eq!(
+            trading_policy.update_params(params.clone(), 100, 1_000),
+            Ok(false)
+        );
+        assert_eq!(trading_policy.max_leverage, 2 * PERCENTAGE_PRECISION as u64);
+        assert_eq!(trading_policy.pending_params, Some(params.clone()));
+        assert_eq!(trading_policy.pending_params_ts, 1_100);
+
+        assert_eq!(
+            trading_policy.update_params(params.clone(), 100, 1_099),
+            Err(VaultErrorCode::TradingPolicyTimelocked.into())
+        );
+
+        assert_eq!(trading_policy.update_params(params, 100, 1_100), Ok(true));
+        assert_eq!(trading_policy.max_leverage, 5 * PERCENTAGE_PRECISION as u64);
+        assert!(trading_policy.pending_params.is_none());
+        assert_eq!(trading_policy.pending_params_ts, 0);
+    }
+
+    #[test]
+    pub fn test_update_params_other_loosening_restarts_timelock() {
+        let mut trading_policy = create_trading_policy();
+        let params = trading_policy.get_params();
+
+        let disable = TradingPolicyParams {
+            enabled: false,
+            ..params.clone()
+        };
+        assert_eq!(trading_policy.update_params(disable, 100, 1_000), Ok(false));
+
+        let looser = TradingPolicyParams {
+            max_swap_slippage: params.max_swap_slippage * 2,
+            ..params
+        };
+        assert_eq!(
+            trading_policy.update_params(looser.clone(), 100, 1_200),
+            Ok(false)
+        );
+        assert_eq!(trading_policy.pending_params, Some(looser));
+        assert_eq!(trading_policy.pending_params_ts, 1_300);
+        assert!(trading_policy.enabled);
+    }
+
+    #[test]
+    pub fn test_update_params_invalid() {
+        let mut trading_policy = create_trading_policy();
+
+        let params = TradingPolicyParams {
+            max_leverage: 0,
+            ..trading_policy.get_params()
+        };
+        assert_eq!(
+            trading_policy.update_params(params, 100, 1_000),
+            Err(VaultErrorCode::InvalidTradingPolicy.into())
+        );
+
+        let params = TradingPolicyParams {
+            allowed_perp_markets: (0..=MAX_TRADING_POLICY_MARKETS as u16).collect(),
+            ..trading_policy.get_params()
+        };
+        assert_eq!(
+            trading_policy.update_params(params, 100, 1_000),
+            Err(VaultErrorCode::InvalidTradingPolicy.into())
+        );
+    }
+}
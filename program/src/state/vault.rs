@@ -18,26 +18,29 @@ use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 use std::result::Result;
 
 use crate::common::{calculate_amount_to_shares, log_data, log_params};
-use crate::constants::PERCENTAGE_PRECISION_U64;
+use crate::constants::{
+    FUEL_PER_SHARE_PRECISION, MAX_VAULT_COLLATERAL_MARKETS, MAX_VAULT_SUB_ACCOUNTS,
+    PERCENTAGE_PRECISION_U64, SHARE_PRICE_PRECISION,
+};
 use crate::custom_validate;
-use crate::error::VaultErrorCode;
-use crate::state::{VaultDepositorAction, VaultDepositorRecord};
+use crate::error::{wrap_drift_error, VaultErrorCode};
+use crate::state::{VaultDepositorAction, VaultDepositorRecord, VaultParamsSnapshot};
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default)]
 pub struct Vault {
     /// The name of the vault. Vault pubkey is derived from this name.
     pub name: [u8; 32],
     /// The vault's pubkey. It is a pda of name and also used as the authority for drift user
     pub pubkey: Pubkey,
-    /// The manager of the vault who has ability to update vault params
+    /// The manager of the vault, owns the manager shares and can deposit/withdraw them
     pub manager: Pubkey,
     /// The vaults token account. Used to receive tokens between deposits and withdrawals
     pub token_account: Pubkey,
     /// The drift user stats account for the vault
     pub user_stats: Pubkey,
-    /// The drift user account for the vault
+    /// The drift user account of the primary sub account. Deposits and withdrawals go through it
     pub user: Pubkey,
-    /// The vaults designated delegate for drift user account
+    /// The vaults designated delegate for drift sub account 0, mirrors sub_accounts[0].delegate
     /// can differ from actual user delegate if vault is in liquidation
     pub delegate: Pubkey,
     /// The delegate handling liquidation for depositor
@@ -98,6 +101,79 @@ pub struct Vault {
     pub bump: u8,
     /// Whether anybody can be a depositor
     pub permissioned: bool,
+    /// Whether trading goes through the program and is checked against the vault's trading policy.
+    /// When set the drift user has no raw delegate and `delegate` can only trade via PlaceOrders
+    pub trading_policy_enabled: bool,
+    /// The drift sub account deposits go to, its user is `user`
+    pub primary_sub_account_id: u16,
+    /// The number of drift sub accounts registered in sub_accounts
+    pub num_sub_accounts: u8,
+    /// The drift sub accounts of the vault, the first num_sub_accounts are registered
+    pub sub_accounts: [VaultSubAccount; MAX_VAULT_SUB_ACCOUNTS],
+    /// The number of extra spot markets in collateral_markets
+    pub num_collateral_markets: u8,
+    /// Extra spot markets the vault accepts deposits in, valued by oracle in the vault's spot market
+    pub collateral_markets: [u16; MAX_VAULT_COLLATERAL_MARKETS],
+    /// The drift insurance fund stake of the vault in its spot market, default if it never staked
+    pub insurance_fund_stake: Pubkey,
+    /// The insurance fund unstaking period (in seconds) of the vault's spot market
+    pub insurance_fund_unstaking_period: u64,
+    /// The drift referrer rewards of sub account 0 already swept to the treasury
+    pub referrer_rewards_swept: u64,
+    /// The drift custom margin ratio of every sub account, the vault's leverage ceiling: MARGIN_PRECISION.
+    /// 0 for drift's market margin ratios
+    pub custom_margin_ratio: u32,
+    /// A lower custom margin ratio waiting for its timelock
+    pub pending_custom_margin_ratio: u32,
+    /// When the pending custom margin ratio can be applied, 0 if none is pending
+    pub pending_custom_margin_ratio_ts: u64,
+    /// Whether spot margin trading is enabled on the sub accounts
+    pub margin_trading_enabled: bool,
+    /// Whether the sub accounts are in drift high leverage mode
+    pub high_leverage_mode: bool,
+    /// Whether a sub account went bankrupt during the liquidation that started at liquidation_start_ts
+    pub bankrupt: bool,
+    /// Drift fuel earned per vault share: FUEL_PER_SHARE_PRECISION
+    pub fuel_per_share: u128,
+    /// Total fuel of the vault's drift user stats when fuel_per_share was last updated
+    pub last_fuel: u64,
+    /// The SPL mint of the vault's share token, default if shares are not tokenized.
+    /// It is a pda of the vault, which is its mint authority
+    pub share_mint: Pubkey,
+    /// The user shares wrapped into share tokens, equal to the share mint supply. They stay in
+    /// user_shares and total_shares
+    pub wrapped_shares: u128,
+    /// The net deposits carried by the wrapped shares, moved back to depositors pro rata on unwrap
+    pub wrapped_net_deposits: u64,
+    /// Whether depositors can move their locked lots into receipt NFTs
+    pub lot_receipts_enabled: bool,
+    /// The user shares held by lot receipts. They stay in user_shares and total_shares
+    pub receipt_shares: u128,
+    /// The max value a single depositor can hold, 0 for no limit.
+    /// Depositors with a deposit_cap_override use that instead
+    pub max_deposit_per_depositor: u64,
+    /// Can update the economic terms of the vault (fees, periods, caps) and the vault roles
+    pub admin: Pubkey,
+    /// Can update delegates and trading settings
+    pub operator: Pubkey,
+    /// Receives the collected manager fees
+    pub fee_recipient: Pubkey,
+    /// The cost basis carried by the wrapped shares, moved back to depositors pro rata on unwrap
+    pub wrapped_cost_basis: u64,
+    /// The fuel earned by the wrapped shares, moved back to depositors pro rata on unwrap
+    pub wrapped_fuel: u64,
+    /// The fuel_per_share up to which wrapped_fuel is accounted
+    pub wrapped_fuel_per_share_checkpoint: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct VaultSubAccount {
+    /// The drift user account
+    pub user: Pubkey,
+    /// The designated delegate for the drift user account
+    pub delegate: Pubkey,
+    /// The drift sub account id of the user
+    pub sub_account_id: u16,
 }
 
 impl Sealed for Vault {}
@@ -113,8 +189,30 @@ impl Vault {
         Pubkey::find_program_address(&[b"vault", name.as_bytes()], program_id)
     }
 
-    pub fn get(account: &AccountInfo) -> Self {
-        try_from_slice_unchecked::<Vault>(&account.data.borrow()).unwrap()
+    /// Fails on vaults created before the current layout, they must be migrated first
+    pub fn get(account: &AccountInfo) -> Result<Self, ProgramError> {
+        if Self::is_legacy(account.data_len()) {
+            msg!("Vault {} must be migrated", account.key);
+            return Err(VaultErrorCode::AccountNeedsMigration.into());
+        }
+
+        Ok(try_from_slice_unchecked::<Vault>(&account.data.borrow()).unwrap())
+    }
+
+    /// Whether the account was sized for the layout before the fields appended to Vault
+    pub fn is_legacy(data_len: usize) -> bool {
+        data_len < Self::SIZE
+    }
+
+    /// Reads a legacy vault, the appended fields are zeroed
+    pub fn get_legacy(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut padded_data = data.to_vec();
+        padded_data.resize(Self::SIZE, 0);
+
+        try_from_slice_unchecked::<Vault>(&padded_data).map_err(|_| {
+            msg!("Invalid legacy Vault");
+            ProgramError::InvalidAccountData
+        })
     }
 
     pub fn save(vault: &Vault, vault_account: &AccountInfo) -> ProgramResult {
@@ -122,25 +220,350 @@ impl Vault {
         Ok(())
     }
 
+    pub fn get_params_snapshot(&self) -> VaultParamsSnapshot {
+        VaultParamsSnapshot {
+            lock_in_period: self.lock_in_period,
+            redeem_period: self.redeem_period,
+            max_tokens: self.max_tokens,
+            management_fee: self.management_fee,
+            min_deposit_amount: self.min_deposit_amount,
+            max_deposit_per_depositor: self.max_deposit_per_depositor,
+            profit_share: self.profit_share,
+            hurdle_rate: self.hurdle_rate,
+            permissioned: self.permissioned,
+        }
+    }
+
+    /// Registers a drift sub account, the first one registered becomes the primary
+    pub fn add_sub_account(&mut self, user: Pubkey, sub_account_id: u16) -> ProgramResult {
+        let num_sub_accounts = self.num_sub_accounts as usize;
+
+        custom_validate!(
+            num_sub_accounts < MAX_VAULT_SUB_ACCOUNTS,
+            VaultErrorCode::MaxSubAccountsReached,
+            "vault can have at most {} sub accounts",
+            MAX_VAULT_SUB_ACCOUNTS
+        )?;
+
+        custom_validate!(
+            self.get_sub_account(sub_account_id).is_none(),
+            VaultErrorCode::InvalidSubAccount,
+            "sub account {} already registered",
+            sub_account_id
+        )?;
+
+        self.sub_accounts[num_sub_accounts] = VaultSubAccount {
+            user,
+            delegate: Pubkey::default(),
+            sub_account_id,
+        };
+        self.num_sub_accounts += 1;
+
+        if num_sub_accounts == 0 {
+            self.primary_sub_account_id = sub_account_id;
+            self.user = user;
+        }
+
+        Ok(())
+    }
+
+    /// The registered sub accounts, in registration order
+    pub fn get_sub_accounts(&self) -> &[VaultSubAccount] {
+        &self.sub_accounts[..self.num_sub_accounts as usize]
+    }
+
+    /// The registered sub accounts other than the primary, in registration order
+    pub fn get_secondary_sub_accounts(&self) -> Vec<VaultSubAccount> {
+        self.get_sub_accounts()
+            .iter()
+            .filter(|sub_account| sub_account.sub_account_id != self.primary_sub_account_id)
+            .copied()
+            .collect()
+    }
+
+    pub fn get_sub_account(&self, sub_account_id: u16) -> Option<&VaultSubAccount> {
+        self.get_sub_accounts()
+            .iter()
+            .find(|sub_account| sub_account.sub_account_id == sub_account_id)
+    }
+
+    pub fn get_sub_account_by_user(&self, user: &Pubkey) -> Option<&VaultSubAccount> {
+        self.get_sub_accounts()
+            .iter()
+            .find(|sub_account| sub_account.user == *user)
+    }
+
+    pub fn set_sub_account_delegate(
+        &mut self,
+        sub_account_id: u16,
+        delegate: Pubkey,
+    ) -> ProgramResult {
+        let num_sub_accounts = self.num_sub_accounts as usize;
+
+        let sub_account = self.sub_accounts[..num_sub_accounts]
+            .iter_mut()
+            .find(|sub_account| sub_account.sub_account_id == sub_account_id);
+
+        match sub_account {
+            Some(sub_account) => sub_account.delegate = delegate,
+            None => {
+                msg!("sub account {} not registered", sub_account_id);
+                return Err(VaultErrorCode::InvalidSubAccount.into());
+            }
+        }
+
+        if sub_account_id == 0 {
+            self.delegate = delegate;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_primary_sub_account(&mut self, sub_account_id: u16) -> ProgramResult {
+        let user = match self.get_sub_account(sub_account_id) {
+            Some(sub_account) => sub_account.user,
+            None => {
+                msg!("sub account {} not registered", sub_account_id);
+                return Err(VaultErrorCode::InvalidSubAccount.into());
+            }
+        };
+
+        self.primary_sub_account_id = sub_account_id;
+        self.user = user;
+
+        Ok(())
+    }
+
+    pub fn set_collateral_markets(&mut self, market_indexes: &[u16]) -> ProgramResult {
+        custom_validate!(
+            market_indexes.len() <= MAX_VAULT_COLLATERAL_MARKETS,
+            VaultErrorCode::InvalidCollateralMarket,
+            "vault can have at most {} collateral markets",
+            MAX_VAULT_COLLATERAL_MARKETS
+        )?;
+
+        for (i, market_index) in market_indexes.iter().enumerate() {
+            custom_validate!(
+                *market_index != self.spot_market_index,
+                VaultErrorCode::InvalidCollateralMarket,
+                "spot market {} is the vault's spot market",
+                market_index
+            )?;
+
+            custom_validate!(
+                !market_indexes[..i].contains(market_index),
+                VaultErrorCode::InvalidCollateralMarket,
+                "spot market {} is listed twice",
+                market_index
+            )?;
+        }
+
+        self.collateral_markets = [0; MAX_VAULT_COLLATERAL_MARKETS];
+        self.collateral_markets[..market_indexes.len()].copy_from_slice(market_indexes);
+        self.num_collateral_markets = market_indexes.len() as u8;
+
+        Ok(())
+    }
+
+    pub fn get_share_mint_signer_seeds<'a>(vault: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"share_mint", vault, bump]
+    }
+
+    pub fn get_share_mint_pda(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"share_mint", vault.as_ref()], program_id)
+    }
+
+    /// Checkpoints the fuel of the wrapped shares, must be called before the wrapped shares change
+    pub fn update_wrapped_fuel(&mut self) -> ProgramResult {
+        let fuel = self
+            .wrapped_shares
+            .safe_mul(
+                self.fuel_per_share
+                    .saturating_sub(self.wrapped_fuel_per_share_checkpoint),
+            )
+            .and_then(|fuel| fuel.safe_div(FUEL_PER_SHARE_PRECISION))
+            .and_then(|fuel| fuel.cast::<u64>())
+            .map_err(wrap_drift_error)?;
+
+        self.wrapped_fuel = self.wrapped_fuel.saturating_add(fuel);
+        self.wrapped_fuel_per_share_checkpoint = self.fuel_per_share;
+
+        Ok(())
+    }
+
+    pub fn has_share_mint(&self) -> bool {
+        self.share_mint != Pubkey::default()
+    }
+
+    pub fn has_insurance_fund_stake(&self) -> bool {
+        self.insurance_fund_stake != Pubkey::default()
+    }
+
+    /// The period a withdraw request has to wait. Capital staked in the insurance fund needs the
+    /// unstaking period to come back, so that sets a floor on the vault's redeem period
+    pub fn get_redeem_period(&self) -> u64 {
+        if self.has_insurance_fund_stake() {
+            self.redeem_period.max(self.insurance_fund_unstaking_period)
+        } else {
+            self.redeem_period
+        }
+    }
+
+    /// The time a change that raises the vault's risk has to wait, so a depositor who just
+    /// deposited can sit out the lock-in and a withdraw request before it applies
+    pub fn get_timelock_period(&self) -> u64 {
+        self.lock_in_period.saturating_add(self.get_redeem_period())
+    }
+
+    /// The number of accounts ahead of the market accounts in the remaining accounts: the drift
+    /// users of the secondary sub accounts, then the insurance fund stake and vault if staked
+    pub fn get_num_remaining_vault_accounts(&self) -> usize {
+        let num_insurance_fund_accounts = if self.has_insurance_fund_stake() {
+            2
+        } else {
+            0
+        };
+
+        self.get_secondary_sub_accounts().len() + num_insurance_fund_accounts
+    }
+
+    /// Updates the custom margin ratio and returns whether it applies now. Raising the ratio
+    /// applies immediately. Lowering it raises the leverage ceiling, so it is scheduled and applies
+    /// when requested again after the timelock period, giving depositors time to exit
+    pub fn update_custom_margin_ratio(
+        &mut self,
+        margin_ratio: u32,
+        now: i64,
+    ) -> Result<bool, ProgramError> {
+        let pending = self.pending_custom_margin_ratio_ts != 0
+            && self.pending_custom_margin_ratio == margin_ratio;
+
+        if margin_ratio < self.custom_margin_ratio && !pending {
+            self.pending_custom_margin_ratio = margin_ratio;
+            self.pending_custom_margin_ratio_ts =
+                (now as u64).saturating_add(self.get_timelock_period());
+
+            msg!(
+                "custom margin ratio {} scheduled for {}",
+                margin_ratio,
+                self.pending_custom_margin_ratio_ts
+            );
+
+            return Ok(false);
+        }
+
+        if margin_ratio < self.custom_margin_ratio {
+            custom_validate!(
+                now as u64 >= self.pending_custom_margin_ratio_ts,
+                VaultErrorCode::MarginRatioTimelocked,
+                "custom margin ratio {} can be applied at {}",
+                margin_ratio,
+                self.pending_custom_margin_ratio_ts
+            )?;
+        }
+
+        self.custom_margin_ratio = margin_ratio;
+        self.pending_custom_margin_ratio = 0;
+        self.pending_custom_margin_ratio_ts = 0;
+
+        Ok(true)
+    }
+
+    /// Spreads the fuel earned since the last update over the current shares. Fuel earned while
+    /// the vault has no shares is not owned by anyone and is skipped
+    pub fn update_fuel_per_share(&mut self, total_fuel: u64) -> ProgramResult {
+        let fuel = total_fuel.saturating_sub(self.last_fuel);
+
+        if fuel > 0 && self.total_shares > 0 {
+            let fuel_per_share = (fuel as u128)
+                .safe_mul(FUEL_PER_SHARE_PRECISION)
+                .and_then(|fuel| fuel.safe_div(self.total_shares))
+                .map_err(wrap_drift_error)?;
+
+            self.fuel_per_share = self.fuel_per_share.saturating_add(fuel_per_share);
+        }
+
+        msg!("fuel: {}, fuel_per_share: {}", fuel, self.fuel_per_share);
+
+        self.last_fuel = total_fuel;
+
+        Ok(())
+    }
+
+    pub fn is_collateral_market(&self, market_index: u16) -> bool {
+        self.collateral_markets[..self.num_collateral_markets as usize].contains(&market_index)
+    }
+
+    /// Values a collateral amount in the vault's spot market token. The collateral is priced at
+    /// the lower of its oracle price and 5 min oracle twap so a price spike can't inflate the value
+    pub fn calculate_collateral_value(
+        &self,
+        market_index: u16,
+        amount: u64,
+        spot_market_map: &SpotMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> Result<u64, drift::error::ErrorCode> {
+        let collateral_market = spot_market_map.get_ref(&market_index)?;
+        let collateral_oracle_price = oracle_map.get_price_data(&collateral_market.oracle)?.price;
+
+        let spot_market = spot_market_map.get_ref(&self.spot_market_index)?;
+        let oracle_price = oracle_map.get_price_data(&spot_market.oracle)?.price;
+
+        calculate_collateral_value_from_prices(
+            amount,
+            collateral_oracle_price.min(
+                collateral_market
+                    .historical_oracle_data
+                    .last_oracle_price_twap_5min,
+            ),
+            collateral_market.get_precision().cast()?,
+            oracle_price,
+            spot_market.get_precision().cast()?,
+        )
+    }
+
+    /// Pays a management fee charged in vault value instead of tokens, as manager shares.
+    /// Used for collateral deposits since the treasury only holds the vault's spot market token.
+    /// Like any change of the vault shares, update_vault_fuel must be called first
+    pub fn issue_manager_fee_shares(&mut self, fee: u64, vault_equity: u64) -> ProgramResult {
+        let fee_shares = calculate_amount_to_shares(fee, self.total_shares, vault_equity)?;
+
+        // The fee is kept in the vault, not in the treasury, so the manager can't collect it
+        self.manager_total_net_fee = self.manager_total_net_fee.saturating_sub(fee);
+        self.total_shares = self.total_shares.saturating_add(fee_shares);
+
+        msg!("Issuing manager fee shares: {}", fee_shares);
+
+        Ok(())
+    }
+
     pub fn get_manager_shares(&self) -> u128 {
         self.total_shares.saturating_sub(self.user_shares)
     }
 
+    /// Calculates the vault equity in the vault's spot market token, summed across the drift
+    /// users of all sub accounts
     pub fn calculate_total_equity(
         &self,
-        user: &User,
+        users: &[Box<User>],
         perp_market_map: &PerpMarketMap,
         spot_market_map: &SpotMarketMap,
         oracle_map: &mut OracleMap,
     ) -> Result<u64, drift::error::ErrorCode> {
-        let (vault_equity, all_oracles_valid) =
-            calculate_user_equity(user, perp_market_map, spot_market_map, oracle_map)?;
+        let mut vault_equity: i128 = 0;
 
-        validate!(
-            all_oracles_valid,
-            drift::error::ErrorCode::InvalidOracle,
-            "oracle invalid"
-        )?;
+        for user in users.iter() {
+            let (user_equity, all_oracles_valid) =
+                calculate_user_equity(user, perp_market_map, spot_market_map, oracle_map)?;
+
+            validate!(
+                all_oracles_valid,
+                drift::error::ErrorCode::InvalidOracle,
+                "oracle invalid"
+            )?;
+
+            vault_equity = vault_equity.safe_add(user_equity)?;
+        }
 
         validate!(
             vault_equity >= 0,
@@ -161,6 +584,29 @@ impl Vault {
             .cast::<u64>()?)
     }
 
+    /// Calculates the confidence interval of the vault's spot market oracle relative to its price:
+    /// SHARE_PRICE_PRECISION. The vault equity is converted to the vault's token at that price
+    pub fn calculate_oracle_confidence(
+        &self,
+        spot_market_map: &SpotMarketMap,
+        oracle_map: &mut OracleMap,
+    ) -> Result<u128, drift::error::ErrorCode> {
+        let spot_market = spot_market_map.get_ref(&self.spot_market_index)?;
+        let oracle_price_data = oracle_map.get_price_data(&spot_market.oracle)?;
+
+        validate!(
+            oracle_price_data.price > 0,
+            drift::error::ErrorCode::InvalidOracle,
+            "oracle price not positive"
+        )?;
+
+        oracle_price_data
+            .confidence
+            .cast::<u128>()?
+            .safe_mul(SHARE_PRICE_PRECISION)?
+            .safe_div(oracle_price_data.price.cast::<u128>()?)
+    }
+
     pub fn calculate_fees(&self, amount: u64) -> u64 {
         let numerator = amount
             .checked_mul(self.management_fee)
@@ -332,3 +778,218 @@ impl Vault {
         Ok(())
     }
 }
+
+/// Converts a collateral amount to the vault's spot market token at the given oracle prices
+///
+/// # Arguments
+/// * `amount` - Collateral token amount
+/// * `collateral_price` - Price of the collateral: PRICE_PRECISION
+/// * `collateral_precision` - Precision of `amount`
+/// * `oracle_price` - Price of the vault's spot market token: PRICE_PRECISION
+/// * `spot_market_precision` - Precision of the vault's spot market token
+pub fn calculate_collateral_value_from_prices(
+    amount: u64,
+    collateral_price: i64,
+    collateral_precision: u128,
+    oracle_price: i64,
+    spot_market_precision: u128,
+) -> Result<u64, drift::error::ErrorCode> {
+    validate!(
+        collateral_price > 0 && oracle_price > 0,
+        drift::error::ErrorCode::InvalidOracle,
+        "oracle price not positive"
+    )?;
+
+    amount
+        .cast::<u128>()?
+        .safe_mul(collateral_price.cast()?)?
+        .safe_mul(spot_market_precision)?
+        .safe_div(collateral_precision.safe_mul(oracle_price.cast()?)?)?
+        .cast::<u64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use drift::math::constants::PRICE_PRECISION_I64;
+
+    #[test]
+    pub fn test_update_custom_margin_ratio_raise_applies_immediately() {
+        let mut vault = Vault {
+            custom_margin_ratio: 1_000,
+            lock_in_period: 100,
+            redeem_period: 50,
+            ..Vault::default()
+        };
+
+        assert_eq!(vault.update_custom_margin_ratio(2_000, 1_000), Ok(true));
+        assert_eq!(vault.custom_margin_ratio, 2_000);
+        assert_eq!(vault.pending_custom_margin_ratio_ts, 0);
+    }
+
+    #[test]
+    pub fn test_update_custom_margin_ratio_lower_is_timelocked() {
+        let mut vault = Vault {
+            custom_margin_ratio: 2_000,
+            lock_in_period: 100,
+            redeem_period: 50,
+            ..Vault::default()
+        };
+
+        // Waits out the lock in and the redeem period
+        assert_eq!(vault.update_custom_margin_ratio(1_000, 1_000), Ok(false));
+        assert_eq!(vault.custom_margin_ratio, 2_000);
+        assert_eq!(vault.pending_custom_margin_ratio, 1_000);
+        assert_eq!(vault.pending_custom_margin_ratio_ts, 1_150);
+
+        assert_eq!(
+            vault.update_custom_margin_ratio(1_000, 1_149),
+            Err(VaultErrorCode::MarginRatioTimelocked.into())
+        );
+
+        assert_eq!(vault.update_custom_margin_ratio(1_000, 1_150), Ok(true));
+        assert_eq!(vault.custom_margin_ratio, 1_000);
+        assert_eq!(vault.pending_custom_margin_ratio, 0);
+        assert_eq!(vault.pending_custom_margin_ratio_ts, 0);
+    }
+
+    #[test]
+    pub fn test_update_custom_margin_ratio_other_ratio_restarts_timelock() {
+        let mut vault = Vault {
+            custom_margin_ratio: 2_000,
+            lock_in_period: 100,
+            redeem_period: 50,
+            insurance_fund_stake: Pubkey::new_unique(),
+            insurance_fund_unstaking_period: 200,
+            ..Vault::default()
+        };
+
+        // The insurance fund unstaking period replaces the shorter redeem period
+        assert_eq!(vault.update_custom_margin_ratio(1_000, 1_000), Ok(false));
+        assert_eq!(vault.pending_custom_margin_ratio_ts, 1_300);
+
+        // Removing the custom ratio loosens it as well
+        assert_eq!(vault.update_custom_margin_ratio(0, 1_300), Ok(false));
+        assert_eq!(vault.custom_margin_ratio, 2_000);
+        assert_eq!(vault.pending_custom_margin_ratio, 0);
+        assert_eq!(vault.pending_custom_margin_ratio_ts, 1_600);
+    }
+
+    #[test]
+    pub fn test_update_fuel_per_share() {
+        let mut vault = Vault {
+            total_shares: 1_000,
+            last_fuel: 100,
+            ..Vault::default()
+        };
+
+        vault.update_fuel_per_share(600).unwrap();
+        assert_eq!(vault.fuel_per_share, FUEL_PER_SHARE_PRECISION / 2);
+        assert_eq!(vault.last_fuel, 600);
+
+        // No new fuel
+        vault.update_fuel_per_share(600).unwrap();
+        assert_eq!(vault.fuel_per_share, FUEL_PER_SHARE_PRECISION / 2);
+
+        // New shares only earn fuel from here on
+        vault.total_shares = 2_000;
+        vault.update_fuel_per_share(1_600).unwrap();
+        assert_eq!(vault.fuel_per_share, FUEL_PER_SHARE_PRECISION);
+        assert_eq!(vault.last_fuel, 1_600);
+    }
+
+    #[test]
+    pub fn test_update_fuel_per_share_without_shares() {
+        let mut vault = Vault {
+            last_fuel: 100,
+            ..Vault::default()
+        };
+
+        // Fuel earned without shares is skipped, not handed to the next depositor
+        vault.update_fuel_per_share(500).unwrap();
+        assert_eq!(vault.fuel_per_share, 0);
+        assert_eq!(vault.last_fuel, 500);
+
+        vault.total_shares = 100;
+        vault.update_fuel_per_share(600).unwrap();
+        assert_eq!(vault.fuel_per_share, FUEL_PER_SHARE_PRECISION);
+    }
+
+    #[test]
+    pub fn test_calculate_collateral_value_from_prices() {
+        // 2 SOL (9 decimals) at $150 into USDC (6 decimals) at $1
+        let value = calculate_collateral_value_from_prices(
+            2_000_000_000,
+            150 * PRICE_PRECISION_I64,
+            1_000_000_000,
+            PRICE_PRECISION_I64,
+            1_000_000,
+        )
+        .unwrap();
+        assert_eq!(value, 300_000_000);
+
+        // 300 USDC into SOL at $150
+        let value = calculate_collateral_value_from_prices(
+            300_000_000,
+            PRICE_PRECISION_I64,
+            1_000_000,
+            150 * PRICE_PRECISION_I64,
+            1_000_000_000,
+        )
+        .unwrap();
+        assert_eq!(value, 2_000_000_000);
+
+        // Rounds down in favor of the vault
+        let value = calculate_collateral_value_from_prices(
+            1,
+            PRICE_PRECISION_I64,
+            1_000_000,
+            3 * PRICE_PRECISION_I64,
+            1_000_000,
+        )
+        .unwrap();
+        assert_eq!(value, 0);
+    }
+
+    #[test]
+    pub fn test_calculate_collateral_value_from_prices_invalid_oracle() {
+        for (collateral_price, oracle_price) in
+            [(0, PRICE_PRECISION_I64), (PRICE_PRECISION_I64, -1)]
+        {
+            assert_eq!(
+                calculate_collateral_value_from_prices(
+                    1_000_000,
+                    collateral_price,
+                    1_000_000,
+                    oracle_price,
+                    1_000_000,
+                ),
+                Err(drift::error::ErrorCode::InvalidOracle)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_get_legacy_zeroes_appended_fields() {
+        let manager = Pubkey::new_unique();
+        let vault = Vault {
+            manager,
+            permissioned: true,
+            admin: Pubkey::new_unique(),
+            num_sub_accounts: 1,
+            ..Vault::default()
+        };
+
+        // The legacy layout ends with permissioned
+        let legacy_len = 456;
+        let data = vault.try_to_vec().unwrap();
+        let legacy_vault = Vault::get_legacy(&data[..legacy_len]).unwrap();
+
+        assert_eq!(legacy_vault.manager, manager);
+        assert!(legacy_vault.permissioned);
+        assert_eq!(legacy_vault.admin, Pubkey::default());
+        assert_eq!(legacy_vault.num_sub_accounts, 0);
+        assert!(Vault::is_legacy(legacy_len));
+        assert!(!Vault::is_legacy(Vault::SIZE));
+    }
+}
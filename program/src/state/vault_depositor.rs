@@ -1,9 +1,9 @@
 use std::mem;
 
-use super::{Vault, WithdrawRequest};
+use super::{LotReceipt, Vault, WithdrawRequest};
 use crate::{
     common::{calculate_amount_to_shares, log_data, log_params},
-    constants::PERCENTAGE_PRECISION,
+    constants::{FUEL_PER_SHARE_PRECISION, PERCENTAGE_PRECISION},
     custom_validate,
     error::{wrap_drift_error, VaultErrorCode},
     state::{VaultDepositorAction, VaultDepositorRecord},
@@ -15,7 +15,7 @@ use solana_program::{
     program_error::ProgramError, program_pack::Sealed, pubkey::Pubkey,
 };
 
-#[derive(BorshSerialize, BorshDeserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Default)]
 pub struct VaultDepositor {
     /// The vault deposited into
     pub vault: Pubkey,
@@ -42,8 +42,24 @@ pub struct VaultDepositor {
     pub profit_share_fee_paid: u64,
     /// the exponent for vault_shares decimal places
     pub vault_shares_base: u32,
-    pub padding1: u32,
-    pub padding: [u64; 8],
+    /// The account layout version. Accounts written before deposits had a cost basis are version
+    /// 0 and must be converted by MigrateVaultDepositor
+    pub version: u32,
+    /// admin set cap on the value this depositor can hold, 0 for no limit.
+    /// None falls back to vault.max_deposit_per_depositor
+    pub deposit_cap_override: Option<u64>,
+    /// vault.fuel_per_share when the depositor's fuel was last updated
+    pub fuel_per_share_checkpoint: u128,
+    /// drift fuel earned by the depositor's shares up to the checkpoint
+    pub fuel_amount: u64,
+    /// lifetime pnl booked on withdraws, the amount paid out less the cost basis of the lots withdrawn
+    pub realized_pnl: i64,
+    /// lifetime management fees paid on deposits and withdraws
+    pub management_fee_paid: u64,
+    /// value of the shares at the vault equity of the last RefreshDepositorStats less their cost basis
+    pub unrealized_pnl: i64,
+    /// Timestamp of the last RefreshDepositorStats
+    pub last_stats_ts: i64,
 }
 
 impl Sealed for VaultDepositor {}
@@ -51,6 +67,8 @@ impl Sealed for VaultDepositor {}
 impl VaultDepositor {
     pub const INITIAL_SIZE: usize = mem::size_of::<Self>() + mem::size_of::<DepositInfo>() * 10 + 8;
 
+    pub const VERSION: u32 = 1;
+
     pub fn get_vault_depositor_signer_seeds<'a>(
         vault: &'a [u8],
         authority: &'a [u8],
@@ -80,8 +98,28 @@ impl VaultDepositor {
         )
     }
 
-    pub fn get(account: &AccountInfo) -> Self {
-        try_from_slice_unchecked::<VaultDepositor>(&account.data.borrow()).unwrap()
+    /// Fails on legacy accounts, their deposits can't be read with the current layout
+    pub fn get(account: &AccountInfo) -> Result<Self, ProgramError> {
+        match try_from_slice_unchecked::<VaultDepositor>(&account.data.borrow()) {
+            Ok(vault_depositor)
+                if vault_depositor.version == Self::VERSION
+                    || vault_depositor.pubkey == Pubkey::default() =>
+            {
+                Ok(vault_depositor)
+            }
+            _ => {
+                msg!("Vault Depositor {} must be migrated", account.key);
+                Err(VaultErrorCode::AccountNeedsMigration.into())
+            }
+        }
+    }
+
+    /// Whether the account data is written with the layout before VERSION
+    pub fn is_legacy(data: &[u8]) -> bool {
+        !matches!(
+            try_from_slice_unchecked::<VaultDepositor>(data),
+            Ok(vault_depositor) if vault_depositor.version == Self::VERSION
+        )
     }
 
     pub fn save(&self, account: &AccountInfo) -> ProgramResult {
@@ -89,6 +127,12 @@ impl VaultDepositor {
         Ok(())
     }
 
+    /// Returns the max value this depositor can hold, 0 for no limit
+    pub fn get_deposit_cap(&self, vault: &Vault) -> u64 {
+        self.deposit_cap_override
+            .unwrap_or(vault.max_deposit_per_depositor)
+    }
+
     pub fn calculate_shares_to_amount(
         n_shares: u128,
         total_vault_shares: u128,
@@ -100,6 +144,30 @@ impl VaultDepositor {
         Ok(amount)
     }
 
+    /// Returns the drift fuel earned by the depositor's shares, including fuel since the checkpoint
+    pub fn get_fuel(&self, vault: &Vault) -> Result<u64, ProgramError> {
+        let fuel = self
+            .vault_shares
+            .safe_mul(
+                vault
+                    .fuel_per_share
+                    .saturating_sub(self.fuel_per_share_checkpoint),
+            )
+            .and_then(|fuel| fuel.safe_div(FUEL_PER_SHARE_PRECISION))
+            .and_then(|fuel| fuel.cast::<u64>())
+            .map_err(wrap_drift_error)?;
+
+        Ok(self.fuel_amount.saturating_add(fuel))
+    }
+
+    /// Checkpoints the depositor's fuel, must be called before the depositor's shares change
+    pub fn update_fuel(&mut self, vault: &Vault) -> ProgramResult {
+        self.fuel_amount = self.get_fuel(vault)?;
+        self.fuel_per_share_checkpoint = vault.fuel_per_share;
+
+        Ok(())
+    }
+
     pub fn deposit(
         &mut self,
         mut amount: u64,
@@ -123,10 +191,33 @@ impl VaultDepositor {
             vault.min_deposit_amount
         )?;
 
+        let deposit_cap = self.get_deposit_cap(vault);
+
+        if deposit_cap > 0 {
+            let depositor_equity = Self::calculate_shares_to_amount(
+                self.vault_shares,
+                vault.total_shares,
+                vault_equity,
+            )?;
+
+            custom_validate!(
+                depositor_equity.saturating_add(amount) <= deposit_cap,
+                VaultErrorCode::DepositorIsAtCapacity,
+                "after deposit depositor equity is {} > {}",
+                depositor_equity.saturating_add(amount),
+                deposit_cap
+            )?;
+        }
+
+        self.update_fuel(vault)?;
+
         let vault_shares_before = self.vault_shares;
         let total_vault_shares_before = vault.total_shares;
         let user_vault_shares_before = vault.user_shares;
 
+        // The lot's cost basis is the whole amount paid, so pnl is net of the management fee
+        let cost_basis = amount;
+
         let management_fee = vault.calculate_fees(amount);
         amount -= management_fee;
         msg!("Fees: {}", management_fee);
@@ -139,7 +230,9 @@ impl VaultDepositor {
         self.total_deposits = self.total_deposits.saturating_add(amount);
         self.net_deposits = self.net_deposits.saturating_add(amount);
         self.vault_shares = self.vault_shares.saturating_add(new_shares);
-        self.deposits.push(DepositInfo::new(now, new_shares));
+        self.deposits
+            .push(DepositInfo::new(now, new_shares, cost_basis));
+        self.management_fee_paid = self.management_fee_paid.saturating_add(management_fee);
 
         vault.manager_total_fee = vault.manager_total_fee.saturating_add(management_fee);
         vault.manager_total_net_fee = vault.manager_total_net_fee.saturating_add(management_fee);
@@ -176,10 +269,20 @@ impl VaultDepositor {
         Ok((amount, management_fee))
     }
 
+    /// Requests a withdraw of the depositor's unlocked shares and the shares it holds as share
+    /// tokens. Share tokens are only burned on withdraw
+    ///
+    /// # Arguments
+    /// * `withdraw_amount` - The value to withdraw
+    /// * `vault_equity` - The vault's equity
+    /// * `token_shares` - The share token balance of the depositor's authority
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
     pub fn request_withdraw(
         &mut self,
         withdraw_amount: u64,
         vault_equity: u64,
+        token_shares: u128,
         vault: &mut Vault,
         now: i64,
     ) -> ProgramResult {
@@ -191,7 +294,9 @@ impl VaultDepositor {
             "Requested shares = 0"
         )?;
 
-        let withdrawable_shares = self.calculate_withdrawable_shares(now, vault.lock_in_period)?;
+        let withdrawable_shares = self
+            .calculate_withdrawable_shares(now, vault.lock_in_period)?
+            .saturating_add(token_shares);
 
         custom_validate!(
             shares <= withdrawable_shares,
@@ -204,12 +309,12 @@ impl VaultDepositor {
         let user_vault_shares_before = vault.user_shares;
 
         custom_validate!(
-            vault_shares_before >= shares,
+            vault_shares_before.saturating_add(token_shares) >= shares,
             VaultErrorCode::InsufficientVaultShares
         )?;
 
         self.last_withdraw_request.set(
-            vault_shares_before,
+            vault_shares_before.saturating_add(token_shares),
             shares,
             withdraw_amount,
             vault_equity,
@@ -301,6 +406,8 @@ impl VaultDepositor {
         self.last_withdraw_request
             .check_redeem_period_finished(vault, now)?;
 
+        self.update_fuel(vault)?;
+
         let vault_shares_before: u128 = self.vault_shares;
         let total_vault_shares_before = vault.total_shares;
         let user_vault_shares_before = vault.user_shares;
@@ -311,7 +418,7 @@ impl VaultDepositor {
             shares > 0,
             VaultErrorCode::InvalidVaultWithdraw,
             "Must submit withdraw request and wait the redeem_period ({} seconds)",
-            vault.redeem_period
+            vault.get_redeem_period()
         )?;
 
         custom_validate!(
@@ -319,23 +426,11 @@ impl VaultDepositor {
             VaultErrorCode::InsufficientVaultShares
         )?;
 
-        let mut withdraw_amount: u64 =
-            if_shares_to_vault_amount(shares, vault.total_shares, vault_equity)
-                .map_err(wrap_drift_error)?;
-        // let mut withdraw_amount = amount.min(self.last_withdraw_request.value);
-
-        // Calculate fees and profit share
-        let management_fee = vault.calculate_fees(withdraw_amount);
-        let profit_share = self.calculate_profit_share(withdraw_amount, vault_equity, vault)?;
-
-        msg!("Management fee: {}", management_fee);
-        msg!("Profit share: {}", profit_share);
+        let (withdraw_amount, management_fee, profit_share) =
+            self.calculate_withdraw(shares, vault_equity, vault)?;
 
-        // Calculate total deductions and final amount
         let total_deductions = management_fee.saturating_add(profit_share);
 
-        withdraw_amount = withdraw_amount.saturating_sub(total_deductions);
-
         msg!("Total deductions: {}", total_deductions);
         msg!("Final withdraw amount: {}", withdraw_amount);
         msg!(
@@ -355,13 +450,20 @@ impl VaultDepositor {
         );
 
         self.profit_share_fee_paid = self.profit_share_fee_paid.saturating_add(profit_share);
+        self.management_fee_paid = self.management_fee_paid.saturating_add(management_fee);
 
         self.vault_shares = self.vault_shares.saturating_sub(shares);
 
         self.total_withdraws = self.total_withdraws.saturating_add(withdraw_amount);
         self.net_deposits = self.net_deposits.saturating_sub(withdraw_amount);
 
-        self.remove_shares(shares)?;
+        // Lots are withdrawn oldest first, so pnl is realized FIFO
+        let cost_basis = self.remove_shares(shares)?;
+
+        let realized_pnl = (withdraw_amount as i64).saturating_sub(cost_basis as i64);
+        msg!("Realized pnl: {}", realized_pnl);
+
+        self.realized_pnl = self.realized_pnl.saturating_add(realized_pnl);
 
         vault.manager_total_fee = vault.manager_total_fee.saturating_add(management_fee);
         vault.manager_total_profit_share = vault
@@ -409,6 +511,553 @@ impl VaultDepositor {
         Ok((withdraw_amount, total_deductions))
     }
 
+    /// Moves this depositor's whole position to another depositor of the same vault
+    ///
+    /// Lots keep their deposit timestamps so lock-in is not restarted and are merged into the
+    /// destination's lots oldest first. The net deposits, profit share high-water data and accrued
+    /// fuel move with the shares.
+    ///
+    /// # Arguments
+    /// * `destination` - The depositor receiving the position
+    /// * `vault` - The vault both depositors belong to
+    /// * `now` - Current Unix timestamp
+    pub fn transfer_position(
+        &mut self,
+        destination: &mut VaultDepositor,
+        vault: &Vault,
+        vault_equity: u64,
+        now: i64,
+    ) -> ProgramResult {
+        custom_validate!(
+            self.vault == destination.vault && self.pubkey != destination.pubkey,
+            VaultErrorCode::InvalidPositionTransfer,
+            "position must move to another depositor of the same vault"
+        )?;
+
+        custom_validate!(
+            self.last_withdraw_request.shares == 0,
+            VaultErrorCode::VaultWithdrawRequestInProgress,
+            "cancel the withdraw request before transferring the position"
+        )?;
+
+        custom_validate!(
+            self.vault_shares > 0,
+            VaultErrorCode::InsufficientVaultShares,
+            "no shares to transfer"
+        )?;
+
+        let deposit_cap = destination.get_deposit_cap(vault);
+
+        if deposit_cap > 0 {
+            let destination_equity = Self::calculate_shares_to_amount(
+                destination.vault_shares.saturating_add(self.vault_shares),
+                vault.total_shares,
+                vault_equity,
+            )?;
+
+            custom_validate!(
+                destination_equity <= deposit_cap,
+                VaultErrorCode::DepositorIsAtCapacity,
+                "after transfer destination equity is {} > {}",
+                destination_equity,
+                deposit_cap
+            )?;
+        }
+
+        self.update_fuel(vault)?;
+        destination.update_fuel(vault)?;
+
+        let shares = self.vault_shares;
+        let source_shares_before = self.vault_shares;
+        let destination_shares_before = destination.vault_shares;
+
+        // The whole position moves, so its lifetime accounting moves with it
+        destination.vault_shares = destination.vault_shares.saturating_add(shares);
+        destination.net_deposits = destination.net_deposits.saturating_add(self.net_deposits);
+        destination.total_deposits = destination
+            .total_deposits
+            .saturating_add(self.total_deposits);
+        destination.total_withdraws = destination
+            .total_withdraws
+            .saturating_add(self.total_withdraws);
+        destination.cumulative_profit_share_amount = destination
+            .cumulative_profit_share_amount
+            .saturating_add(self.cumulative_profit_share_amount);
+        destination.profit_share_fee_paid = destination
+            .profit_share_fee_paid
+            .saturating_add(self.profit_share_fee_paid);
+        destination.management_fee_paid = destination
+            .management_fee_paid
+            .saturating_add(self.management_fee_paid);
+        destination.realized_pnl = destination.realized_pnl.saturating_add(self.realized_pnl);
+        destination.unrealized_pnl = destination
+            .unrealized_pnl
+            .saturating_add(self.unrealized_pnl);
+        // Withdraws remove lots oldest first, so the lots are merged in deposit order. The sort is
+        // stable and both ledgers are already in order
+        destination.deposits.append(&mut self.deposits);
+        destination.deposits.sort_by_key(|deposit| deposit.ts);
+        destination.fuel_amount = destination.fuel_amount.saturating_add(self.fuel_amount);
+
+        self.vault_shares = 0;
+        self.net_deposits = 0;
+        self.total_deposits = 0;
+        self.total_withdraws = 0;
+        self.cumulative_profit_share_amount = 0;
+        self.profit_share_fee_paid = 0;
+        self.management_fee_paid = 0;
+        self.realized_pnl = 0;
+        self.unrealized_pnl = 0;
+        self.fuel_amount = 0;
+
+        msg!("Vault Transfer Position Record");
+        for (depositor_authority, vault_shares_before, vault_shares_after) in [
+            (self.authority, source_shares_before, self.vault_shares),
+            (
+                destination.authority,
+                destination_shares_before,
+                destination.vault_shares,
+            ),
+        ] {
+            let record = VaultDepositorRecord {
+                ts: now,
+                vault: vault.pubkey,
+                depositor_authority,
+                action: VaultDepositorAction::TransferPosition,
+                amount: 0,
+                spot_market_index: vault.spot_market_index,
+                vault_equity_before: vault_equity,
+                vault_shares_before,
+                user_vault_shares_before: vault.user_shares,
+                total_vault_shares_before: vault.total_shares,
+                vault_shares_after,
+                total_vault_shares_after: vault.total_shares,
+                user_vault_shares_after: vault.user_shares,
+                profit_share: vault.profit_share,
+                profit_share_amount: 0,
+                management_fee: vault.management_fee,
+                management_fee_amount: 0,
+            };
+
+            log_data(&record)?;
+
+            log_params(&record);
+        }
+
+        Ok(())
+    }
+
+    /// Moves unlocked shares out of the depositor's ledger so they can be minted as share tokens
+    ///
+    /// The lots are removed oldest first and the shares take their part of the depositor's net
+    /// deposits and the lots' cost basis with them, so profit share and pnl on the remaining
+    /// shares are unchanged.
+    ///
+    /// # Arguments
+    /// * `shares` - The shares to wrap
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<u64, ProgramError>` - The share token amount to mint
+    pub fn wrap_shares(
+        &mut self,
+        shares: u128,
+        vault: &mut Vault,
+        now: i64,
+    ) -> Result<u64, ProgramError> {
+        custom_validate!(
+            shares > 0,
+            VaultErrorCode::InsufficientVaultShares,
+            "no shares to wrap"
+        )?;
+
+        custom_validate!(
+            self.last_withdraw_request.shares == 0,
+            VaultErrorCode::VaultWithdrawRequestInProgress,
+            "cancel the withdraw request before wrapping shares"
+        )?;
+
+        let withdrawable_shares = self.calculate_withdrawable_shares(now, vault.lock_in_period)?;
+
+        custom_validate!(
+            shares <= withdrawable_shares,
+            VaultErrorCode::InsufficientVaultShares,
+            "only the {} shares past the lock-in period can be wrapped",
+            withdrawable_shares
+        )?;
+
+        let amount = shares.cast::<u64>().map_err(wrap_drift_error)?;
+
+        // The depositor keeps the fuel earned so far, the wrapped shares earn it from here on
+        self.update_fuel(vault)?;
+        vault.update_wrapped_fuel()?;
+
+        let vault_shares_before = self.vault_shares;
+
+        let net_deposits = (self.net_deposits as u128)
+            .safe_mul(shares)
+            .and_then(|net_deposits| net_deposits.safe_div(vault_shares_before))
+            .and_then(|net_deposits| net_deposits.cast::<u64>())
+            .map_err(wrap_drift_error)?;
+
+        let cost_basis = self.remove_shares(shares)?;
+
+        self.vault_shares = self.vault_shares.saturating_sub(shares);
+        self.net_deposits = self.net_deposits.saturating_sub(net_deposits);
+
+        vault.wrapped_shares = vault.wrapped_shares.saturating_add(shares);
+        vault.wrapped_net_deposits = vault.wrapped_net_deposits.saturating_add(net_deposits);
+        vault.wrapped_cost_basis = vault.wrapped_cost_basis.saturating_add(cost_basis);
+
+        msg!("Vault Wrap Shares Record");
+        self.log_ledger_record(
+            VaultDepositorAction::WrapShares,
+            amount,
+            vault_shares_before,
+            vault,
+            now,
+        )?;
+
+        Ok(amount)
+    }
+
+    /// Moves the lot of the deposit just made out of the depositor's ledger so it can be minted as
+    /// share tokens, for vaults with a share mint
+    ///
+    /// Lock-in only applies to the lots in the ledger, so the shares are wrapped at once with the
+    /// deposit's net deposits and cost basis.
+    ///
+    /// # Arguments
+    /// * `net_deposits` - The deposit amount after fees
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp, the deposit's
+    ///
+    /// # Returns
+    /// * `Result<u64, ProgramError>` - The share token amount to mint
+    pub fn wrap_deposit(
+        &mut self,
+        net_deposits: u64,
+        vault: &mut Vault,
+        now: i64,
+    ) -> Result<u64, ProgramError> {
+        custom_validate!(
+            self.deposits.last().map_or(false, |lot| lot.ts == now),
+            VaultErrorCode::InvalidVaultDeposit,
+            "no deposit at {} to wrap",
+            now
+        )?;
+
+        self.update_fuel(vault)?;
+        vault.update_wrapped_fuel()?;
+
+        let vault_shares_before = self.vault_shares;
+
+        let lot = self
+            .deposits
+            .pop()
+            .ok_or(VaultErrorCode::InvalidVaultDeposit)?;
+        let amount = lot.shares.cast::<u64>().map_err(wrap_drift_error)?;
+
+        self.vault_shares = self.vault_shares.saturating_sub(lot.shares);
+        self.net_deposits = self.net_deposits.saturating_sub(net_deposits);
+
+        vault.wrapped_shares = vault.wrapped_shares.saturating_add(lot.shares);
+        vault.wrapped_net_deposits = vault.wrapped_net_deposits.saturating_add(net_deposits);
+        vault.wrapped_cost_basis = vault.wrapped_cost_basis.saturating_add(lot.cost_basis);
+
+        msg!("Vault Wrap Shares Record");
+        self.log_ledger_record(
+            VaultDepositorAction::WrapShares,
+            amount,
+            vault_shares_before,
+            vault,
+            now,
+        )?;
+
+        Ok(amount)
+    }
+
+    /// Moves shares burned as share tokens back into the depositor's ledger
+    ///
+    /// The shares come back as a single unlocked lot, since only unlocked shares can be wrapped,
+    /// and bring their pro rata part of the net deposits, cost basis and fuel carried by all
+    /// wrapped shares.
+    ///
+    /// # Arguments
+    /// * `amount` - The share token amount burned
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
+    pub fn unwrap_shares(&mut self, amount: u64, vault: &mut Vault, now: i64) -> ProgramResult {
+        let shares = amount as u128;
+
+        custom_validate!(
+            shares > 0 && shares <= vault.wrapped_shares,
+            VaultErrorCode::InsufficientVaultShares,
+            "can unwrap at most {} shares",
+            vault.wrapped_shares
+        )?;
+
+        self.update_fuel(vault)?;
+        vault.update_wrapped_fuel()?;
+
+        let vault_shares_before = self.vault_shares;
+
+        // The unwrapped shares take their part of what the wrapped shares carry
+        let wrapped_shares = vault.wrapped_shares;
+        let pro_rata = |wrapped_amount: u64| -> Result<u64, ProgramError> {
+            (wrapped_amount as u128)
+                .safe_mul(shares)
+                .and_then(|amount| amount.safe_div(wrapped_shares))
+                .and_then(|amount| amount.cast::<u64>())
+                .map_err(wrap_drift_error)
+        };
+
+        let net_deposits = pro_rata(vault.wrapped_net_deposits)?;
+        let cost_basis = pro_rata(vault.wrapped_cost_basis)?;
+        let fuel = pro_rata(vault.wrapped_fuel)?;
+
+        let unlocked_ts = now.saturating_sub(vault.lock_in_period as i64);
+
+        self.vault_shares = self.vault_shares.saturating_add(shares);
+        self.net_deposits = self.net_deposits.saturating_add(net_deposits);
+        self.fuel_amount = self.fuel_amount.saturating_add(fuel);
+        self.deposits
+            .push(DepositInfo::new(unlocked_ts, shares, cost_basis));
+
+        vault.wrapped_shares = vault.wrapped_shares.saturating_sub(shares);
+        vault.wrapped_net_deposits = vault.wrapped_net_deposits.saturating_sub(net_deposits);
+        vault.wrapped_cost_basis = vault.wrapped_cost_basis.saturating_sub(cost_basis);
+        vault.wrapped_fuel = vault.wrapped_fuel.saturating_sub(fuel);
+
+        msg!("Vault Unwrap Shares Record");
+        self.log_ledger_record(
+            VaultDepositorAction::UnwrapShares,
+            amount,
+            vault_shares_before,
+            vault,
+            now,
+        )
+    }
+
+    /// Moves a locked lot out of the depositor's ledger so it can be minted as a receipt NFT
+    ///
+    /// The lot takes its part of the depositor's net deposits with it, so profit share on the
+    /// remaining shares is unchanged. Unlocked shares can be wrapped instead.
+    ///
+    /// # Arguments
+    /// * `deposit_ts` - The timestamp of the lot's deposit
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
+    ///
+    /// # Returns
+    /// * `Result<(DepositInfo, u64), ProgramError>` - The lot and the net deposits it carries
+    pub fn take_lot(
+        &mut self,
+        deposit_ts: i64,
+        vault: &mut Vault,
+        now: i64,
+    ) -> Result<(DepositInfo, u64), ProgramError> {
+        custom_validate!(
+            vault.lot_receipts_enabled,
+            VaultErrorCode::LotReceiptsDisabled,
+            "lot receipts are disabled for the vault"
+        )?;
+
+        custom_validate!(
+            self.last_withdraw_request.shares == 0,
+            VaultErrorCode::VaultWithdrawRequestInProgress,
+            "cancel the withdraw request before minting a lot receipt"
+        )?;
+
+        let index = self
+            .deposits
+            .iter()
+            .position(|deposit| deposit.ts == deposit_ts)
+            .ok_or(VaultErrorCode::InvalidLotReceipt)?;
+
+        let unlock_ts = deposit_ts.saturating_add(vault.lock_in_period as i64);
+
+        custom_validate!(
+            now < unlock_ts && self.deposits[index].shares > 0,
+            VaultErrorCode::InvalidLotReceipt,
+            "lot unlocked at {}, wrap its shares instead",
+            unlock_ts
+        )?;
+
+        self.update_fuel(vault)?;
+
+        let vault_shares_before = self.vault_shares;
+        let lot = self.deposits.remove(index);
+
+        let net_deposits = (self.net_deposits as u128)
+            .safe_mul(lot.shares)
+            .and_then(|net_deposits| net_deposits.safe_div(vault_shares_before))
+            .and_then(|net_deposits| net_deposits.cast::<u64>())
+            .map_err(wrap_drift_error)?;
+
+        self.vault_shares = self.vault_shares.saturating_sub(lot.shares);
+        self.net_deposits = self.net_deposits.saturating_sub(net_deposits);
+
+        vault.receipt_shares = vault.receipt_shares.saturating_add(lot.shares);
+
+        msg!("Vault Mint Lot Receipt Record");
+        self.log_ledger_record(
+            VaultDepositorAction::MintLotReceipt,
+            0,
+            vault_shares_before,
+            vault,
+            now,
+        )?;
+
+        Ok((lot, net_deposits))
+    }
+
+    /// Moves the lot of a burned receipt NFT into the depositor's ledger
+    ///
+    /// The lot keeps its deposit timestamp, so it unlocks when it would have for the depositor
+    /// who minted the receipt.
+    ///
+    /// # Arguments
+    /// * `lot_receipt` - The receipt of the lot
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
+    pub fn redeem_lot(
+        &mut self,
+        lot_receipt: &LotReceipt,
+        vault: &mut Vault,
+        now: i64,
+    ) -> ProgramResult {
+        custom_validate!(
+            lot_receipt.vault == self.vault && lot_receipt.shares <= vault.receipt_shares,
+            VaultErrorCode::InvalidLotReceipt,
+            "lot receipt {} is not of the vault",
+            lot_receipt.pubkey
+        )?;
+
+        self.update_fuel(vault)?;
+
+        let vault_shares_before = self.vault_shares;
+
+        self.vault_shares = self.vault_shares.saturating_add(lot_receipt.shares);
+        self.net_deposits = self.net_deposits.saturating_add(lot_receipt.net_deposits);
+        self.deposits.push(DepositInfo::new(
+            lot_receipt.ts,
+            lot_receipt.shares,
+            lot_receipt.cost_basis,
+        ));
+
+        vault.receipt_shares = vault.receipt_shares.saturating_sub(lot_receipt.shares);
+
+        msg!("Vault Redeem Lot Receipt Record");
+        self.log_ledger_record(
+            VaultDepositorAction::RedeemLotReceipt,
+            0,
+            vault_shares_before,
+            vault,
+            now,
+        )
+    }
+
+    /// Values the depositor's shares at vault_equity against the cost basis of its lots
+    ///
+    /// # Arguments
+    /// * `vault_equity` - The current vault equity
+    /// * `vault` - The vault the depositor belongs to
+    /// * `now` - Current Unix timestamp
+    pub fn update_unrealized_pnl(
+        &mut self,
+        vault_equity: u64,
+        vault: &Vault,
+        now: i64,
+    ) -> ProgramResult {
+        let value =
+            Self::calculate_shares_to_amount(self.vault_shares, vault.total_shares, vault_equity)?;
+
+        let cost_basis = self.deposits.iter().fold(0_u64, |cost_basis, deposit| {
+            cost_basis.saturating_add(deposit.cost_basis)
+        });
+
+        self.unrealized_pnl = (value as i64).saturating_sub(cost_basis as i64);
+        self.last_stats_ts = now;
+
+        msg!(
+            "value: {}, cost_basis: {}, unrealized_pnl: {}, realized_pnl: {}",
+            value,
+            cost_basis,
+            self.unrealized_pnl,
+            self.realized_pnl
+        );
+
+        Ok(())
+    }
+
+    fn log_ledger_record(
+        &self,
+        action: VaultDepositorAction,
+        amount: u64,
+        vault_shares_before: u128,
+        vault: &Vault,
+        now: i64,
+    ) -> ProgramResult {
+        let record = VaultDepositorRecord {
+            ts: now,
+            vault: vault.pubkey,
+            depositor_authority: self.authority,
+            action,
+            amount,
+            spot_market_index: vault.spot_market_index,
+            vault_equity_before: 0,
+            vault_shares_before,
+            user_vault_shares_before: vault.user_shares,
+            total_vault_shares_before: vault.total_shares,
+            vault_shares_after: self.vault_shares,
+            total_vault_shares_after: vault.total_shares,
+            user_vault_shares_after: vault.user_shares,
+            profit_share: vault.profit_share,
+            profit_share_amount: 0,
+            management_fee: vault.management_fee,
+            management_fee_amount: 0,
+        };
+
+        log_data(&record)?;
+
+        log_params(&record);
+
+        Ok(())
+    }
+
+    /// Calculates what withdrawing shares pays at the current vault equity
+    ///
+    /// # Returns
+    /// * `Result<(u64, u64, u64), ProgramError>` - The amount paid out after fees, the management
+    ///   fee and the profit share
+    pub fn calculate_withdraw(
+        &self,
+        shares: u128,
+        vault_equity: u64,
+        vault: &mut Vault,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let withdraw_amount = if_shares_to_vault_amount(shares, vault.total_shares, vault_equity)
+            .map_err(wrap_drift_error)?;
+
+        // Calculate fees and profit share
+        let management_fee = vault.calculate_fees(withdraw_amount);
+        let profit_share = self.calculate_profit_share(withdraw_amount, vault_equity, vault)?;
+
+        msg!("Management fee: {}", management_fee);
+        msg!("Profit share: {}", profit_share);
+
+        // Calculate total deductions and final amount
+        let total_deductions = management_fee.saturating_add(profit_share);
+
+        Ok((
+            withdraw_amount.saturating_sub(total_deductions),
+            management_fee,
+            profit_share,
+        ))
+    }
+
     pub fn calculate_profit_share(
         &self,
         amount: u64,
@@ -507,7 +1156,12 @@ impl VaultDepositor {
         Ok(withdrawable_shares)
     }
 
-    pub fn remove_shares(&mut self, shares_to_remove: u128) -> Result<(), ProgramError> {
+    /// Removes shares from the lots, oldest first. A lot partially removed keeps the part of its
+    /// cost basis of the shares left
+    ///
+    /// # Returns
+    /// * `Result<u64, ProgramError>` - The cost basis of the shares removed
+    pub fn remove_shares(&mut self, shares_to_remove: u128) -> Result<u64, ProgramError> {
         // Calculate and log total shares before removal
         let total_shares_before: u128 = self.deposits.iter().map(|d| d.shares).sum();
 
@@ -518,6 +1172,7 @@ impl VaultDepositor {
         );
 
         let mut remaining_shares = shares_to_remove;
+        let mut cost_basis_removed: u64 = 0;
 
         // Sort deposits by timestamp (oldest first)
         self.deposits.sort_by_key(|d| d.ts);
@@ -536,13 +1191,22 @@ impl VaultDepositor {
                 remaining_shares = remaining_shares
                     .checked_sub(deposit.shares)
                     .ok_or(VaultErrorCode::MathError)?;
+                cost_basis_removed = cost_basis_removed.saturating_add(deposit.cost_basis);
                 indices_to_remove.push(i);
             } else {
                 // Partial removal
+                let cost_basis = (deposit.cost_basis as u128)
+                    .safe_mul(remaining_shares)
+                    .and_then(|cost_basis| cost_basis.safe_div(deposit.shares))
+                    .and_then(|cost_basis| cost_basis.cast::<u64>())
+                    .map_err(wrap_drift_error)?;
+
                 deposit.shares = deposit
                     .shares
                     .checked_sub(remaining_shares)
                     .ok_or(VaultErrorCode::MathError)?;
+                deposit.cost_basis = deposit.cost_basis.saturating_sub(cost_basis);
+                cost_basis_removed = cost_basis_removed.saturating_add(cost_basis);
                 remaining_shares = 0;
             }
         }
@@ -567,7 +1231,7 @@ impl VaultDepositor {
             total_shares_before - total_shares_after
         );
 
-        Ok(())
+        Ok(cost_basis_removed)
     }
 }
 
@@ -577,12 +1241,621 @@ pub struct DepositInfo {
     pub ts: i64,
     /// Shares allocated at Deposit
     pub shares: u128,
+    /// Token amount paid for the shares, including the management fee
+    pub cost_basis: u64,
 }
 
 impl DepositInfo {
     pub const SIZE: usize = mem::size_of::<DepositInfo>();
 
-    pub fn new(ts: i64, shares: u128) -> Self {
-        Self { ts, shares }
+    pub fn new(ts: i64, shares: u128, cost_basis: u64) -> Self {
+        Self {
+            ts,
+            shares,
+            cost_basis,
+        }
+    }
+}
+
+/// The vault depositor layout before deposits had a cost basis
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct LegacyVaultDepositor {
+    pub vault: Pubkey,
+    pub pubkey: Pubkey,
+    pub authority: Pubkey,
+    pub vault_shares: u128,
+    pub last_withdraw_request: WithdrawRequest,
+    pub init_ts: u64,
+    pub net_deposits: u64,
+    pub total_deposits: u64,
+    pub deposits: Vec<LegacyDepositInfo>,
+    pub total_withdraws: u64,
+    pub cumulative_profit_share_amount: u64,
+    pub profit_share_fee_paid: u64,
+    pub vault_shares_base: u32,
+    pub padding1: u32,
+    pub padding: [u64; 8],
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct LegacyDepositInfo {
+    pub ts: i64,
+    pub shares: u128,
+}
+
+impl LegacyVaultDepositor {
+    pub fn get(data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(try_from_slice_unchecked::<LegacyVaultDepositor>(data)?)
+    }
+
+    /// Converts the legacy depositor to the current layout
+    ///
+    /// The amounts paid for legacy lots weren't recorded, so the depositor's net deposits are
+    /// split over its lots pro rata to their shares as their cost basis. Fuel is checkpointed at
+    /// the vault's current fuel per share, legacy depositors never accrued it
+    ///
+    /// # Arguments
+    /// * `vault` - The vault the depositor belongs to
+    pub fn into_vault_depositor(self, vault: &Vault) -> Result<VaultDepositor, ProgramError> {
+        let mut deposits = Vec::with_capacity(self.deposits.len());
+
+        for deposit in self.deposits {
+            let cost_basis = if self.vault_shares == 0 {
+                0
+            } else {
+                (self.net_deposits as u128)
+                    .safe_mul(deposit.shares.min(self.vault_shares))
+                    .and_then(|cost_basis| cost_basis.safe_div(self.vault_shares))
+                    .and_then(|cost_basis| cost_basis.cast::<u64>())
+                    .map_err(wrap_drift_error)?
+            };
+
+            deposits.push(DepositInfo::new(deposit.ts, deposit.shares, cost_basis));
+        }
+
+        Ok(VaultDepositor {
+            vault: self.vault,
+            pubkey: self.pubkey,
+            authority: self.authority,
+            vault_shares: self.vault_shares,
+            last_withdraw_request: self.last_withdraw_request,
+            init_ts: self.init_ts,
+            net_deposits: self.net_deposits,
+            total_deposits: self.total_deposits,
+            deposits,
+            total_withdraws: self.total_withdraws,
+            cumulative_profit_share_amount: self.cumulative_profit_share_amount,
+            profit_share_fee_paid: self.profit_share_fee_paid,
+            vault_shares_base: self.vault_shares_base,
+            version: VaultDepositor::VERSION,
+            fuel_per_share_checkpoint: vault.fuel_per_share,
+            ..VaultDepositor::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use drift::math::constants::QUOTE_PRECISION_U64;
+
+    fn create_vault(total_shares: u128) -> Vault {
+        Vault {
+            pubkey: Pubkey::new_unique(),
+            total_shares,
+            user_shares: total_shares,
+            ..Vault::default()
+        }
+    }
+
+    fn create_vault_depositor(vault: &Vault, vault_shares: u128) -> VaultDepositor {
+        VaultDepositor {
+            vault: vault.pubkey,
+            pubkey: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            vault_shares,
+            ..VaultDepositor::default()
+        }
+    }
+
+    /// Deposits the (ts, amount) lots at a share price of 1
+    fn deposit_lots(vault_depositor: &mut VaultDepositor, vault: &mut Vault, lots: &[(i64, u64)]) {
+        for &(ts, amount) in lots {
+            let vault_equity = vault.total_shares as u64;
+            vault_depositor
+                .deposit(amount, vault_equity, vault, ts)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    pub fn test_transfer_position() {
+        let vault = create_vault(300);
+        let vault_equity = 300 * QUOTE_PRECISION_U64;
+
+        let mut source = create_vault_depositor(&vault, 100);
+        source.net_deposits = 90;
+        source.total_deposits = 120;
+        source.total_withdraws = 30;
+        source.cumulative_profit_share_amount = 10;
+        source.profit_share_fee_paid = 2;
+        source.management_fee_paid = 3;
+        source.realized_pnl = 5;
+        source.unrealized_pnl = -4;
+        source.fuel_amount = 7;
+        source.deposits = vec![DepositInfo::new(1, 100, 95)];
+
+        let mut destination = create_vault_depositor(&vault, 50);
+        destination.net_deposits = 50;
+        destination.total_deposits = 50;
+        destination.management_fee_paid = 1;
+        destination.realized_pnl = -1;
+        destination.deposits = vec![DepositInfo::new(2, 50, 50)];
+
+        source
+            .transfer_position(&mut destination, &vault, vault_equity, 3)
+            .unwrap();
+
+        assert_eq!(destination.vault_shares, 150);
+        assert_eq!(destination.net_deposits, 140);
+        assert_eq!(destination.total_deposits, 170);
+        assert_eq!(destination.total_withdraws, 30);
+        assert_eq!(destination.cumulative_profit_share_amount, 10);
+        assert_eq!(destination.profit_share_fee_paid, 2);
+        assert_eq!(destination.management_fee_paid, 4);
+        assert_eq!(destination.realized_pnl, 4);
+        assert_eq!(destination.unrealized_pnl, -4);
+        assert_eq!(destination.fuel_amount, 7);
+        assert_eq!(destination.deposits.len(), 2);
+        assert_eq!(destination.deposits[0].shares, 100);
+        assert_eq!(destination.deposits[0].cost_basis, 95);
+
+        assert_eq!(source.vault_shares, 0);
+        assert_eq!(source.net_deposits, 0);
+        assert_eq!(source.total_deposits, 0);
+        assert_eq!(source.total_withdraws, 0);
+        assert_eq!(source.cumulative_profit_share_amount, 0);
+        assert_eq!(source.profit_share_fee_paid, 0);
+        assert_eq!(source.management_fee_paid, 0);
+        assert_eq!(source.realized_pnl, 0);
+        assert_eq!(source.unrealized_pnl, 0);
+        assert_eq!(source.fuel_amount, 0);
+        assert!(source.deposits.is_empty());
+    }
+
+    #[test]
+    pub fn test_transfer_position_merges_lots_oldest_first() {
+        let mut vault = create_vault(0);
+
+        let mut source = create_vault_depositor(&vault, 0);
+        let mut destination = create_vault_depositor(&vault, 0);
+
+        deposit_lots(&mut source, &mut vault, &[(1, 100), (3, 30)]);
+        deposit_lots(&mut destination, &mut vault, &[(2, 50), (4, 20)]);
+
+        let vault_equity = vault.total_shares as u64;
+        source
+            .transfer_position(&mut destination, &vault, vault_equity, 5)
+            .unwrap();
+
+        let lot_ts: Vec<i64> = destination.deposits.iter().map(|lot| lot.ts).collect();
+        assert_eq!(lot_ts, vec![1, 2, 3, 4]);
+
+        // The source's older lot is withdrawn before the destination's newer one
+        assert_eq!(destination.remove_shares(120).unwrap(), 120);
+        assert_eq!(destination.deposits[0].ts, 2);
+        assert_eq!(destination.deposits[0].shares, 30);
+        assert_eq!(destination.deposits[0].cost_basis, 30);
+    }
+
+    #[test]
+    pub fn test_transfer_position_destination_cap() {
+        let mut vault = create_vault(300);
+        let vault_equity = 300 * QUOTE_PRECISION_U64;
+
+        let mut source = create_vault_depositor(&vault, 100);
+        let mut destination = create_vault_depositor(&vault, 50);
+
+        // 150 shares are worth 150 after the transfer
+        vault.max_deposit_per_depositor = 149 * QUOTE_PRECISION_U64;
+        assert_eq!(
+            source.transfer_position(&mut destination, &vault, vault_equity, 0),
+            Err(VaultErrorCode::DepositorIsAtCapacity.into())
+        );
+        assert_eq!(source.vault_shares, 100);
+        assert_eq!(destination.vault_shares, 50);
+
+        // The destination's override takes precedence over the vault cap
+        destination.deposit_cap_override = Some(150 * QUOTE_PRECISION_U64);
+        source
+            .transfer_position(&mut destination, &vault, vault_equity, 0)
+            .unwrap();
+        assert_eq!(destination.vault_shares, 150);
+    }
+
+    #[test]
+    pub fn test_get_deposit_cap() {
+        let mut vault = create_vault(0);
+        vault.max_deposit_per_depositor = 100;
+
+        let mut vault_depositor = create_vault_depositor(&vault, 0);
+        assert_eq!(vault_depositor.get_deposit_cap(&vault), 100);
+
+        vault_depositor.deposit_cap_override = Some(200);
+        assert_eq!(vault_depositor.get_deposit_cap(&vault), 200);
+
+        // An override of 0 lifts the vault cap for the depositor
+        vault_depositor.deposit_cap_override = Some(0);
+        assert_eq!(vault_depositor.get_deposit_cap(&vault), 0);
+    }
+
+    #[test]
+    pub fn test_transfer_position_invalid() {
+        let vault = create_vault(300);
+        let vault_equity = 300 * QUOTE_PRECISION_U64;
+
+        let mut source = create_vault_depositor(&vault, 100);
+        let mut destination = create_vault_depositor(&vault, 50);
+
+        source.last_withdraw_request.shares = 10;
+        assert_eq!(
+            source.transfer_position(&mut destination, &vault, vault_equity, 0),
+            Err(VaultErrorCode::VaultWithdrawRequestInProgress.into())
+        );
+        source.last_withdraw_request.shares = 0;
+
+        let mut other_vault_depositor = create_vault_depositor(&create_vault(300), 50);
+        assert_eq!(
+            source.transfer_position(&mut other_vault_depositor, &vault, vault_equity, 0),
+            Err(VaultErrorCode::InvalidPositionTransfer.into())
+        );
+
+        let mut empty = create_vault_depositor(&vault, 0);
+        assert_eq!(
+            empty.transfer_position(&mut destination, &vault, vault_equity, 0),
+            Err(VaultErrorCode::InsufficientVaultShares.into())
+        );
+    }
+
+    #[test]
+    pub fn test_wrap_shares() {
+        let mut vault = create_vault(0);
+        vault.lock_in_period = 100;
+
+        let mut vault_depositor = create_vault_depositor(&vault, 0);
+        deposit_lots(&mut vault_depositor, &mut vault, &[(0, 60), (150, 40)]);
+
+        assert_eq!(vault_depositor.wrap_shares(50, &mut vault, 200), Ok(50));
+
+        assert_eq!(vault_depositor.vault_shares, 50);
+        assert_eq!(vault_depositor.net_deposits, 50);
+        assert_eq!(vault_depositor.deposits.len(), 2);
+        assert_eq!(vault_depositor.deposits[0].shares, 10);
+        assert_eq!(vault_depositor.deposits[0].cost_basis, 10);
+        assert_eq!(vault.wrapped_shares, 50);
+        assert_eq!(vault.wrapped_net_deposits, 50);
+        assert_eq!(vault.wrapped_cost_basis, 50);
+        // Wrapped shares stay in the vault's shares
+        assert_eq!(vault.user_shares, 100);
+        assert_eq!(vault.total_shares, 100);
+
+        // The second lot is still locked
+        assert_eq!(
+            vault_depositor.wrap_shares(20, &mut vault, 200),
+            Err(VaultErrorCode::InsufficientVaultShares.into())
+        );
+        assert_eq!(
+            vault_depositor.wrap_shares(0, &mut vault, 200),
+            Err(VaultErrorCode::InsufficientVaultShares.into())
+        );
+
+        vault_depositor.last_withdraw_request.shares = 10;
+        assert_eq!(
+            vault_depositor.wrap_shares(10, &mut vault, 200),
+            Err(VaultErrorCode::VaultWithdrawRequestInProgress.into())
+        );
+    }
+
+    #[test]
+    pub fn test_wrap_unwrap_round_trip() {
+        let mut vault = create_vault(0);
+        vault.lock_in_period = 100;
+
+        let mut vault_depositor = create_vault_depositor(&vault, 0);
+        deposit_lots(&mut vault_depositor, &mut vault, &[(0, 60), (50, 40)]);
+
+        // 1 fuel per share before wrapping
+        vault.update_fuel_per_share(100).unwrap();
+
+        assert_eq!(vault_depositor.wrap_shares(100, &mut vault, 200), Ok(100));
+        assert!(vault_depositor.deposits.is_empty());
+
+        // 2 fuel per share while wrapped, earned by the wrapped shares
+        vault.update_fuel_per_share(300).unwrap();
+        assert_eq!(vault_depositor.get_fuel(&vault), Ok(100));
+
+        vault_depositor.unwrap_shares(40, &mut vault, 300).unwrap();
+        vault_depositor.unwrap_shares(60, &mut vault, 300).unwrap();
+
+        assert_eq!(vault_depositor.vault_shares, 100);
+        assert_eq!(vault_depositor.net_deposits, 100);
+        assert_eq!(vault_depositor.get_fuel(&vault), Ok(300));
+        assert_eq!(
+            vault_depositor
+                .deposits
+                .iter()
+                .map(|deposit| deposit.cost_basis)
+                .sum::<u64>(),
+            100
+        );
+        assert_eq!(vault.wrapped_shares, 0);
+        assert_eq!(vault.wrapped_net_deposits, 0);
+        assert_eq!(vault.wrapped_cost_basis, 0);
+        assert_eq!(vault.wrapped_fuel, 0);
+
+        // The unwrapped shares come back unlocked
+        assert_eq!(
+            vault_depositor.calculate_withdrawable_shares(300, vault.lock_in_period),
+            Ok(100)
+        );
+
+        // The pnl realized on withdraw is against the cost basis paid before wrapping
+        let vault_equity = 150;
+        vault_depositor
+            .request_withdraw(150, vault_equity, 0, &mut vault, 300)
+            .unwrap();
+        vault_depositor
+            .withdraw(vault_equity, &mut vault, 300)
+            .unwrap();
+
+        assert_eq!(vault_depositor.vault_shares, 0);
+        assert_eq!(vault_depositor.realized_pnl, 50);
+    }
+
+    #[test]
+    pub fn test_unwrap_shares_invalid() {
+        let mut vault = create_vault(0);
+
+        let mut vault_depositor = create_vault_depositor(&vault, 0);
+        deposit_lots(&mut vault_depositor, &mut vault, &[(0, 50)]);
+        vault_depositor.wrap_shares(25, &mut vault, 0).unwrap();
+
+        assert_eq!(
+            vault_depositor.unwrap_shares(26, &mut vault, 0),
+            Err(VaultErrorCode::InsufficientVaultShares.into())
+        );
+        assert_eq!(
+            vault_depositor.unwrap_shares(0, &mut vault, 0),
+            Err(VaultErrorCode::InsufficientVaultShares.into())
+        );
+    }
+
+    #[test]
+    pub fn test_wrap_deposit_and_withdraw_share_tokens() {
+        let mut vault = create_vault(0);
+        vault.lock_in_period = 100;
+
+        let mut vault_depositor = create_vault_depositor(&vault, 0);
+        deposit_lots(&mut vault_depositor, &mut vault, &[(0, 60), (50, 40)]);
+
+        // The deposit's shares are minted right away, lock-in only applies to the ledger
+        assert_eq!(vault_depositor.wrap_deposit(40, &mut vault, 50), Ok(40));
+
+        assert_eq!(vault_depositor.vault_shares, 60);
+        assert_eq!(vault_depositor.net_deposits, 60);
+        assert_eq!(vault_depositor.deposits.len(), 1);
+        assert_eq!(vault.wrapped_shares, 40);
+        assert_eq!(vault.wrapped_net_deposits, 40);
+        assert_eq!(vault.wrapped_cost_basis, 40);
+
+        // Only a deposit made at the same time can be wrapped
+        assert_eq!(
+            vault_depositor.wrap_deposit(60, &mut vault, 60),
+            Err(VaultErrorCode::InvalidVaultDeposit.into())
+        );
+
+        // Share tokens can be requested on top of the unlocked ledger shares
+        let vault_equity = 100;
+        assert_eq!(
+            vault_depositor.request_withdraw(100, vault_equity, 40, &mut vault, 60),
+            Err(VaultErrorCode::InvalidVaultWithdrawSize.into())
+        );
+        vault_depositor
+            .request_withdraw(100, vault_equity, 40, &mut vault, 100)
+            .unwrap();
+        assert_eq!(vault_depositor.last_withdraw_request.shares, 100);
+
+        // The withdraw burns the requested shares the ledger doesn't hold and unwraps them
+        vault_depositor.unwrap_shares(40, &mut vault, 100).unwrap();
+        assert_eq!(
+            vault_depositor.withdraw(vault_equity, &mut vault, 100),
+            Ok((100, 0))
+        );
+
+        assert_eq!(vault_depositor.vault_shares, 0);
+        assert_eq!(vault_depositor.realized_pnl, 0);
+        assert!(vault_depositor.deposits.is_empty());
+        assert_eq!(vault.wrapped_shares, 0);
+        assert_eq!(vault.total_shares, 0);
+    }
+
+    #[test]
+    pub fn test_redeem_lot() {
+        let mut vault = create_vault(100);
+        vault.lock_in_period = 100;
+        vault.lot_receipts_enabled = true;
+
+        let mut source = create_vault_depositor(&vault, 100);
+        source.net_deposits = 100;
+        source.deposits = vec![DepositInfo::new(0, 60, 60), DepositInfo::new(150, 40, 50)];
+
+        let (lot, net_deposits) = source.take_lot(150, &mut vault, 200).unwrap();
+
+        assert_eq!(lot.shares, 40);
+        assert_eq!(net_deposits, 40);
+        assert_eq!(source.vault_shares, 60);
+        assert_eq!(source.net_deposits, 60);
+        assert_eq!(vault.receipt_shares, 40);
+
+        let lot_receipt = LotReceipt {
+            vault: vault.pubkey,
+            pubkey: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            shares: lot.shares,
+            ts: lot.ts,
+            unlock_ts: 250,
+            net_deposits,
+            cost_basis: lot.cost_basis,
+            bump: 0,
+        };
+
+        let mut destination = create_vault_depositor(&vault, 0);
+        destination
+            .redeem_lot(&lot_receipt, &mut vault, 200)
+            .unwrap();
+
+        assert_eq!(destination.vault_shares, 40);
+        assert_eq!(destination.net_deposits, 40);
+        assert_eq!(destination.deposits.len(), 1);
+        assert_eq!(destination.deposits[0].ts, 150);
+        assert_eq!(destination.deposits[0].cost_basis, 50);
+        assert_eq!(vault.receipt_shares, 0);
+        // Receipt shares never left the vault's shares
+        assert_eq!(vault.user_shares, 100);
+        assert_eq!(vault.total_shares, 100);
+
+        // The lot keeps its deposit timestamp, so the lock-in is not restarted
+        assert_eq!(
+            destination.calculate_withdrawable_shares(249, vault.lock_in_period),
+            Ok(0)
+        );
+        assert_eq!(
+            destination.calculate_withdrawable_shares(250, vault.lock_in_period),
+            Ok(40)
+        );
+    }
+
+    #[test]
+    pub fn test_redeem_lot_invalid() {
+        let mut vault = create_vault(100);
+        vault.receipt_shares = 40;
+
+        let mut vault_depositor = create_vault_depositor(&vault, 60);
+
+        let mut lot_receipt = LotReceipt {
+            vault: Pubkey::new_unique(),
+            pubkey: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            shares: 40,
+            ts: 0,
+            unlock_ts: 100,
+            net_deposits: 40,
+            cost_basis: 40,
+            bump: 0,
+        };
+
+        // Receipt of another vault
+        assert_eq!(
+            vault_depositor.redeem_lot(&lot_receipt, &mut vault, 0),
+            Err(VaultErrorCode::InvalidLotReceipt.into())
+        );
+
+        // More shares than the vault's receipts hold
+        lot_receipt.vault = vault.pubkey;
+        lot_receipt.shares = 41;
+        assert_eq!(
+            vault_depositor.redeem_lot(&lot_receipt, &mut vault, 0),
+            Err(VaultErrorCode::InvalidLotReceipt.into())
+        );
+
+        assert_eq!(vault_depositor.vault_shares, 60);
+        assert_eq!(vault.receipt_shares, 40);
+    }
+
+    #[test]
+    pub fn test_remove_shares_cost_basis() {
+        let vault = create_vault(100);
+
+        let mut vault_depositor = create_vault_depositor(&vault, 100);
+        vault_depositor.deposits = vec![
+            DepositInfo::new(200, 30, 45),
+            DepositInfo::new(100, 40, 40),
+            DepositInfo::new(300, 30, 100),
+        ];
+
+        // The oldest lot entirely and a third of the next one
+        assert_eq!(vault_depositor.remove_shares(50), Ok(55));
+
+        assert_eq!(vault_depositor.deposits.len(), 2);
+        assert_eq!(vault_depositor.deposits[0].ts, 200);
+        assert_eq!(vault_depositor.deposits[0].shares, 20);
+        assert_eq!(vault_depositor.deposits[0].cost_basis, 30);
+
+        // Rounds the cost basis removed down, the lot keeps the remainder
+        assert_eq!(vault_depositor.remove_shares(21), Ok(30 + 3));
+
+        assert_eq!(vault_depositor.deposits.len(), 1);
+        assert_eq!(vault_depositor.deposits[0].shares, 29);
+        assert_eq!(vault_depositor.deposits[0].cost_basis, 97);
+
+        assert_eq!(
+            vault_depositor.remove_shares(30),
+            Err(VaultErrorCode::InsufficientShares.into())
+        );
+    }
+
+    #[test]
+    pub fn test_migrate_legacy_vault_depositor() {
+        let mut vault = create_vault(200);
+        vault.fuel_per_share = 5;
+
+        let legacy_vault_depositor = LegacyVaultDepositor {
+            vault: vault.pubkey,
+            pubkey: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            vault_shares: 100,
+            last_withdraw_request: WithdrawRequest::default(),
+            init_ts: 1,
+            net_deposits: 90,
+            total_deposits: 120,
+            deposits: vec![
+                LegacyDepositInfo { ts: 0, shares: 60 },
+                LegacyDepositInfo { ts: 10, shares: 40 },
+            ],
+            total_withdraws: 30,
+            cumulative_profit_share_amount: 2,
+            profit_share_fee_paid: 1,
+            vault_shares_base: 0,
+            padding1: 0,
+            padding: [0; 8],
+        };
+
+        let mut data = legacy_vault_depositor.try_to_vec().unwrap();
+        data.resize(VaultDepositor::INITIAL_SIZE, 0);
+
+        assert!(VaultDepositor::is_legacy(&data));
+
+        let vault_depositor = LegacyVaultDepositor::get(&data)
+            .unwrap()
+            .into_vault_depositor(&vault)
+            .unwrap();
+
+        assert_eq!(vault_depositor.version, VaultDepositor::VERSION);
+        assert_eq!(vault_depositor.pubkey, legacy_vault_depositor.pubkey);
+        assert_eq!(vault_depositor.vault_shares, 100);
+        assert_eq!(vault_depositor.net_deposits, 90);
+        assert_eq!(vault_depositor.total_withdraws, 30);
+        assert_eq!(vault_depositor.deposit_cap_override, None);
+        assert_eq!(vault_depositor.fuel_per_share_checkpoint, 5);
+        // The net deposits are split over the lots pro rata to their shares
+        assert_eq!(vault_depositor.deposits.len(), 2);
+        assert_eq!(vault_depositor.deposits[0].shares, 60);
+        assert_eq!(vault_depositor.deposits[0].cost_basis, 54);
+        assert_eq!(vault_depositor.deposits[1].ts, 10);
+        assert_eq!(vault_depositor.deposits[1].cost_basis, 36);
+
+        let data = vault_depositor.try_to_vec().unwrap();
+
+        assert!(!VaultDepositor::is_legacy(&data));
     }
 }
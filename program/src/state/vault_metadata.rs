@@ -0,0 +1,132 @@
+use std::mem;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_program::account_info::AccountInfo;
+use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::msg;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::constants::{
+    MAX_VAULT_DESCRIPTION_LENGTH, MAX_VAULT_RISK_RATING, MAX_VAULT_SYMBOL_LENGTH, MAX_VAULT_TAGS,
+    MAX_VAULT_TAG_LENGTH, MAX_VAULT_URI_LENGTH, MAX_VAULT_WEBSITE_LENGTH,
+};
+use crate::custom_validate;
+use crate::error::VaultErrorCode;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct VaultMetadata {
+    /// The vault the metadata describes
+    pub vault: Pubkey,
+    /// The vault metadata's pubkey. It is a pda of vault
+    pub pubkey: Pubkey,
+    /// Short ticker for the vault, used as the Metaplex symbol once shares are tokenized
+    pub symbol: String,
+    /// Human readable description of the vault strategy
+    pub description: String,
+    /// Strategy tags, e.g. "market-making", "basis"
+    pub tags: Vec<String>,
+    /// The vault's website
+    pub website: String,
+    /// Risk rating from 1 (lowest) to MAX_VAULT_RISK_RATING, 0 if unrated
+    pub risk_rating: u8,
+    /// Off-chain JSON following the Metaplex token metadata standard
+    pub uri: String,
+    /// Last update unix timestamp
+    pub last_update_ts: i64,
+    /// The bump for the vault metadata pda
+    pub bump: u8,
+}
+
+impl VaultMetadata {
+    pub const SIZE: usize = mem::size_of::<VaultMetadata>()
+        + MAX_VAULT_SYMBOL_LENGTH
+        + MAX_VAULT_DESCRIPTION_LENGTH
+        + (mem::size_of::<u32>() + MAX_VAULT_TAG_LENGTH) * MAX_VAULT_TAGS
+        + MAX_VAULT_WEBSITE_LENGTH
+        + MAX_VAULT_URI_LENGTH
+        + 8;
+
+    pub fn get_vault_metadata_signer_seeds<'a>(vault: &'a [u8], bump: &'a [u8]) -> [&'a [u8]; 3] {
+        [b"vault_metadata", vault, bump]
+    }
+
+    pub fn get_pda<'a>(vault: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vault_metadata", vault.as_ref()], program_id)
+    }
+
+    pub fn get(account: &AccountInfo) -> Self {
+        try_from_slice_unchecked::<VaultMetadata>(&account.data.borrow()).unwrap()
+    }
+
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    pub fn set_params(
+        &mut self,
+        symbol: String,
+        description: String,
+        tags: Vec<String>,
+        website: String,
+        risk_rating: u8,
+        uri: String,
+        now: i64,
+    ) -> ProgramResult {
+        custom_validate!(
+            symbol.len() <= MAX_VAULT_SYMBOL_LENGTH,
+            VaultErrorCode::InvalidVaultMetadata,
+            "symbol must be at most {} bytes",
+            MAX_VAULT_SYMBOL_LENGTH
+        )?;
+
+        custom_validate!(
+            description.len() <= MAX_VAULT_DESCRIPTION_LENGTH,
+            VaultErrorCode::InvalidVaultMetadata,
+            "description must be at most {} bytes",
+            MAX_VAULT_DESCRIPTION_LENGTH
+        )?;
+
+        custom_validate!(
+            tags.len() <= MAX_VAULT_TAGS
+                && tags.iter().all(|tag| tag.len() <= MAX_VAULT_TAG_LENGTH),
+            VaultErrorCode::InvalidVaultMetadata,
+            "at most {} tags of at most {} bytes",
+            MAX_VAULT_TAGS,
+            MAX_VAULT_TAG_LENGTH
+        )?;
+
+        custom_validate!(
+            website.len() <= MAX_VAULT_WEBSITE_LENGTH,
+            VaultErrorCode::InvalidVaultMetadata,
+            "website must be at most {} bytes",
+            MAX_VAULT_WEBSITE_LENGTH
+        )?;
+
+        custom_validate!(
+            risk_rating <= MAX_VAULT_RISK_RATING,
+            VaultErrorCode::InvalidVaultMetadata,
+            "risk rating must be <= {}",
+            MAX_VAULT_RISK_RATING
+        )?;
+
+        custom_validate!(
+            uri.len() <= MAX_VAULT_URI_LENGTH,
+            VaultErrorCode::InvalidVaultMetadata,
+            "uri must be at most {} bytes",
+            MAX_VAULT_URI_LENGTH
+        )?;
+
+        self.symbol = symbol;
+        self.description = description;
+        self.tags = tags;
+        self.website = website;
+        self.risk_rating = risk_rating;
+        self.uri = uri;
+        self.last_update_ts = now;
+
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use solana_program::sysvar::slot_history::ProgramError;
 
 use super::Vault;
 
-#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Default)]
 pub struct WithdrawRequest {
     /// request shares of vault withdraw
     pub shares: u128,
@@ -69,7 +69,7 @@ impl WithdrawRequest {
         let time_since_withdraw_request = now.saturating_sub(self.ts);
 
         custom_validate!(
-            time_since_withdraw_request >= vault.redeem_period as i64,
+            time_since_withdraw_request >= vault.get_redeem_period() as i64,
             VaultErrorCode::CannotWithdrawBeforeRedeemPeriodEnd
         )?;
 